@@ -0,0 +1,319 @@
+use pulldown_cmark::{Event, Parser};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::images::is_image_file;
+use super::links::{is_external, normalize_path, parse_links, percent_decode, LinkIndex, LinkKind};
+use super::markdown::{markdown_files, parser_options, workspace_walker, LineIndex};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingAsset {
+    /// Document with the reference
+    pub source: String,
+    pub target: String,
+    pub line: usize,
+    pub column: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrphanedAsset {
+    pub path: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetReport {
+    pub missing: Vec<MissingAsset>,
+    pub orphaned: Vec<OrphanedAsset>,
+    /// Asset files referenced by at least one document
+    pub referenced: usize,
+    /// Bytes freed by removing every orphaned asset
+    pub reclaimable_bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetCleanup {
+    pub trashed: Vec<String>,
+    /// Paths left alone because they are referenced again, outside the
+    /// workspace or could not be moved to the trash
+    pub skipped: Vec<String>,
+    pub reclaimed_bytes: u64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AssetError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Path not found: {0}")]
+    NotFound(String),
+    #[error("Asset task failed: {0}")]
+    Task(String),
+}
+
+impl Serialize for AssetError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+fn img_src_regex() -> &'static Regex {
+    static IMG_SRC: OnceLock<Regex> = OnceLock::new();
+    IMG_SRC.get_or_init(|| Regex::new(r#"(?i)<img\b[^>]*?\bsrc\s*=\s*["']([^"']+)["']"#).unwrap())
+}
+
+/// `src` of `<img>` tags in raw HTML, with their byte offsets
+pub(crate) fn html_image_sources(content: &str) -> Vec<(String, usize)> {
+    let mut sources = Vec::new();
+
+    for (event, range) in Parser::new_ext(content, parser_options()).into_offset_iter() {
+        if let Event::Html(html) | Event::InlineHtml(html) = event {
+            for captures in img_src_regex().captures_iter(&html) {
+                let src = captures.get(1).unwrap();
+                sources.push((src.as_str().to_string(), range.start + src.start()));
+            }
+        }
+    }
+
+    sources
+}
+
+/// Resolve an HTML `src` the way a browser would from the document
+fn resolve_src(root: &Path, source: &Path, src: &str) -> PathBuf {
+    let src = percent_decode(src.split(['?', '#']).next().unwrap_or(src));
+    let path = match src.strip_prefix('/') {
+        Some(rest) => root.join(rest),
+        None => source.parent().unwrap_or(root).join(src),
+    };
+    normalize_path(&path)
+}
+
+fn audit(root: &Path) -> AssetReport {
+    let index = LinkIndex::file_names(root);
+    let assets: Vec<PathBuf> = workspace_walker(root)
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| normalize_path(entry.path()))
+        .filter(|path| is_image_file(path))
+        .collect();
+
+    // Wiki embeds like `![[diagram.png]]` find attachments anywhere in the
+    // workspace by name
+    let mut by_name: HashMap<String, Vec<&PathBuf>> = HashMap::new();
+    for path in &assets {
+        if let Some(name) = path.file_name() {
+            by_name.entry(name.to_string_lossy().to_lowercase()).or_default().push(path);
+        }
+    }
+    let find_attachment = |target: &str| {
+        let name = target.rsplit('/').next()?.to_lowercase();
+        by_name.get(&name)?.iter().min_by_key(|path| path.components().count()).map(|path| path.to_path_buf())
+    };
+
+    let mut referenced: HashSet<PathBuf> = HashSet::new();
+    let mut missing = Vec::new();
+
+    for source in markdown_files(root) {
+        let Ok(content) = fs::read_to_string(&source) else {
+            continue;
+        };
+
+        for link in parse_links(&content) {
+            let resolved = index.resolve(&link, Some(&source)).or_else(|| match link.kind {
+                LinkKind::Wiki => find_attachment(&link.target),
+                LinkKind::Markdown => None,
+            });
+            match resolved {
+                Some(target) => {
+                    referenced.insert(target);
+                }
+                None if is_image_file(Path::new(&link.target)) => missing.push(MissingAsset {
+                    source: source.display().to_string(),
+                    target: link.target,
+                    line: link.line,
+                    column: link.column,
+                    start: link.start,
+                    end: link.end,
+                }),
+                None => {}
+            }
+        }
+
+        let lines = LineIndex::new(&content);
+        for (src, offset) in html_image_sources(&content) {
+            if is_external(&src) || src.starts_with("data:") {
+                continue;
+            }
+            let target = resolve_src(root, &source, &src);
+            if target.exists() {
+                referenced.insert(target);
+            } else {
+                let line = lines.line(offset);
+                missing.push(MissingAsset {
+                    source: source.display().to_string(),
+                    line,
+                    column: content[lines.line_start(line)..offset].chars().count() + 1,
+                    start: offset,
+                    end: offset + src.len(),
+                    target: src,
+                });
+            }
+        }
+    }
+
+    let mut orphaned: Vec<OrphanedAsset> = assets
+        .iter()
+        .filter(|path| !referenced.contains(*path))
+        .map(|path| OrphanedAsset {
+            path: path.display().to_string(),
+            size: fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+        })
+        .collect();
+    orphaned.sort_by(|a, b| a.path.cmp(&b.path));
+
+    AssetReport {
+        reclaimable_bytes: orphaned.iter().map(|asset| asset.size).sum(),
+        referenced: referenced.iter().filter(|path| is_image_file(path)).count(),
+        missing,
+        orphaned,
+    }
+}
+
+/// Trash the given paths that are still orphaned in a fresh audit
+fn trash_orphans(
+    root: &Path,
+    paths: Vec<String>,
+    delete: impl Fn(&Path) -> Result<(), String>,
+) -> AssetCleanup {
+    let report = audit(root);
+    let orphaned: HashSet<&str> = report.orphaned.iter().map(|asset| asset.path.as_str()).collect();
+
+    let mut cleanup = AssetCleanup {
+        trashed: Vec::new(),
+        skipped: Vec::new(),
+        reclaimed_bytes: 0,
+    };
+
+    for path in paths {
+        let normalized = normalize_path(Path::new(&path)).display().to_string();
+        if !orphaned.contains(normalized.as_str()) {
+            cleanup.skipped.push(path);
+            continue;
+        }
+
+        let size = fs::metadata(&normalized).map(|m| m.len()).unwrap_or(0);
+        match delete(Path::new(&normalized)) {
+            Ok(()) => {
+                cleanup.reclaimed_bytes += size;
+                cleanup.trashed.push(path);
+            }
+            Err(e) => {
+                log::warn!("Failed to move {} to the trash: {}", normalized, e);
+                cleanup.skipped.push(path);
+            }
+        }
+    }
+
+    cleanup
+}
+
+/// List images referenced by a workspace's documents but missing on disk,
+/// and image files no document references
+#[tauri::command]
+pub async fn audit_assets(workspace: String) -> Result<AssetReport, AssetError> {
+    let root = normalize_path(Path::new(&workspace));
+
+    if !root.is_dir() {
+        return Err(AssetError::NotFound(workspace));
+    }
+
+    tauri::async_runtime::spawn_blocking(move || audit(&root))
+        .await
+        .map_err(|e| AssetError::Task(e.to_string()))
+}
+
+/// Move orphaned assets to the trash. The workspace is audited again first,
+/// so anything referenced since the report was made is kept.
+#[tauri::command]
+pub async fn trash_orphaned_assets(workspace: String, paths: Vec<String>) -> Result<AssetCleanup, AssetError> {
+    let root = normalize_path(Path::new(&workspace));
+
+    if !root.is_dir() {
+        return Err(AssetError::NotFound(workspace));
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        trash_orphans(&root, paths, |path| trash::delete(path).map_err(|e| e.to_string()))
+    })
+    .await
+    .map_err(|e| AssetError::Task(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    #[test]
+    fn finds_orphans_and_trashes_only_unreferenced_ones() {
+        let dir = std::env::temp_dir().join(format!("ourea-assets-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let root = dir.join("workspace");
+        fs::create_dir_all(root.join("assets")).unwrap();
+        fs::create_dir_all(root.join("notes")).unwrap();
+
+        let write = |name: &str, size: usize| {
+            let path = root.join(name);
+            fs::write(&path, vec![0; size]).unwrap();
+            path.display().to_string()
+        };
+        write("assets/linked.png", 10);
+        write("assets/embedded.png", 20);
+        write("notes/inline.jpg", 30);
+        let orphan = write("assets/orphan.png", 40);
+        let later = write("assets/later.gif", 50);
+        fs::write(dir.join("outside.png"), [0; 60]).unwrap();
+        fs::write(
+            root.join("notes/note.md"),
+            "![a](../assets/linked.png)\n\n![[embedded.png]]\n\n<img src=\"inline.jpg\">\n\n![gone](missing.png)\n",
+        )
+        .unwrap();
+
+        let report = audit(&root);
+        let orphaned: Vec<&str> = report.orphaned.iter().map(|asset| asset.path.as_str()).collect();
+        assert_eq!(orphaned, [later.as_str(), orphan.as_str()]);
+        assert_eq!((report.referenced, report.reclaimable_bytes), (3, 90));
+        assert_eq!(report.missing.len(), 1);
+        assert_eq!((report.missing[0].target.as_str(), report.missing[0].line), ("missing.png", 7));
+
+        // Referenced after the report was made
+        fs::write(root.join("later.md"), "![](assets/later.gif)\n").unwrap();
+
+        let deleted = RefCell::new(Vec::new());
+        let outside = dir.join("outside.png").display().to_string();
+        let cleanup = trash_orphans(&root, vec![orphan.clone(), later.clone(), outside.clone()], |path| {
+            deleted.borrow_mut().push(path.to_path_buf());
+            Ok(())
+        });
+
+        assert_eq!(cleanup.trashed, [orphan.as_str()]);
+        assert_eq!(cleanup.skipped, [later, outside]);
+        assert_eq!(cleanup.reclaimed_bytes, 40);
+        assert_eq!(deleted.into_inner(), [PathBuf::from(&orphan)]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
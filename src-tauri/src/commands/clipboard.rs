@@ -0,0 +1,324 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::{ImageFormat, RgbaImage};
+use scraper::{Html, Selector};
+use serde::Serialize;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use super::images::{
+    document_image_settings, import_file, is_image_file, store_image, unsaved_images_dir, ImageError, ImportedImage,
+};
+use super::links::percent_decode;
+use super::localize::{build_agent, download, is_remote, LocalizeOptions};
+use super::settings::AppSettings;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardImage {
+    #[serde(flatten)]
+    pub image: ImportedImage,
+    pub alt: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardImport {
+    pub images: Vec<ClipboardImage>,
+    /// Image references ready to insert, one paragraph per image
+    pub markdown: String,
+    /// Files or image sources on the clipboard that could not be imported
+    pub skipped: Vec<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClipboardError {
+    #[error("Clipboard error: {0}")]
+    Clipboard(String),
+    #[error("The clipboard holds no images")]
+    NoImage,
+    #[error("{0}")]
+    Image(#[from] ImageError),
+    #[error("Paste task failed: {0}")]
+    Task(String),
+}
+
+impl Serialize for ClipboardError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// What the clipboard held, read in one go
+#[derive(Default)]
+struct ClipboardContents {
+    files: Vec<PathBuf>,
+    html: Option<String>,
+    /// RGBA pixels with width and height
+    image: Option<(usize, usize, Vec<u8>)>,
+}
+
+fn read_clipboard() -> Result<ClipboardContents, ClipboardError> {
+    let mut clipboard = arboard::Clipboard::new().map_err(|e| ClipboardError::Clipboard(e.to_string()))?;
+
+    // Each format is optional, so a missing one is not an error
+    Ok(ClipboardContents {
+        files: clipboard.get().file_list().unwrap_or_default(),
+        html: clipboard.get().html().ok(),
+        image: clipboard
+            .get_image()
+            .ok()
+            .map(|image| (image.width, image.height, image.bytes.into_owned())),
+    })
+}
+
+/// Bytes of a `data:` URL, with the subtype as a file extension
+fn decode_data_url(url: &str) -> Option<(Vec<u8>, String)> {
+    let (meta, data) = url.strip_prefix("data:")?.split_once(',')?;
+    let mime = meta.split(';').next().unwrap_or_default();
+    let extension = mime.strip_prefix("image/")?.trim_end_matches("+xml").to_string();
+
+    let bytes = if meta.ends_with(";base64") {
+        STANDARD.decode(data.trim()).ok()?
+    } else {
+        percent_decode(data).into_bytes()
+    };
+    Some((bytes, extension))
+}
+
+/// Import the `<img>` sources of copied HTML: inline data, local files and
+/// remote images
+fn import_html(
+    html: &str,
+    doc_path: Option<&Path>,
+    settings: &AppSettings,
+    unsaved_dir: &Path,
+    import: &mut ClipboardImport,
+) {
+    let document = Html::parse_fragment(html);
+    let selector = Selector::parse("img[src]").unwrap();
+    let mut agent = None;
+
+    for element in document.select(&selector) {
+        let src = element.value().attr("src").unwrap_or_default().trim();
+        let alt = element.value().attr("alt").unwrap_or_default().to_string();
+
+        let imported = if let Some((bytes, extension)) = decode_data_url(src) {
+            let name = format!("image.{}", extension);
+            store_image(doc_path, &bytes, Some(&name), settings, unsaved_dir).map_err(|e| e.to_string())
+        } else if is_remote(src) {
+            let agent = match &agent {
+                Some(agent) => agent,
+                None => match build_agent(settings, &LocalizeOptions::default()) {
+                    Ok(built) => agent.insert(built),
+                    Err(e) => {
+                        log::warn!("Failed to set up downloads: {}", e);
+                        import.skipped.push(src.to_string());
+                        continue;
+                    }
+                },
+            };
+            let name = src.split(['?', '#']).next().and_then(|path| path.rsplit('/').next());
+            download(agent, src).and_then(|bytes| {
+                store_image(doc_path, &bytes, name.map(percent_decode).as_deref(), settings, unsaved_dir)
+                    .map_err(|e| e.to_string())
+            })
+        } else {
+            let path = percent_decode(src.strip_prefix("file://").unwrap_or(src));
+            import_file(doc_path, Path::new(&path), None, settings, unsaved_dir).map_err(|e| e.to_string())
+        };
+
+        match imported {
+            Ok(image) => import.images.push(ClipboardImage { image, alt }),
+            Err(e) => {
+                log::warn!("Failed to import clipboard image {}: {}", src, e);
+                import.skipped.push(src.to_string());
+            }
+        }
+    }
+}
+
+/// Import what was on the clipboard, preferring copied files, then images
+/// in copied HTML, then the bitmap
+fn import_contents(
+    contents: ClipboardContents,
+    doc_path: Option<&Path>,
+    settings: &AppSettings,
+    unsaved_dir: &Path,
+) -> Result<ClipboardImport, ClipboardError> {
+    let mut import = ClipboardImport {
+        images: Vec::new(),
+        markdown: String::new(),
+        skipped: Vec::new(),
+    };
+
+    for file in &contents.files {
+        if !is_image_file(file) {
+            import.skipped.push(file.display().to_string());
+            continue;
+        }
+        match import_file(doc_path, file, None, settings, unsaved_dir) {
+            Ok(image) => {
+                let alt = file.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+                import.images.push(ClipboardImage { image, alt });
+            }
+            Err(e) => {
+                log::warn!("Failed to import clipboard file {}: {}", file.display(), e);
+                import.skipped.push(file.display().to_string());
+            }
+        }
+    }
+
+    if import.images.is_empty() {
+        if let Some(html) = &contents.html {
+            import_html(html, doc_path, settings, unsaved_dir, &mut import);
+        }
+    }
+
+    // A copied image usually comes with HTML pointing at the original,
+    // which is only used when it could be fetched
+    if import.images.is_empty() {
+        if let Some((width, height, pixels)) = contents.image {
+            let image = RgbaImage::from_raw(width as u32, height as u32, pixels)
+                .ok_or_else(|| ClipboardError::Clipboard("Invalid image data".to_string()))?;
+            let mut png = Vec::new();
+            image
+                .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+                .map_err(|e| ClipboardError::Clipboard(e.to_string()))?;
+
+            let image = store_image(doc_path, &png, Some("image.png"), settings, unsaved_dir)?;
+            import.images.push(ClipboardImage {
+                image,
+                alt: String::new(),
+            });
+        }
+    }
+
+    if import.images.is_empty() {
+        return Err(ClipboardError::NoImage);
+    }
+
+    import.markdown = import
+        .images
+        .iter()
+        .map(|image| format!("![{}]({})", image.alt.replace(['[', ']'], ""), image.image.markdown_path))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    Ok(import)
+}
+
+/// Read images straight from the system clipboard and import them for a
+/// document: copied image files, images in copied HTML, or a bitmap such
+/// as a screenshot. Errors with `NoImage` when there is nothing to paste,
+/// so the editor can fall back to its own paste handling.
+#[tauri::command]
+pub async fn paste_clipboard_images(
+    app: tauri::AppHandle,
+    doc_path: Option<String>,
+) -> Result<ClipboardImport, ClipboardError> {
+    let settings = document_image_settings(&app, doc_path.as_deref().map(Path::new))?;
+    let unsaved_dir = unsaved_images_dir(&app)?;
+
+    // Downloads, PNG encoding and optimization would stall the async runtime
+    tauri::async_runtime::spawn_blocking(move || {
+        let contents = read_clipboard()?;
+        import_contents(contents, doc_path.as_deref().map(Path::new), &settings, &unsaved_dir)
+    })
+    .await
+    .map_err(|e| ClipboardError::Task(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\nclipboard";
+
+    struct Folder(PathBuf);
+
+    impl Folder {
+        fn new(name: &str) -> Self {
+            let root = std::env::temp_dir().join(format!("ourea-clipboard-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&root);
+            fs::create_dir_all(&root).unwrap();
+            Self(root)
+        }
+
+        fn import(&self, contents: ClipboardContents) -> Result<ClipboardImport, ClipboardError> {
+            let settings = AppSettings {
+                image_naming_rule: "original".to_string(),
+                ..AppSettings::default()
+            };
+            import_contents(contents, Some(&self.0.join("note.md")), &settings, &self.0.join("unsaved"))
+        }
+    }
+
+    impl Drop for Folder {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn bitmap() -> Option<(usize, usize, Vec<u8>)> {
+        Some((1, 1, vec![255, 0, 0, 255]))
+    }
+
+    #[test]
+    fn decodes_image_data_urls() {
+        let base64 = format!("data:image/png;base64,{}", STANDARD.encode(PNG));
+        assert_eq!(decode_data_url(&base64), Some((PNG.to_vec(), "png".to_string())));
+
+        let svg = "data:image/svg+xml;charset=utf-8,%3Csvg%20xmlns%3D%22x%22%2F%3E";
+        assert_eq!(decode_data_url(svg), Some((b"<svg xmlns=\"x\"/>".to_vec(), "svg".to_string())));
+
+        assert_eq!(decode_data_url("data:text/plain;base64,aGk="), None);
+        assert_eq!(decode_data_url("data:image/png;base64,!!!"), None);
+        assert_eq!(decode_data_url("https://example.com/a.png"), None);
+    }
+
+    #[test]
+    fn prefers_files_then_html_then_the_bitmap() {
+        let folder = Folder::new("order");
+        let copied = folder.0.join("copied.png");
+        fs::write(&copied, PNG).unwrap();
+        let inline = STANDARD.encode(b"\x89PNG\r\n\x1a\nhtml");
+        let html = format!("<img alt=\"Inline [1]\" src=\"data:image/png;base64,{}\">", inline);
+
+        let files = folder
+            .import(ClipboardContents {
+                files: vec![copied.clone(), folder.0.join("notes.txt")],
+                html: Some(html.clone()),
+                image: bitmap(),
+            })
+            .unwrap();
+        assert_eq!(files.markdown, "![copied](./copied.png)");
+        assert_eq!(files.skipped, [folder.0.join("notes.txt").display().to_string()]);
+
+        let from_html = folder
+            .import(ClipboardContents {
+                html: Some(format!("{}<img src=\"gone.png\">", html)),
+                image: bitmap(),
+                ..ClipboardContents::default()
+            })
+            .unwrap();
+        assert_eq!(from_html.markdown, "![Inline 1](./image.png)");
+        assert_eq!(from_html.skipped, ["gone.png"]);
+
+        let from_bitmap = folder
+            .import(ClipboardContents {
+                html: Some("<img src=\"file:///nowhere/gone.png\">".to_string()),
+                image: bitmap(),
+                ..ClipboardContents::default()
+            })
+            .unwrap();
+        assert_eq!(from_bitmap.images.len(), 1);
+        assert!(from_bitmap.images[0].image.path.ends_with(".png"));
+        assert_eq!(from_bitmap.skipped, ["file:///nowhere/gone.png"]);
+
+        assert!(matches!(folder.import(ClipboardContents::default()), Err(ClipboardError::NoImage)));
+    }
+}
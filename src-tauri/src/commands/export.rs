@@ -0,0 +1,908 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use pulldown_cmark::{html, Alignment, CodeBlockKind, CowStr, Event, HeadingLevel, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use super::links::percent_decode;
+use super::markdown::{parser_options, Slugger};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatexExportOptions {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    /// Path to a file whose content replaces the default preamble
+    pub preamble_path: Option<String>,
+    /// Path to a `.bib` file used for `[@key]` citations
+    pub bibliography: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlideExportOptions {
+    pub title: Option<String>,
+    /// "light" or "dark"
+    pub theme: Option<String>,
+    /// Also start a new slide at every H1/H2 (defaults to true)
+    pub split_on_headings: Option<bool>,
+    /// Path of the source document, used to resolve and embed relative images
+    pub document_path: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("File not found: {0}")]
+    NotFound(String),
+}
+
+impl Serialize for ExportError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+const DEFAULT_PREAMBLE: &str = r"\documentclass{article}
+\usepackage[utf8]{inputenc}
+\usepackage[T1]{fontenc}
+\usepackage{amsmath}
+\usepackage{amssymb}
+\usepackage{graphicx}
+\usepackage{booktabs}
+\usepackage{listings}
+\usepackage[normalem]{ulem}
+\usepackage{hyperref}
+\lstset{basicstyle=\ttfamily\small,breaklines=true,frame=single}
+";
+
+/// Escape LaTeX special characters in plain text
+fn escape_latex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str(r"\textbackslash{}"),
+            '{' | '}' | '$' | '&' | '#' | '_' | '%' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '^' => escaped.push_str(r"\textasciicircum{}"),
+            '~' => escaped.push_str(r"\textasciitilde{}"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Map a fenced code block language to a `listings` language name
+fn listings_language(lang: &str) -> Option<&'static str> {
+    match lang.to_lowercase().as_str() {
+        "c" => Some("C"),
+        "cpp" | "c++" | "cc" | "hpp" => Some("C++"),
+        "java" => Some("Java"),
+        "python" | "py" => Some("Python"),
+        "ruby" | "rb" => Some("Ruby"),
+        "php" => Some("PHP"),
+        "perl" | "pl" => Some("Perl"),
+        "sql" => Some("SQL"),
+        "bash" | "sh" | "shell" | "zsh" => Some("bash"),
+        "html" | "htm" => Some("HTML"),
+        "xml" | "svg" => Some("XML"),
+        "haskell" | "hs" => Some("Haskell"),
+        "tex" | "latex" => Some("TeX"),
+        "matlab" => Some("Matlab"),
+        "r" => Some("R"),
+        _ => None,
+    }
+}
+
+/// Convert Pandoc-style `[@key]` / `[@a; @b]` citations in a text run to
+/// `\cite{...}`, escaping everything else
+fn convert_citations(text: &str, has_citations: &mut bool) -> String {
+    let mut result = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("[@") {
+        let Some(len) = rest[start..].find(']') else {
+            break;
+        };
+
+        let inner = &rest[start + 1..start + len];
+        let keys: Option<Vec<&str>> = inner
+            .split(';')
+            .map(|part| {
+                let key = part.trim().strip_prefix('@')?;
+                let valid = !key.is_empty()
+                    && key
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | ':' | '.'));
+                valid.then_some(key)
+            })
+            .collect();
+
+        result.push_str(&escape_latex(&rest[..start]));
+        match keys {
+            Some(keys) => {
+                result.push_str(&format!(r"\cite{{{}}}", keys.join(",")));
+                *has_citations = true;
+            }
+            None => result.push_str(&escape_latex(&rest[start..start + len + 1])),
+        }
+        rest = &rest[start + len + 1..];
+    }
+
+    result.push_str(&escape_latex(rest));
+    result
+}
+
+/// Streaming converter from pulldown-cmark events to a LaTeX body
+struct LatexWriter {
+    /// Output buffers; nested captures (captions, headings, footnotes) push a new one
+    buffers: Vec<String>,
+    /// Consecutive text events are merged so citations split across events still match
+    pending_text: String,
+    footnotes: HashMap<String, String>,
+    footnote_name: Option<String>,
+    heading_text: String,
+    heading_id: Option<String>,
+    slugger: Slugger,
+    in_heading: bool,
+    in_code_block: bool,
+    in_metadata: bool,
+    in_figure: bool,
+    image_url: String,
+    list_depth: usize,
+    cell_index: usize,
+    has_citations: bool,
+}
+
+impl LatexWriter {
+    fn new() -> Self {
+        Self {
+            buffers: vec![String::new()],
+            pending_text: String::new(),
+            footnotes: HashMap::new(),
+            footnote_name: None,
+            heading_text: String::new(),
+            heading_id: None,
+            slugger: Slugger::default(),
+            in_heading: false,
+            in_code_block: false,
+            in_metadata: false,
+            in_figure: false,
+            image_url: String::new(),
+            list_depth: 0,
+            cell_index: 0,
+            has_citations: false,
+        }
+    }
+
+    fn write(&mut self, text: &str) {
+        self.flush_text();
+        self.buffers.last_mut().unwrap().push_str(text);
+    }
+
+    fn flush_text(&mut self) {
+        if self.pending_text.is_empty() {
+            return;
+        }
+
+        let text = std::mem::take(&mut self.pending_text);
+        let converted = convert_citations(&text, &mut self.has_citations);
+        self.buffers.last_mut().unwrap().push_str(&converted);
+    }
+
+    fn push_buffer(&mut self) {
+        self.flush_text();
+        self.buffers.push(String::new());
+    }
+
+    fn pop_buffer(&mut self) -> String {
+        self.flush_text();
+        self.buffers.pop().unwrap_or_default()
+    }
+
+    /// Whether the paragraph starting at `index` holds nothing but one image
+    fn is_figure(events: &[Event], index: usize) -> bool {
+        if !matches!(events.get(index + 1), Some(Event::Start(Tag::Image { .. }))) {
+            return false;
+        }
+
+        let mut depth = 0;
+        for (offset, event) in events[index + 1..].iter().enumerate() {
+            match event {
+                Event::Start(_) => depth += 1,
+                Event::End(_) => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                return matches!(
+                    events.get(index + offset + 2),
+                    Some(Event::End(TagEnd::Paragraph))
+                );
+            }
+        }
+
+        false
+    }
+
+    fn run(mut self, events: Vec<Event>) -> (String, bool) {
+        for index in 0..events.len() {
+            let event = &events[index];
+
+            if self.in_metadata {
+                if matches!(event, Event::End(TagEnd::MetadataBlock(_))) {
+                    self.in_metadata = false;
+                }
+                continue;
+            }
+
+            match event {
+                Event::Start(tag) => self.start_tag(tag, &events, index),
+                Event::End(tag) => self.end_tag(tag),
+                Event::Text(text) => {
+                    if self.in_heading {
+                        self.heading_text.push_str(text);
+                    }
+                    if self.in_code_block {
+                        self.write(text);
+                    } else {
+                        self.pending_text.push_str(text);
+                    }
+                }
+                Event::Code(code) => {
+                    if self.in_heading {
+                        self.heading_text.push_str(code);
+                    }
+                    self.write(&format!(r"\texttt{{{}}}", escape_latex(code)));
+                }
+                Event::InlineMath(math) => self.write(&format!("${}$", math)),
+                Event::DisplayMath(math) => self.write(&format!("\n\\[\n{}\n\\]\n", math.trim())),
+                // Raw HTML has no LaTeX equivalent
+                Event::Html(_) | Event::InlineHtml(_) => {}
+                Event::FootnoteReference(name) => {
+                    self.write(&format!("\u{0}FN:{}\u{0}", name));
+                }
+                Event::SoftBreak => self.write("\n"),
+                Event::HardBreak => self.write("\\\\\n"),
+                Event::Rule => self.write("\n\\par\\noindent\\rule{\\textwidth}{0.4pt}\\par\n\n"),
+                Event::TaskListMarker(checked) => {
+                    self.write(if *checked { r"$\boxtimes$ " } else { r"$\square$ " });
+                }
+            }
+        }
+
+        self.flush_text();
+        let mut body = self.buffers.swap_remove(0);
+
+        // Inline footnote definitions at their reference sites
+        for (name, text) in &self.footnotes {
+            body = body.replace(
+                &format!("\u{0}FN:{}\u{0}", name),
+                &format!(r"\footnote{{{}}}", text.trim()),
+            );
+        }
+        while let Some(start) = body.find("\u{0}FN:") {
+            let end = body[start + 1..]
+                .find('\u{0}')
+                .map_or(body.len(), |e| start + e + 2);
+            body.replace_range(start..end, "");
+        }
+
+        (body, self.has_citations)
+    }
+
+    fn start_tag(&mut self, tag: &Tag, events: &[Event], index: usize) {
+        match tag {
+            Tag::Paragraph => {
+                if Self::is_figure(events, index) {
+                    self.in_figure = true;
+                    self.write("\\begin{figure}[h]\n\\centering\n");
+                }
+            }
+            Tag::Heading { id, .. } => {
+                self.in_heading = true;
+                self.heading_text.clear();
+                self.heading_id = id.as_ref().map(|id| id.to_string());
+                self.push_buffer();
+            }
+            Tag::BlockQuote(_) => self.write("\\begin{quote}\n"),
+            Tag::CodeBlock(kind) => {
+                self.in_code_block = true;
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info
+                        .split_whitespace()
+                        .next()
+                        .and_then(listings_language),
+                    CodeBlockKind::Indented => None,
+                };
+                match language {
+                    Some(language) => self.write(&format!("\\begin{{lstlisting}}[language={}]\n", language)),
+                    None => self.write("\\begin{lstlisting}\n"),
+                }
+            }
+            Tag::HtmlBlock => {}
+            Tag::List(start) => {
+                self.list_depth += 1;
+                match start {
+                    Some(start) => {
+                        self.write("\\begin{enumerate}\n");
+                        let counter = ["i", "ii", "iii", "iv"].get(self.list_depth - 1);
+                        if let (Some(counter), true) = (counter, *start != 1) {
+                            self.write(&format!("\\setcounter{{enum{}}}{{{}}}\n", counter, start.saturating_sub(1)));
+                        }
+                    }
+                    None => self.write("\\begin{itemize}\n"),
+                }
+            }
+            Tag::Item => self.write("\\item "),
+            Tag::FootnoteDefinition(name) => {
+                self.footnote_name = Some(name.to_string());
+                self.push_buffer();
+            }
+            Tag::DefinitionList => self.write("\\begin{description}\n"),
+            Tag::DefinitionListTitle => self.write("\\item["),
+            Tag::DefinitionListDefinition => {}
+            Tag::Table(alignments) => {
+                let spec: String = alignments
+                    .iter()
+                    .map(|a| match a {
+                        Alignment::Center => 'c',
+                        Alignment::Right => 'r',
+                        Alignment::Left | Alignment::None => 'l',
+                    })
+                    .collect();
+                self.write(&format!(
+                    "\\begin{{table}}[h]\n\\centering\n\\begin{{tabular}}{{{}}}\n\\toprule\n",
+                    spec
+                ));
+            }
+            Tag::TableHead | Tag::TableRow => self.cell_index = 0,
+            Tag::TableCell => {
+                if self.cell_index > 0 {
+                    self.write(" & ");
+                }
+                self.cell_index += 1;
+            }
+            Tag::Emphasis => self.write("\\emph{"),
+            Tag::Strong => self.write("\\textbf{"),
+            Tag::Strikethrough => self.write("\\sout{"),
+            Tag::Link { dest_url, .. } => {
+                if let Some(anchor) = dest_url.strip_prefix('#') {
+                    self.write(&format!("\\hyperref[{}]{{", anchor));
+                } else {
+                    self.write(&format!("\\href{{{}}}{{", dest_url.replace('%', "\\%").replace('#', "\\#")));
+                }
+            }
+            Tag::Image { dest_url, .. } => {
+                self.image_url = dest_url.to_string();
+                self.push_buffer();
+            }
+            Tag::MetadataBlock(_) => self.in_metadata = true,
+        }
+    }
+
+    fn end_tag(&mut self, tag: &TagEnd) {
+        match tag {
+            TagEnd::Paragraph => {
+                if self.in_figure {
+                    self.in_figure = false;
+                    self.write("\\end{figure}\n\n");
+                } else {
+                    self.write("\n\n");
+                }
+            }
+            TagEnd::Heading(level) => {
+                self.in_heading = false;
+                let content = self.pop_buffer();
+                let command = match level {
+                    HeadingLevel::H1 => "section",
+                    HeadingLevel::H2 => "subsection",
+                    HeadingLevel::H3 => "subsubsection",
+                    HeadingLevel::H4 => "paragraph",
+                    HeadingLevel::H5 | HeadingLevel::H6 => "subparagraph",
+                };
+                // An explicit `{#id}` wins over the generated slug; generated
+                // ones are numbered so repeated headings keep unique labels
+                let label = match self.heading_id.take() {
+                    Some(id) => id,
+                    None => self.slugger.slug(&self.heading_text),
+                };
+                self.write(&format!("\\{}{{{}}}\\label{{{}}}\n\n", command, content.trim(), label));
+            }
+            TagEnd::BlockQuote(_) => self.write("\\end{quote}\n\n"),
+            TagEnd::CodeBlock => {
+                self.in_code_block = false;
+                self.write("\\end{lstlisting}\n\n");
+            }
+            TagEnd::HtmlBlock => {}
+            TagEnd::List(ordered) => {
+                self.list_depth -= 1;
+                if *ordered {
+                    self.write("\\end{enumerate}\n\n");
+                } else {
+                    self.write("\\end{itemize}\n\n");
+                }
+            }
+            TagEnd::Item => self.write("\n"),
+            TagEnd::FootnoteDefinition => {
+                let text = self.pop_buffer();
+                if let Some(name) = self.footnote_name.take() {
+                    self.footnotes.insert(name, text);
+                }
+            }
+            TagEnd::DefinitionList => self.write("\\end{description}\n\n"),
+            TagEnd::DefinitionListTitle => self.write("] "),
+            TagEnd::DefinitionListDefinition => self.write("\n"),
+            TagEnd::Table => {
+                self.write("\\bottomrule\n\\end{tabular}\n\\end{table}\n\n");
+            }
+            TagEnd::TableHead => self.write(" \\\\\n\\midrule\n"),
+            TagEnd::TableRow => self.write(" \\\\\n"),
+            TagEnd::TableCell => {}
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough | TagEnd::Link => self.write("}"),
+            TagEnd::Image => {
+                let alt = self.pop_buffer();
+                let url = std::mem::take(&mut self.image_url);
+                let graphic = if url.starts_with("http://") || url.starts_with("https://") {
+                    // Remote images cannot be embedded by pdflatex
+                    format!("\\url{{{}}}", url)
+                } else {
+                    format!("\\includegraphics[width=\\linewidth]{{{}}}", url)
+                };
+
+                if self.in_figure {
+                    self.write(&format!("{}\n", graphic));
+                    if !alt.trim().is_empty() {
+                        self.write(&format!("\\caption{{{}}}\n", alt.trim()));
+                    }
+                } else {
+                    self.write(&graphic);
+                }
+            }
+            TagEnd::MetadataBlock(_) => self.in_metadata = false,
+        }
+    }
+}
+
+/// Convert Markdown to a complete LaTeX document
+pub(crate) fn markdown_to_latex(
+    content: &str,
+    options: &LatexExportOptions,
+) -> Result<String, ExportError> {
+    let events: Vec<Event> = Parser::new_ext(content, parser_options()).collect();
+    let (body, has_citations) = LatexWriter::new().run(events);
+
+    let mut preamble = match &options.preamble_path {
+        Some(path) => {
+            if !Path::new(path).exists() {
+                return Err(ExportError::NotFound(path.clone()));
+            }
+            fs::read_to_string(path)?
+                .replace("{{title}}", &escape_latex(options.title.as_deref().unwrap_or_default()))
+                .replace("{{author}}", &escape_latex(options.author.as_deref().unwrap_or_default()))
+        }
+        None => DEFAULT_PREAMBLE.to_string(),
+    };
+
+    if options.preamble_path.is_none() {
+        if let Some(title) = &options.title {
+            preamble.push_str(&format!("\\title{{{}}}\n", escape_latex(title)));
+        }
+        if let Some(author) = &options.author {
+            preamble.push_str(&format!("\\author{{{}}}\n", escape_latex(author)));
+        }
+    }
+
+    let mut document = preamble;
+    if !document.ends_with('\n') {
+        document.push('\n');
+    }
+    document.push_str("\n\\begin{document}\n\n");
+    if options.title.is_some() {
+        document.push_str("\\maketitle\n\n");
+    }
+    document.push_str(body.trim_end());
+    document.push('\n');
+
+    if let (true, Some(bibliography)) = (has_citations, &options.bibliography) {
+        let name = Path::new(bibliography).with_extension("");
+        document.push_str(&format!(
+            "\n\\bibliographystyle{{plain}}\n\\bibliography{{{}}}\n",
+            name.display()
+        ));
+    }
+
+    document.push_str("\n\\end{document}\n");
+    Ok(document)
+}
+
+/// Export Markdown content to a `.tex` file
+#[tauri::command]
+pub fn export_latex(
+    content: &str,
+    output_path: &str,
+    options: Option<LatexExportOptions>,
+) -> Result<(), ExportError> {
+    let document = markdown_to_latex(content, &options.unwrap_or_default())?;
+    let path = Path::new(output_path);
+
+    // Create parent directories if they don't exist
+    if let Some(parent) = path.parent() {
+        if !parent.exists() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+
+    fs::write(path, document)?;
+    Ok(())
+}
+
+const SLIDES_CSS: &str = r#"
+:root { --bg: #ffffff; --fg: #24292f; --muted: #6e7781; --accent: #0969da; --code-bg: #f6f8fa; }
+body.theme-dark { --bg: #1e1e1e; --fg: #d4d4d4; --muted: #8b949e; --accent: #58a6ff; --code-bg: #2d2d2d; }
+* { box-sizing: border-box; }
+html, body { margin: 0; height: 100%; background: var(--bg); color: var(--fg); }
+body { font-family: system-ui, -apple-system, "Segoe UI", sans-serif; overflow: hidden; }
+.slide { display: none; position: absolute; inset: 0; padding: 6vh 8vw; font-size: 3.2vh; line-height: 1.5; overflow: auto; }
+.slide.active { display: flex; flex-direction: column; justify-content: center; }
+.slide h1 { font-size: 2.2em; margin: 0 0 0.5em; }
+.slide h2 { font-size: 1.6em; margin: 0 0 0.5em; }
+.slide a { color: var(--accent); }
+.slide img { max-width: 100%; max-height: 60vh; align-self: center; }
+.slide pre { background: var(--code-bg); padding: 1em; border-radius: 6px; overflow: auto; font-size: 0.8em; }
+.slide code { font-family: ui-monospace, "SF Mono", Menlo, Consolas, monospace; }
+.slide table { border-collapse: collapse; }
+.slide th, .slide td { border: 1px solid var(--muted); padding: 0.3em 0.8em; }
+.slide blockquote { border-left: 4px solid var(--muted); margin: 0; padding-left: 1em; color: var(--muted); }
+.notes { display: none; }
+body.show-notes .slide { bottom: 30vh; }
+body.show-notes .slide.active .notes { display: block; position: fixed; left: 0; right: 0; bottom: 0; height: 30vh; overflow: auto; padding: 2vh 8vw; font-size: 2.4vh; background: var(--code-bg); border-top: 1px solid var(--muted); }
+.progress { position: fixed; left: 0; bottom: 0; height: 4px; background: var(--accent); transition: width 0.2s; }
+.counter { position: fixed; right: 2vw; bottom: 1.5vh; color: var(--muted); font-size: 1.8vh; }
+@media print {
+  html, body { overflow: visible; height: auto; }
+  .slide { display: flex !important; position: relative; height: 100vh; page-break-after: always; }
+  .progress, .counter, .notes { display: none !important; }
+}
+"#;
+
+const SLIDES_JS: &str = r#"
+(function () {
+  var slides = document.querySelectorAll('.slide');
+  var progress = document.querySelector('.progress');
+  var counter = document.querySelector('.counter');
+  var current = 0;
+
+  function show(index) {
+    current = Math.max(0, Math.min(slides.length - 1, index));
+    slides.forEach(function (slide, i) { slide.classList.toggle('active', i === current); });
+    progress.style.width = ((current + 1) / slides.length * 100) + '%';
+    counter.textContent = (current + 1) + ' / ' + slides.length;
+    history.replaceState(null, '', '#' + (current + 1));
+  }
+
+  document.addEventListener('keydown', function (e) {
+    switch (e.key) {
+      case 'ArrowRight': case 'ArrowDown': case 'PageDown': case ' ': case 'Enter':
+        show(current + 1); break;
+      case 'ArrowLeft': case 'ArrowUp': case 'PageUp': case 'Backspace':
+        show(current - 1); break;
+      case 'Home': show(0); break;
+      case 'End': show(slides.length - 1); break;
+      case 'n': case 'N': case 's': case 'S':
+        document.body.classList.toggle('show-notes'); break;
+      case 'f': case 'F':
+        if (document.fullscreenElement) { document.exitFullscreen(); }
+        else { document.documentElement.requestFullscreen(); }
+        break;
+      default: return;
+    }
+    e.preventDefault();
+  });
+
+  document.addEventListener('click', function (e) {
+    if (e.target.closest('a')) return;
+    show(e.clientX < window.innerWidth / 3 ? current - 1 : current + 1);
+  });
+
+  // Clicks in the notes pane (selecting text, scrolling) don't change slides
+  document.querySelectorAll('.notes').forEach(function (notes) {
+    notes.addEventListener('click', function (e) { e.stopPropagation(); });
+  });
+
+  show((parseInt(location.hash.slice(1), 10) || 1) - 1);
+})();
+"#;
+
+/// Escape text for use inside HTML
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Guess an image MIME type from its file extension
+fn image_mime_type(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_string_lossy().to_lowercase();
+    match ext.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "svg" => Some("image/svg+xml"),
+        "webp" => Some("image/webp"),
+        "avif" => Some("image/avif"),
+        "bmp" => Some("image/bmp"),
+        "ico" => Some("image/x-icon"),
+        _ => None,
+    }
+}
+
+/// Inline a local image as a data URI so the deck works offline
+fn embed_image(url: &str, base_dir: Option<&Path>) -> Option<String> {
+    if url.contains("://") || url.starts_with("data:") {
+        return None;
+    }
+
+    let decoded = percent_decode(url);
+    let path = match base_dir {
+        Some(base) if Path::new(&decoded).is_relative() => base.join(&decoded),
+        _ => Path::new(&decoded).to_path_buf(),
+    };
+
+    let mime = image_mime_type(&path)?;
+    let bytes = fs::read(&path).ok()?;
+    Some(format!("data:{};base64,{}", mime, STANDARD.encode(bytes)))
+}
+
+/// Split a top-level event stream into slides on thematic breaks and,
+/// optionally, H1/H2 headings
+fn split_slides(events: Vec<Event>, split_on_headings: bool) -> Vec<Vec<Event>> {
+    let mut slides: Vec<Vec<Event>> = vec![Vec::new()];
+    let mut depth = 0;
+    let mut in_metadata = false;
+
+    for event in events {
+        match &event {
+            Event::Start(Tag::MetadataBlock(_)) => in_metadata = true,
+            Event::End(TagEnd::MetadataBlock(_)) => {
+                in_metadata = false;
+                continue;
+            }
+            _ => {}
+        }
+        if in_metadata {
+            continue;
+        }
+
+        let starts_slide = match &event {
+            Event::Rule => depth == 0,
+            Event::Start(Tag::Heading { level, .. }) => {
+                split_on_headings && depth == 0 && matches!(level, HeadingLevel::H1 | HeadingLevel::H2)
+            }
+            _ => false,
+        };
+
+        if starts_slide && slides.last().is_some_and(|slide| !slide.is_empty()) {
+            slides.push(Vec::new());
+        }
+
+        match &event {
+            Event::Rule if depth == 0 => continue,
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth -= 1,
+            _ => {}
+        }
+
+        slides.last_mut().unwrap().push(event);
+    }
+
+    slides.retain(|slide| !slide.is_empty());
+    slides
+}
+
+/// Separate a slide into its content and the speaker notes that follow a
+/// top-level paragraph beginning with `Note:`
+fn split_notes(mut slide: Vec<Event>) -> (Vec<Event>, Vec<Event>) {
+    let mut depth = 0;
+    let mut notes_start = None;
+
+    for (index, event) in slide.iter().enumerate() {
+        if depth == 0 && matches!(event, Event::Start(Tag::Paragraph)) {
+            if let Some(Event::Text(text)) = slide.get(index + 1) {
+                if text.starts_with("Note:") || text.starts_with("Notes:") {
+                    notes_start = Some(index);
+                    break;
+                }
+            }
+        }
+        match event {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth -= 1,
+            _ => {}
+        }
+    }
+
+    let Some(start) = notes_start else {
+        return (slide, Vec::new());
+    };
+
+    let mut notes = slide.split_off(start);
+    if let Some(Event::Text(text)) = notes.get_mut(1) {
+        let stripped = text.split_once(':').map(|(_, rest)| rest.trim_start().to_string());
+        *text = CowStr::from(stripped.unwrap_or_default());
+    }
+
+    (slide, notes)
+}
+
+/// Convert Markdown to a self-contained HTML slide deck
+pub(crate) fn markdown_to_slides(content: &str, options: &SlideExportOptions) -> String {
+    let base_dir = options
+        .document_path
+        .as_deref()
+        .and_then(|path| Path::new(path).parent());
+
+    let events: Vec<Event> = Parser::new_ext(content, parser_options())
+        .map(|event| match event {
+            Event::Start(Tag::Image { link_type, dest_url, title, id }) => {
+                let dest_url = embed_image(&dest_url, base_dir)
+                    .map(CowStr::from)
+                    .unwrap_or(dest_url);
+                Event::Start(Tag::Image { link_type, dest_url, title, id })
+            }
+            event => event,
+        })
+        .collect();
+
+    let split_on_headings = options.split_on_headings.unwrap_or(true);
+    let mut sections = String::new();
+
+    for (index, slide) in split_slides(events, split_on_headings).into_iter().enumerate() {
+        let (body, notes) = split_notes(slide);
+
+        sections.push_str(&format!("<section class=\"slide\" id=\"slide-{}\">\n", index + 1));
+        html::push_html(&mut sections, body.into_iter());
+        if !notes.is_empty() {
+            sections.push_str("<aside class=\"notes\">\n");
+            html::push_html(&mut sections, notes.into_iter());
+            sections.push_str("</aside>\n");
+        }
+        sections.push_str("</section>\n");
+    }
+
+    let theme = match options.theme.as_deref() {
+        Some("dark") => "theme-dark",
+        _ => "theme-light",
+    };
+    let title = escape_html(options.title.as_deref().unwrap_or("Slides"));
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<style>{}</style>\n</head>\n<body class=\"{}\">\n\
+         {}<div class=\"progress\"></div>\n<div class=\"counter\"></div>\n\
+         <script>{}</script>\n</body>\n</html>\n",
+        title, SLIDES_CSS, theme, sections, SLIDES_JS
+    )
+}
+
+/// Export Markdown content as an HTML slide deck
+#[tauri::command]
+pub fn export_slides(
+    content: &str,
+    output_path: &str,
+    options: Option<SlideExportOptions>,
+) -> Result<(), ExportError> {
+    let document = markdown_to_slides(content, &options.unwrap_or_default());
+    let path = Path::new(output_path);
+
+    // Create parent directories if they don't exist
+    if let Some(parent) = path.parent() {
+        if !parent.exists() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+
+    fs::write(path, document)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn latex_body(content: &str) -> String {
+        let events: Vec<Event> = Parser::new_ext(content, parser_options()).collect();
+        LatexWriter::new().run(events).0
+    }
+
+    #[test]
+    fn escapes_special_characters() {
+        assert_eq!(escape_latex(r"50% & $5_a#{x}"), r"50\% \& \$5\_a\#\{x\}");
+        assert_eq!(escape_latex(r"a\b^c~"), r"a\textbackslash{}b\textasciicircum{}c\textasciitilde{}");
+    }
+
+    #[test]
+    fn passes_math_through_unescaped() {
+        let body = latex_body("Inline $a_b^2$ and\n\n$$\n\\frac{1}{2}\n$$\n");
+        assert!(body.contains("$a_b^2$"));
+        assert!(body.contains("\\[\n\\frac{1}{2}\n\\]"));
+    }
+
+    #[test]
+    fn converts_citations() {
+        let mut has_citations = false;
+        let text = convert_citations("See [@knuth; @lamport:94] and [not a cite].", &mut has_citations);
+        assert_eq!(text, r"See \cite{knuth,lamport:94} and [not a cite].");
+        assert!(has_citations);
+
+        let mut has_citations = false;
+        assert_eq!(convert_citations("[@]", &mut has_citations), "[@]");
+        assert!(!has_citations);
+    }
+
+    #[test]
+    fn numbers_duplicate_heading_labels() {
+        let body = latex_body("# Intro\n\n## Intro\n\n# Setup {#custom}\n\n# Intro\n");
+        assert!(body.contains(r"\section{Intro}\label{intro}"));
+        assert!(body.contains(r"\subsection{Intro}\label{intro-1}"));
+        assert!(body.contains(r"\section{Setup}\label{custom}"));
+        assert!(body.contains(r"\section{Intro}\label{intro-2}"));
+    }
+
+    #[test]
+    fn inlines_footnotes_and_figures() {
+        let body = latex_body("Text[^a].\n\n[^a]: The note.\n\n![A caption](img/cat.png)\n");
+        assert!(body.contains(r"Text\footnote{The note.}."));
+        assert!(body.contains("\\begin{figure}[h]\n\\centering\n\\includegraphics[width=\\linewidth]{img/cat.png}\n\\caption{A caption}\n\\end{figure}"));
+        assert!(!body.contains('\u{0}'));
+    }
+
+    #[test]
+    fn maps_lists_tables_and_code() {
+        let body = latex_body("3. three\n4. four\n\n| a | b |\n|:-:|--:|\n| 1 | 2 |\n\n```py\nx = 1_0\n```\n");
+        assert!(body.contains("\\begin{enumerate}\n\\setcounter{enumi}{2}\n\\item three"));
+        assert!(body.contains("\\begin{tabular}{cr}\n\\toprule\na & b \\\\\n\\midrule\n1 & 2 \\\\\n\\bottomrule"));
+        assert!(body.contains("\\begin{lstlisting}[language=Python]\nx = 1_0\n\\end{lstlisting}"));
+    }
+
+    #[test]
+    fn adds_bibliography_only_when_cited() {
+        let options = LatexExportOptions {
+            title: Some("A & B".into()),
+            bibliography: Some("refs.bib".into()),
+            ..Default::default()
+        };
+
+        let document = markdown_to_latex("As shown [@knuth].", &options).unwrap();
+        assert!(document.contains(r"\title{A \& B}"));
+        assert!(document.contains("\\maketitle"));
+        assert!(document.contains("\\bibliography{refs}"));
+        assert!(document.ends_with("\\end{document}\n"));
+
+        let document = markdown_to_latex("No citations here.", &options).unwrap();
+        assert!(!document.contains("\\bibliography"));
+    }
+
+    #[test]
+    fn embeds_percent_encoded_images() {
+        let dir = std::env::temp_dir().join(format!("ourea-export-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("café (1).png"), b"png").unwrap();
+
+        let embedded = embed_image("caf%C3%A9%20%281%29.png", Some(&dir));
+        assert_eq!(embedded.as_deref(), Some("data:image/png;base64,cG5n"));
+        assert_eq!(embed_image("https://example.com/a.png", Some(&dir)), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
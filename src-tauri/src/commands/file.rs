@@ -70,6 +70,21 @@ pub fn write_file(path: &str, content: &str) -> Result<(), FileError> {
     Ok(())
 }
 
+/// Replace a file's content through a temporary sibling and a rename, so
+/// readers never see a half-written file
+pub(crate) fn write_atomic(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("file");
+    let temp = path.with_file_name(format!(".{}.tmp", file_name));
+
+    fs::write(&temp, content)?;
+    if let Err(e) = fs::rename(&temp, path) {
+        let _ = fs::remove_file(&temp);
+        return Err(e);
+    }
+
+    Ok(())
+}
+
 /// Check if file exists
 #[tauri::command]
 pub fn file_exists(path: &str) -> bool {
@@ -172,7 +187,7 @@ pub fn read_directory(path: &str) -> Result<Vec<FileTreeNode>, FileError> {
 }
 
 /// Check if a file extension is a supported text file
-fn is_text_file_extension(ext: Option<&str>) -> bool {
+pub(crate) fn is_text_file_extension(ext: Option<&str>) -> bool {
     matches!(
         ext,
         // Markdown
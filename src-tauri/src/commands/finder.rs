@@ -0,0 +1,475 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use super::frontmatter::parse_front_matter;
+use super::markdown::{is_markdown_file, workspace_walker};
+use super::session::recent_files;
+use super::watcher::{watch_workspace, WatcherError, WorkspaceSubscription, WorkspaceWatchers};
+
+const DEFAULT_LIMIT: usize = 50;
+/// Number of recently opened files that rank higher
+const RECENT_LIMIT: usize = 30;
+
+// fzf-style scoring
+const SCORE_MATCH: i32 = 16;
+const PENALTY_GAP_START: i32 = 3;
+const PENALTY_GAP_EXTENSION: i32 = 1;
+const BONUS_SEPARATOR: i32 = 10;
+const BONUS_BOUNDARY: i32 = 8;
+const BONUS_CAMEL: i32 = 7;
+const BONUS_CONSECUTIVE: i32 = 4;
+const BONUS_FIRST_CHAR_MULTIPLIER: i32 = 2;
+/// Extra score when a term matches within the file name alone
+const BONUS_FILE_NAME: i32 = 24;
+/// Extra score for the most recently opened file, fading with age
+const BONUS_RECENT: i32 = 40;
+
+#[derive(Debug, Clone)]
+struct FinderEntry {
+    relative: String,
+    name: String,
+    title: Option<String>,
+}
+
+type FileCache = Arc<Mutex<BTreeMap<PathBuf, FinderEntry>>>;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FuzzyFileMatch {
+    pub path: String,
+    pub relative_path: String,
+    pub name: String,
+    pub title: Option<String>,
+    pub score: i32,
+    /// Matched character positions in `relative_path`, for highlighting
+    pub positions: Vec<usize>,
+    /// Whether the query matched the front-matter title instead of the path
+    pub title_match: bool,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum FinderError {
+    #[error("Path not found: {0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Watch(#[from] WatcherError),
+}
+
+impl Serialize for FinderError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// Cached file list of the open workspace
+pub struct FinderState {
+    files: FileCache,
+    subscription: Option<WorkspaceSubscription>,
+}
+
+impl FinderState {
+    pub fn new() -> Self {
+        Self {
+            files: Arc::new(Mutex::new(BTreeMap::new())),
+            subscription: None,
+        }
+    }
+}
+
+impl Default for FinderState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn read_title(path: &Path) -> Option<String> {
+    if !is_markdown_file(path) {
+        return None;
+    }
+
+    let content = fs::read_to_string(path).ok()?;
+    let front_matter = parse_front_matter(&content).ok()??;
+    front_matter.data.get("title").and_then(Value::as_str).map(str::to_string)
+}
+
+fn make_entry(root: &Path, path: &Path) -> Option<FinderEntry> {
+    let relative = path.strip_prefix(root).ok()?.to_string_lossy().replace('\\', "/");
+    let name = path.file_name()?.to_string_lossy().to_string();
+
+    Some(FinderEntry {
+        relative,
+        name,
+        title: read_title(path),
+    })
+}
+
+/// Add every file under a folder to the cache
+fn scan(files: &Mutex<BTreeMap<PathBuf, FinderEntry>>, root: &Path, folder: &Path) {
+    for entry in workspace_walker(folder).build().flatten() {
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        if let Some(finder_entry) = make_entry(root, entry.path()) {
+            files.lock().unwrap().insert(entry.into_path(), finder_entry);
+        }
+    }
+}
+
+/// Bring the cache up to date after a change on disk
+fn update_path(files: &Mutex<BTreeMap<PathBuf, FinderEntry>>, root: &Path, path: &Path) {
+    if path.is_dir() {
+        scan(files, root, path);
+    } else if path.is_file() {
+        if let Some(entry) = make_entry(root, path) {
+            files.lock().unwrap().insert(path.to_path_buf(), entry);
+        }
+    } else {
+        // Removed, either a single file or a whole folder
+        files.lock().unwrap().retain(|cached, _| !cached.starts_with(path));
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum CharClass {
+    Separator,
+    Delimiter,
+    Lower,
+    Upper,
+    Digit,
+    Other,
+}
+
+fn char_class(c: char) -> CharClass {
+    match c {
+        '/' | '\\' => CharClass::Separator,
+        '-' | '_' | ' ' | '.' => CharClass::Delimiter,
+        c if c.is_lowercase() => CharClass::Lower,
+        c if c.is_uppercase() => CharClass::Upper,
+        c if c.is_numeric() => CharClass::Digit,
+        _ => CharClass::Other,
+    }
+}
+
+/// Bonus for matching a character, by what precedes it
+fn position_bonus(previous: Option<char>, current: char) -> i32 {
+    let current = char_class(current);
+    match previous.map(char_class) {
+        None | Some(CharClass::Separator) => BONUS_SEPARATOR,
+        Some(CharClass::Delimiter) => BONUS_BOUNDARY,
+        Some(CharClass::Lower) if current == CharClass::Upper => BONUS_CAMEL,
+        Some(CharClass::Lower) | Some(CharClass::Upper) if current == CharClass::Digit => BONUS_CAMEL,
+        _ => 0,
+    }
+}
+
+/// Best alignment of a pattern as a subsequence of some text, fzf-style:
+/// matches score more at word starts and in runs, and less after gaps.
+/// Returns the score and the matched character positions.
+fn fuzzy_match(pattern: &[char], text: &str, case_sensitive: bool) -> Option<(i32, Vec<usize>)> {
+    let chars: Vec<char> = text.chars().collect();
+    let (m, n) = (pattern.len(), chars.len());
+    if m == 0 || m > n {
+        return None;
+    }
+
+    let equal = |a: char, b: char| {
+        if case_sensitive {
+            a == b
+        } else {
+            a.to_lowercase().eq(b.to_lowercase())
+        }
+    };
+
+    const NONE: i32 = i32::MIN / 2;
+    // score[i][j]: best score with pattern[i] matched at chars[j]; from[i][j]: where pattern[i - 1] was
+    let mut score = vec![vec![NONE; n]; m];
+    let mut from = vec![vec![usize::MAX; n]; m];
+
+    for i in 0..m {
+        // Best score of the previous row reachable through a gap
+        let mut gapped = NONE;
+        let mut gapped_from = usize::MAX;
+
+        for j in i..n {
+            if i > 0 && j >= 2 {
+                let candidate = score[i - 1][j - 2] - PENALTY_GAP_START;
+                if gapped - PENALTY_GAP_EXTENSION >= candidate {
+                    gapped -= PENALTY_GAP_EXTENSION;
+                } else {
+                    gapped = candidate;
+                    gapped_from = j - 2;
+                }
+            }
+
+            if !equal(pattern[i], chars[j]) {
+                continue;
+            }
+
+            let bonus = position_bonus(j.checked_sub(1).map(|k| chars[k]), chars[j]);
+            if i == 0 {
+                // Leading characters skipped before the first match cost a little
+                score[i][j] = SCORE_MATCH + bonus * BONUS_FIRST_CHAR_MULTIPLIER - (j as i32).min(15);
+                continue;
+            }
+
+            let consecutive = score[i - 1][j - 1] + BONUS_CONSECUTIVE;
+            let (best, previous) = if consecutive >= gapped {
+                (consecutive, j - 1)
+            } else {
+                (gapped, gapped_from)
+            };
+            if best > NONE / 2 {
+                score[i][j] = best + SCORE_MATCH + bonus;
+                from[i][j] = previous;
+            }
+        }
+    }
+
+    let (end, &best) = score[m - 1].iter().enumerate().max_by_key(|(_, s)| **s)?;
+    if best <= NONE / 2 {
+        return None;
+    }
+
+    let mut positions = vec![end; m];
+    for i in (1..m).rev() {
+        positions[i - 1] = from[i][positions[i]];
+    }
+
+    Some((best, positions))
+}
+
+/// Score one cached file against every query term, trying the file name,
+/// the relative path and the title
+fn score_entry(terms: &[Vec<char>], entry: &FinderEntry) -> Option<(i32, Vec<usize>, bool)> {
+    let name_offset = entry.relative.chars().count() - entry.name.chars().count();
+    let mut total = 0;
+    let mut positions = Vec::new();
+    let mut title_match = false;
+
+    for term in terms {
+        // Smart case: any uppercase letter makes the term case-sensitive
+        let case_sensitive = term.iter().any(|c| c.is_uppercase());
+
+        let on_name = fuzzy_match(term, &entry.name, case_sensitive).map(|(score, found)| {
+            let found = found.into_iter().map(|p| p + name_offset).collect();
+            (score + BONUS_FILE_NAME, found)
+        });
+        let on_path = fuzzy_match(term, &entry.relative, case_sensitive);
+        let on_title = entry
+            .title
+            .as_deref()
+            .and_then(|title| fuzzy_match(term, title, case_sensitive));
+
+        let best_path = match (on_name, on_path) {
+            (Some(name), Some(path)) => Some(if name.0 >= path.0 { name } else { path }),
+            (name, path) => name.or(path),
+        };
+
+        match (best_path, on_title) {
+            (Some((path_score, _)), Some((score, _))) if score > path_score => {
+                total += score;
+                title_match = true;
+            }
+            (Some((score, found)), _) => {
+                total += score;
+                positions.extend(found);
+            }
+            (None, Some((score, _))) => {
+                total += score;
+                title_match = true;
+            }
+            (None, None) => return None,
+        }
+    }
+
+    positions.sort_unstable();
+    positions.dedup();
+    Some((total, positions, title_match))
+}
+
+fn recent_bonus(recent: &[String], path: &str) -> i32 {
+    recent
+        .iter()
+        .take(RECENT_LIMIT)
+        .position(|p| p == path)
+        .map_or(0, |rank| BONUS_RECENT * (RECENT_LIMIT - rank) as i32 / RECENT_LIMIT as i32)
+}
+
+/// Set the workspace for quick open. Its file list is cached in the
+/// background and kept current by a file watcher.
+#[tauri::command]
+pub fn set_finder_root(
+    root: String,
+    state: tauri::State<'_, Arc<Mutex<FinderState>>>,
+    watchers: tauri::State<'_, Arc<Mutex<WorkspaceWatchers>>>,
+) -> Result<(), FinderError> {
+    let root_path = PathBuf::from(&root);
+
+    if !root_path.exists() {
+        return Err(FinderError::NotFound(root));
+    }
+
+    let files: FileCache = Arc::new(Mutex::new(BTreeMap::new()));
+
+    let watched = files.clone();
+    let watched_root = root_path.clone();
+    let subscription = watch_workspace(&watchers, &root_path, move |path| {
+        update_path(&watched, &watched_root, path)
+    })?;
+
+    let scanned = files.clone();
+    let scan_root = root_path.clone();
+    std::thread::spawn(move || scan(&scanned, &scan_root, &scan_root));
+
+    let mut state = state.lock().unwrap();
+    state.files = files;
+    state.subscription = Some(subscription);
+
+    Ok(())
+}
+
+/// Fuzzy-match the workspace files for quick open. An empty query lists
+/// recently opened files, which also rank higher otherwise.
+#[tauri::command]
+pub fn fuzzy_find_files(
+    app: tauri::AppHandle,
+    query: String,
+    limit: Option<usize>,
+    state: tauri::State<'_, Arc<Mutex<FinderState>>>,
+) -> Vec<FuzzyFileMatch> {
+    let recent = recent_files(&app);
+    let state = state.lock().unwrap();
+    let files = state.files.lock().unwrap();
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+
+    let result = |path: &Path, entry: &FinderEntry, score: i32, positions: Vec<usize>, title_match: bool| {
+        FuzzyFileMatch {
+            path: path.display().to_string(),
+            relative_path: entry.relative.clone(),
+            name: entry.name.clone(),
+            title: entry.title.clone(),
+            score,
+            positions,
+            title_match,
+        }
+    };
+
+    let terms: Vec<Vec<char>> = query.split_whitespace().map(|t| t.chars().collect()).collect();
+    if terms.is_empty() {
+        return recent
+            .iter()
+            .filter_map(|path| {
+                let path = Path::new(path);
+                files.get(path).map(|entry| result(path, entry, 0, Vec::new(), false))
+            })
+            .take(limit)
+            .collect();
+    }
+
+    let mut matches: Vec<FuzzyFileMatch> = files
+        .iter()
+        .filter_map(|(path, entry)| {
+            let (score, positions, title_match) = score_entry(&terms, entry)?;
+            let score = score + recent_bonus(&recent, &path.display().to_string());
+            Some(result(path, entry, score, positions, title_match))
+        })
+        .collect();
+
+    // Shorter paths win ties, as they're usually what was meant
+    matches.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.relative_path.len().cmp(&b.relative_path.len()))
+            .then_with(|| a.relative_path.cmp(&b.relative_path))
+    });
+    matches.truncate(limit);
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(text: &str) -> Vec<char> {
+        text.chars().collect()
+    }
+
+    fn score(pattern: &str, text: &str) -> i32 {
+        fuzzy_match(&chars(pattern), text, false).map(|(score, _)| score).unwrap_or(i32::MIN)
+    }
+
+    fn entry(relative: &str, title: Option<&str>) -> FinderEntry {
+        FinderEntry {
+            relative: relative.to_string(),
+            name: relative.rsplit('/').next().unwrap().to_string(),
+            title: title.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn matches_subsequences_and_reports_positions() {
+        assert_eq!(fuzzy_match(&chars("rdm"), "readme.md", false).unwrap().1, [0, 3, 4]);
+        assert_eq!(fuzzy_match(&chars("abc"), "a/b/c", false).unwrap().1, [0, 2, 4]);
+        assert!(fuzzy_match(&chars("xyz"), "readme.md", false).is_none());
+        assert!(fuzzy_match(&chars("readme.mdx"), "readme.md", false).is_none());
+        assert!(fuzzy_match(&[], "readme.md", false).is_none());
+    }
+
+    #[test]
+    fn prefers_word_starts_runs_and_early_matches() {
+        // Consecutive characters beat scattered ones
+        assert!(score("note", "notes.md") > score("note", "nxoxtxe.md"));
+        // Word boundaries and camel case beat the middle of a word
+        assert!(score("dn", "daily-notes.md") > score("dn", "garden.md"));
+        assert!(score("fb", "fooBar.md") > score("fb", "fabric.md"));
+        // After a slash beats after a dash
+        assert!(score("n", "a/n") > score("n", "a-n"));
+        // Fewer skipped leading characters rank higher
+        assert!(score("md", "md.txt") > score("md", "xxxxxxxxmd.txt"));
+    }
+
+    #[test]
+    fn uses_smart_case() {
+        assert!(fuzzy_match(&chars("readme"), "README.md", false).is_some());
+        assert!(fuzzy_match(&chars("README"), "readme.md", true).is_none());
+
+        let entry = entry("docs/README.md", None);
+        assert!(score_entry(&[chars("readme")], &entry).is_some());
+        assert!(score_entry(&[chars("ReadMe")], &entry).is_none());
+    }
+
+    #[test]
+    fn scores_names_paths_and_titles() {
+        let in_name = entry("archive/plan.md", None);
+        let in_folder = entry("plan/archive.md", None);
+        let by_title = entry("2024/01.md", Some("Quarterly plan"));
+
+        let terms = [chars("plan")];
+        let (name_score, positions, title_match) = score_entry(&terms, &in_name).unwrap();
+        assert_eq!(positions, [8, 9, 10, 11]);
+        assert!(!title_match);
+        assert!(name_score > score_entry(&terms, &in_folder).unwrap().0);
+
+        let (_, positions, title_match) = score_entry(&terms, &by_title).unwrap();
+        assert!(title_match && positions.is_empty());
+
+        // Every term has to match somewhere
+        assert!(score_entry(&[chars("plan"), chars("zzz")], &in_name).is_none());
+        assert!(score_entry(&[chars("arch"), chars("plan")], &in_name).is_some());
+    }
+
+    #[test]
+    fn fades_the_recent_bonus_with_age() {
+        let recent = vec!["/a.md".to_string(), "/b.md".to_string()];
+        assert_eq!(recent_bonus(&recent, "/a.md"), BONUS_RECENT);
+        assert!(recent_bonus(&recent, "/b.md") < BONUS_RECENT);
+        assert_eq!(recent_bonus(&recent, "/c.md"), 0);
+    }
+}
@@ -0,0 +1,632 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+use super::file::write_atomic;
+use super::settings::find_workspace_config;
+
+/// Schema file looked up in the workspace `.ourea` folder
+const SCHEMA_FILE: &str = "frontmatter.schema.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrontMatterFormat {
+    Yaml,
+    Toml,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrontMatter {
+    pub format: Option<FrontMatterFormat>,
+    pub data: Map<String, Value>,
+    /// Byte offset where the document body starts
+    pub body_offset: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrontMatterEntry {
+    pub path: String,
+    pub data: Option<Map<String, Value>>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrontMatterIssue {
+    pub key: String,
+    pub message: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum FrontMatterError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("File not found: {0}")]
+    NotFound(String),
+    #[error("Invalid front matter: {0}")]
+    Parse(String),
+    #[error("Invalid schema: {0}")]
+    Schema(String),
+}
+
+impl Serialize for FrontMatterError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// Raw front matter block located at the top of a document
+pub(crate) struct FrontMatterBlock<'a> {
+    pub format: FrontMatterFormat,
+    pub raw: &'a str,
+    /// Byte offset where `raw` starts, after the opening fence
+    pub raw_offset: usize,
+    pub body_offset: usize,
+}
+
+/// Locate a `---` YAML or `+++` TOML block at the very start of a document
+pub(crate) fn split_front_matter(content: &str) -> Option<FrontMatterBlock<'_>> {
+    let start = if content.starts_with('\u{feff}') { 3 } else { 0 };
+    let rest = &content[start..];
+
+    let (format, fence) = if rest.starts_with("---") {
+        (FrontMatterFormat::Yaml, "---")
+    } else if rest.starts_with("+++") {
+        (FrontMatterFormat::Toml, "+++")
+    } else {
+        return None;
+    };
+
+    let first_line_end = rest.find('\n')?;
+    if rest[..first_line_end].trim_end() != fence {
+        return None;
+    }
+
+    let raw_start = start + first_line_end + 1;
+    let mut offset = raw_start;
+
+    for line in content[raw_start..].split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        let closes = trimmed == fence || (format == FrontMatterFormat::Yaml && trimmed == "...");
+        if closes {
+            return Some(FrontMatterBlock {
+                format,
+                raw: &content[raw_start..offset],
+                raw_offset: raw_start,
+                body_offset: offset + line.len(),
+            });
+        }
+        offset += line.len();
+    }
+
+    None
+}
+
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => Value::from(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(d) => Value::String(d.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(k, v)| (k, toml_to_json(v)))
+                .collect(),
+        ),
+    }
+}
+
+/// TOML has no null; keys set to null are dropped. Strings stay strings,
+/// even when they look like dates.
+fn json_to_toml(value: &Value) -> Option<toml_edit::Value> {
+    Some(match value {
+        Value::Null => return None,
+        Value::Bool(b) => toml_edit::Value::from(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => toml_edit::Value::from(i),
+            None => toml_edit::Value::from(n.as_f64()?),
+        },
+        Value::String(s) => toml_edit::Value::from(s.as_str()),
+        Value::Array(items) => toml_edit::Value::Array(items.iter().filter_map(json_to_toml).collect()),
+        Value::Object(map) => toml_edit::Value::InlineTable(
+            map.iter()
+                .filter_map(|(k, v)| Some((k.as_str(), json_to_toml(v)?)))
+                .collect(),
+        ),
+    })
+}
+
+fn yaml_error(e: serde_yaml::Error) -> FrontMatterError {
+    FrontMatterError::Parse(e.to_string())
+}
+
+/// Top-level `key: value` entries of a YAML block with the byte range of
+/// their lines, trailing blank lines excluded. `None` when the block uses
+/// anything else at the top level, such as complex keys.
+fn yaml_entries(raw: &str) -> Option<Vec<(String, Range<usize>)>> {
+    // Lines starting in column 0 begin an entry, or a comment between entries
+    let mut starts = Vec::new();
+    let mut offset = 0;
+    for line in raw.split_inclusive('\n') {
+        match line.chars().next() {
+            Some(c) if !c.is_whitespace() => starts.push((offset, c != '#')),
+            _ => {}
+        }
+        offset += line.len();
+    }
+
+    let mut entries = Vec::new();
+    for (index, &(start, is_entry)) in starts.iter().enumerate() {
+        if !is_entry {
+            continue;
+        }
+
+        let next = starts.get(index + 1).map_or(raw.len(), |&(next, _)| next);
+        let content_end = start + raw[start..next].trim_end().len();
+        let end = raw[content_end..next].find('\n').map_or(next, |i| content_end + i + 1);
+
+        let mapping: serde_yaml::Mapping = serde_yaml::from_str(&raw[start..end]).ok()?;
+        let mut keys = mapping.keys();
+        let (Some(serde_yaml::Value::String(key)), None) = (keys.next(), keys.next()) else {
+            return None;
+        };
+        entries.push((key.clone(), start..end));
+    }
+
+    Some(entries)
+}
+
+/// A single `key: value` entry, quoted as needed
+fn yaml_entry(key: &str, value: &Value) -> Result<String, FrontMatterError> {
+    let mut mapping = serde_yaml::Mapping::new();
+    mapping.insert(
+        serde_yaml::Value::String(key.to_string()),
+        serde_yaml::to_value(value).map_err(yaml_error)?,
+    );
+    serde_yaml::to_string(&mapping).map_err(yaml_error)
+}
+
+/// Apply updates to a YAML block, rewriting only the entries of the
+/// changed keys so comments, order and quoting elsewhere survive
+fn edit_yaml(raw: &str, updates: &[(String, Value)]) -> Result<String, FrontMatterError> {
+    let Some(entries) = yaml_entries(raw) else {
+        return rewrite_yaml(raw, updates);
+    };
+
+    let mut output = String::with_capacity(raw.len());
+    let mut last = 0;
+    for (key, range) in &entries {
+        if let Some((_, value)) = updates.iter().find(|(k, _)| k == key) {
+            output.push_str(&raw[last..range.start]);
+            if !value.is_null() {
+                output.push_str(&yaml_entry(key, value)?);
+            }
+            last = range.end;
+        }
+    }
+    output.push_str(&raw[last..]);
+
+    for (key, value) in updates {
+        if value.is_null() || entries.iter().any(|(k, _)| k == key) {
+            continue;
+        }
+        if !output.is_empty() && !output.ends_with('\n') {
+            output.push('\n');
+        }
+        output.push_str(&yaml_entry(key, value)?);
+    }
+
+    Ok(output)
+}
+
+/// Re-serialize a YAML block that can't be edited entry by entry
+fn rewrite_yaml(raw: &str, updates: &[(String, Value)]) -> Result<String, FrontMatterError> {
+    let mut mapping: serde_yaml::Mapping = if raw.trim().is_empty() {
+        serde_yaml::Mapping::new()
+    } else {
+        serde_yaml::from_str(raw).map_err(yaml_error)?
+    };
+
+    for (key, value) in updates {
+        let key = serde_yaml::Value::String(key.clone());
+        if value.is_null() {
+            mapping.shift_remove(&key);
+        } else {
+            mapping.insert(key, serde_yaml::to_value(value).map_err(yaml_error)?);
+        }
+    }
+
+    if mapping.is_empty() {
+        return Ok(String::new());
+    }
+    serde_yaml::to_string(&mapping).map_err(yaml_error)
+}
+
+/// Apply updates to a TOML block through its syntax tree, so untouched
+/// keys and comments are kept as written
+fn edit_toml(raw: &str, updates: &[(String, Value)]) -> Result<String, FrontMatterError> {
+    let mut document: toml_edit::DocumentMut = raw
+        .parse()
+        .map_err(|e: toml_edit::TomlError| FrontMatterError::Parse(e.to_string()))?;
+
+    for (key, value) in updates {
+        match json_to_toml(value) {
+            Some(mut value) => {
+                // Keep the spacing and trailing comment of the value it replaces
+                if let Some(existing) = document.get(key).and_then(toml_edit::Item::as_value) {
+                    *value.decor_mut() = existing.decor().clone();
+                }
+                document[key.as_str()] = toml_edit::Item::Value(value);
+            }
+            None => {
+                document.remove(key);
+            }
+        }
+    }
+
+    Ok(document.to_string())
+}
+
+/// Parse the front matter of a document into a JSON object
+pub(crate) fn parse_front_matter(content: &str) -> Result<Option<FrontMatter>, FrontMatterError> {
+    let Some(block) = split_front_matter(content) else {
+        return Ok(None);
+    };
+
+    let value = match block.format {
+        FrontMatterFormat::Yaml => {
+            if block.raw.trim().is_empty() {
+                Value::Object(Map::new())
+            } else {
+                let yaml: serde_yaml::Value = serde_yaml::from_str(block.raw)
+                    .map_err(|e| FrontMatterError::Parse(e.to_string()))?;
+                serde_json::to_value(yaml).map_err(|e| FrontMatterError::Parse(e.to_string()))?
+            }
+        }
+        FrontMatterFormat::Toml => {
+            let table: toml::Table = block
+                .raw
+                .parse()
+                .map_err(|e: toml::de::Error| FrontMatterError::Parse(e.to_string()))?;
+            toml_to_json(toml::Value::Table(table))
+        }
+    };
+
+    let Value::Object(data) = value else {
+        return Err(FrontMatterError::Parse("front matter must be a key/value map".to_string()));
+    };
+
+    Ok(Some(FrontMatter {
+        format: Some(block.format),
+        data,
+        body_offset: block.body_offset,
+    }))
+}
+
+fn read_document(path: &str) -> Result<String, FrontMatterError> {
+    if !Path::new(path).exists() {
+        return Err(FrontMatterError::NotFound(path.to_string()));
+    }
+    Ok(fs::read_to_string(path)?)
+}
+
+/// Read the front matter of a file as JSON
+#[tauri::command]
+pub fn read_front_matter(path: &str) -> Result<FrontMatter, FrontMatterError> {
+    let content = read_document(path)?;
+
+    Ok(parse_front_matter(&content)?.unwrap_or(FrontMatter {
+        format: None,
+        data: Map::new(),
+        body_offset: 0,
+    }))
+}
+
+/// Read the front matter of many files at once; per-file failures are
+/// reported in the entry instead of failing the batch
+#[tauri::command]
+pub fn read_front_matter_batch(paths: Vec<String>) -> Vec<FrontMatterEntry> {
+    paths
+        .into_iter()
+        .map(|path| {
+            let result = read_document(&path).and_then(|content| parse_front_matter(&content));
+            match result {
+                Ok(front_matter) => FrontMatterEntry {
+                    path,
+                    data: Some(front_matter.map(|f| f.data).unwrap_or_default()),
+                    error: None,
+                },
+                Err(e) => FrontMatterEntry {
+                    path,
+                    data: None,
+                    error: Some(e.to_string()),
+                },
+            }
+        })
+        .collect()
+}
+
+/// Set or remove front matter keys, leaving the document body and the
+/// other keys untouched. A `null` value removes the key. Files without
+/// front matter get a YAML block.
+#[tauri::command]
+pub fn update_front_matter(
+    path: &str,
+    updates: Map<String, Value>,
+) -> Result<Map<String, Value>, FrontMatterError> {
+    let content = read_document(path)?;
+    let current = parse_front_matter(&content)?;
+    let data = current.as_ref().map(|f| &f.data);
+
+    // Only keys whose value actually changes are written
+    let updates: Vec<(String, Value)> = updates
+        .into_iter()
+        .filter(|(key, value)| match data.and_then(|data| data.get(key)) {
+            Some(existing) => existing != value,
+            None => !value.is_null(),
+        })
+        .collect();
+    if updates.is_empty() {
+        return Ok(current.map(|f| f.data).unwrap_or_default());
+    }
+
+    let document = match split_front_matter(&content) {
+        Some(block) => {
+            let raw = match block.format {
+                FrontMatterFormat::Yaml => edit_yaml(block.raw, &updates)?,
+                FrontMatterFormat::Toml => edit_toml(block.raw, &updates)?,
+            };
+            let raw_end = block.raw_offset + block.raw.len();
+            format!("{}{}{}", &content[..block.raw_offset], raw, &content[raw_end..])
+        }
+        None => {
+            let yaml = edit_yaml("", &updates)?;
+            if content.is_empty() {
+                format!("---\n{}---\n", yaml)
+            } else {
+                format!("---\n{}---\n\n{}", yaml, content)
+            }
+        }
+    };
+    write_atomic(Path::new(path), document.as_bytes())?;
+
+    Ok(parse_front_matter(&document)?.map(|f| f.data).unwrap_or_default())
+}
+
+fn json_type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn matches_type(value: &Value, expected: &str) -> bool {
+    let actual = json_type_name(value);
+    actual == expected || (expected == "number" && actual == "integer")
+}
+
+fn is_date(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    bytes.len() >= 10
+        && bytes[..10]
+            .iter()
+            .enumerate()
+            .all(|(i, b)| if i == 4 || i == 7 { *b == b'-' } else { b.is_ascii_digit() })
+}
+
+/// Check a value against a schema node supporting the `type`, `enum`,
+/// `format` (`date`, `date-time`), `items`, `properties`, `required` and
+/// `additionalProperties` keywords of JSON Schema
+fn validate_value(key: &str, value: &Value, schema: &Value, issues: &mut Vec<FrontMatterIssue>) {
+    let mut issue = |message: String| {
+        issues.push(FrontMatterIssue {
+            key: key.to_string(),
+            message,
+        })
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|t| matches_type(value, t)) {
+            issue(format!("expected {}, found {}", types.join(" or "), json_type_name(value)));
+            return;
+        }
+    }
+
+    if let Some(Value::Array(allowed)) = schema.get("enum") {
+        if !allowed.contains(value) {
+            let options: Vec<String> = allowed.iter().map(Value::to_string).collect();
+            issue(format!("must be one of {}", options.join(", ")));
+        }
+    }
+
+    if let (Some(format), Some(text)) = (schema.get("format").and_then(Value::as_str), value.as_str()) {
+        if matches!(format, "date" | "date-time") && !is_date(text) {
+            issue(format!("expected a {} (YYYY-MM-DD)", format));
+        }
+    }
+
+    if let (Some(items), Value::Array(values)) = (schema.get("items"), value) {
+        for (index, item) in values.iter().enumerate() {
+            validate_value(&format!("{}[{}]", key, index), item, items, issues);
+        }
+    }
+
+    if let Value::Object(map) = value {
+        validate_object(key, map, schema, issues);
+    }
+}
+
+fn validate_object(prefix: &str, map: &Map<String, Value>, schema: &Value, issues: &mut Vec<FrontMatterIssue>) {
+    let qualified = |key: &str| {
+        if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", prefix, key)
+        }
+    };
+
+    if let Some(Value::Array(required)) = schema.get("required") {
+        for key in required.iter().filter_map(Value::as_str) {
+            if !map.contains_key(key) {
+                issues.push(FrontMatterIssue {
+                    key: qualified(key),
+                    message: "required key is missing".to_string(),
+                });
+            }
+        }
+    }
+
+    let properties = schema.get("properties").and_then(Value::as_object);
+    let allow_additional = schema.get("additionalProperties") != Some(&Value::Bool(false));
+
+    for (key, value) in map {
+        match properties.and_then(|p| p.get(key)) {
+            Some(property) => validate_value(&qualified(key), value, property, issues),
+            None if !allow_additional => issues.push(FrontMatterIssue {
+                key: qualified(key),
+                message: "key is not allowed by the schema".to_string(),
+            }),
+            None => {}
+        }
+    }
+}
+
+/// Validate a file's front matter against the workspace schema in
+/// `.ourea/frontmatter.schema.json`. Returns no issues when there is no schema.
+#[tauri::command]
+pub fn validate_front_matter(
+    path: &str,
+    workspace: Option<String>,
+) -> Result<Vec<FrontMatterIssue>, FrontMatterError> {
+    let content = read_document(path)?;
+
+    let start = match &workspace {
+        Some(workspace) => Path::new(workspace).to_path_buf(),
+        None => Path::new(path).parent().unwrap_or(Path::new(".")).to_path_buf(),
+    };
+    let Some(schema_path) = find_workspace_config(&start, SCHEMA_FILE) else {
+        return Ok(Vec::new());
+    };
+
+    let schema: Value = serde_json::from_str(&fs::read_to_string(&schema_path)?)
+        .map_err(|e| FrontMatterError::Schema(e.to_string()))?;
+
+    let data = parse_front_matter(&content)?.map(|f| f.data).unwrap_or_default();
+    let mut issues = Vec::new();
+    validate_object("", &data, &schema, &mut issues);
+
+    Ok(issues)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn updates(value: Value) -> Vec<(String, Value)> {
+        value.as_object().unwrap().clone().into_iter().collect()
+    }
+
+    /// Write `content` to a scratch file, apply `update_front_matter`, and
+    /// return the new file content
+    fn update(name: &str, content: &str, changes: Value) -> String {
+        let path = std::env::temp_dir().join(format!("ourea-frontmatter-{}-{}.md", name, std::process::id()));
+        fs::write(&path, content).unwrap();
+        update_front_matter(path.to_str().unwrap(), changes.as_object().unwrap().clone()).unwrap();
+        let updated = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        updated
+    }
+
+    #[test]
+    fn parses_yaml_and_toml() {
+        let yaml = parse_front_matter("---\ntitle: Hello\ntags: [a, b]\n---\nBody").unwrap().unwrap();
+        assert_eq!(yaml.format, Some(FrontMatterFormat::Yaml));
+        assert_eq!(Value::Object(yaml.data), json!({ "title": "Hello", "tags": ["a", "b"] }));
+        assert_eq!(yaml.body_offset, "---\ntitle: Hello\ntags: [a, b]\n---\n".len());
+
+        let toml = parse_front_matter("+++\ndate = 2024-01-02\ndraft = true\n+++\n").unwrap().unwrap();
+        assert_eq!(Value::Object(toml.data), json!({ "date": "2024-01-02", "draft": true }));
+
+        assert!(parse_front_matter("No front matter\n---\n").unwrap().is_none());
+        assert!(parse_front_matter("---\n- a list\n---\n").is_err());
+    }
+
+    #[test]
+    fn edits_only_changed_yaml_keys() {
+        let raw = "# Site settings\ntitle: 'Quoted'   # keep me\ntags:\n  - a\n  - b\n\n# Dates\ndate: 2024-01-02\ndraft: true\n";
+        let edited = edit_yaml(raw, &updates(json!({ "tags": ["c"], "draft": null, "id": "007" }))).unwrap();
+
+        assert_eq!(
+            edited,
+            "# Site settings\ntitle: 'Quoted'   # keep me\ntags:\n- c\n\n# Dates\ndate: 2024-01-02\nid: '007'\n"
+        );
+        let reparsed: serde_yaml::Value = serde_yaml::from_str(&edited).unwrap();
+        assert_eq!(reparsed["id"], serde_yaml::Value::String("007".into()));
+    }
+
+    #[test]
+    fn edits_only_changed_toml_keys() {
+        let raw = "# Post\ntitle = \"Old\" # heading\ndate = 2024-01-02\n\n[extra]\nkey = 1\n";
+        let edited = edit_toml(raw, &updates(json!({ "title": "New", "published": "2024-02-03" }))).unwrap();
+
+        assert_eq!(
+            edited,
+            "# Post\ntitle = \"New\" # heading\ndate = 2024-01-02\npublished = \"2024-02-03\"\n\n[extra]\nkey = 1\n"
+        );
+    }
+
+    #[test]
+    fn updates_files() {
+        // Unchanged values and removals of missing keys leave the file alone
+        let content = "---\ntitle: \"Hello\"\n...\nBody\n";
+        assert_eq!(update("noop", content, json!({ "title": "Hello", "gone": null })), content);
+        assert_eq!(update("empty", "Body\n", json!({})), "Body\n");
+
+        // The closing fence and body are kept as written
+        assert_eq!(
+            update("yaml", content, json!({ "draft": false })),
+            "---\ntitle: \"Hello\"\ndraft: false\n...\nBody\n"
+        );
+        assert_eq!(update("new", "Body\n", json!({ "title": "Hi" })), "---\ntitle: Hi\n---\n\nBody\n");
+    }
+
+    #[test]
+    fn validates_against_schema() {
+        let schema = json!({
+            "required": ["title"],
+            "additionalProperties": false,
+            "properties": {
+                "date": { "type": "string", "format": "date" },
+                "tags": { "type": "array", "items": { "type": "string" } },
+                "status": { "enum": ["draft", "done"] }
+            }
+        });
+        let data = json!({ "date": "soon", "tags": ["a", 1], "status": "wip", "other": 1 });
+
+        let mut issues = Vec::new();
+        validate_object("", data.as_object().unwrap(), &schema, &mut issues);
+        let keys: Vec<&str> = issues.iter().map(|issue| issue.key.as_str()).collect();
+        assert_eq!(keys, ["title", "date", "other", "status", "tags[1]"]);
+    }
+}
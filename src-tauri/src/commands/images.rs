@@ -0,0 +1,475 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+use super::links::percent_decode;
+use super::optimize::{optimize_image, CONFIG_FILE};
+use super::relink::{encode_destination, relative_path};
+use super::settings::{read_settings, with_workspace_overrides, AppSettings};
+
+/// Where images go when the document has not been saved yet, under app data
+const UNSAVED_IMAGES_DIR: &str = "images";
+
+/// Extensions treated as images
+pub(crate) const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "svg", "bmp", "avif", "ico"];
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedImage {
+    /// Path to insert into the document, relative to it when possible
+    pub markdown_path: String,
+    /// Absolute path of the stored image
+    pub path: String,
+    /// Whether an identical image was already stored and was reused
+    pub reused: bool,
+    /// Size in bytes of the stored image
+    pub size: u64,
+    /// Size in bytes before optimization
+    pub original_size: u64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ImageError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("File not found: {0}")]
+    NotFound(String),
+    #[error("Image data is required as the request body")]
+    MissingInput,
+    #[error("Not a supported image: {0}")]
+    Unsupported(String),
+    #[error("{0}")]
+    AppData(String),
+    #[error("{0}")]
+    Config(String),
+    #[error("Image task failed: {0}")]
+    Task(String),
+}
+
+impl Serialize for ImageError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NamingRule {
+    Original,
+    Timestamp,
+    Uuid,
+    Hash,
+}
+
+impl NamingRule {
+    fn from_setting(value: &str) -> Self {
+        match value {
+            "original" => NamingRule::Original,
+            "uuid" => NamingRule::Uuid,
+            "hash" => NamingRule::Hash,
+            _ => NamingRule::Timestamp,
+        }
+    }
+}
+
+/// Image format from the first bytes of the file
+pub(crate) fn sniff_extension(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("png")
+    } else if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+        Some("jpg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("gif")
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("webp")
+    } else if bytes.starts_with(b"BM") {
+        Some("bmp")
+    } else if bytes.len() >= 12 && &bytes[4..8] == b"ftyp" && matches!(&bytes[8..12], b"avif" | b"avis") {
+        Some("avif")
+    } else if bytes.starts_with(&[0, 0, 1, 0]) {
+        Some("ico")
+    } else {
+        // SVG is text, so look for the root element near the start
+        let head = String::from_utf8_lossy(&bytes[..bytes.len().min(1024)]).to_lowercase();
+        head.contains("<svg").then_some("svg")
+    }
+}
+
+/// Hex SHA-256 of the image content
+pub(crate) fn content_hash(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// A file name stem that is safe on every platform
+fn sanitize_stem(name: &str) -> String {
+    let stem: String = name
+        .chars()
+        .map(|c| if c.is_control() || r#"\/:*?"<>|"#.contains(c) { '_' } else { c })
+        .collect();
+    let stem = stem.trim().trim_matches('.');
+
+    if stem.is_empty() {
+        "image".to_string()
+    } else {
+        stem.to_string()
+    }
+}
+
+pub(crate) fn is_image_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| IMAGE_EXTENSIONS.contains(&e.to_string_lossy().to_lowercase().as_str()))
+}
+
+/// An image in `dir` with the same content, if any
+fn find_duplicate(dir: &Path, bytes: &[u8], hash: &str) -> Option<PathBuf> {
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter(|entry| entry.metadata().is_ok_and(|m| m.is_file() && m.len() == bytes.len() as u64))
+        .map(|entry| entry.path())
+        .filter(|path| is_image_file(path))
+        .find(|path| fs::read(path).is_ok_and(|existing| content_hash(&existing) == hash))
+}
+
+/// First free `stem.ext`, `stem-1.ext`, ... in `dir`
+fn unique_path(dir: &Path, stem: &str, extension: &str) -> PathBuf {
+    let mut path = dir.join(format!("{}.{}", stem, extension));
+    let mut counter = 1;
+
+    while path.exists() {
+        path = dir.join(format!("{}-{}.{}", stem, counter, extension));
+        counter += 1;
+    }
+
+    path
+}
+
+/// Path to write into the document for an image
+fn markdown_path(doc_dir: Option<&Path>, image: &Path) -> String {
+    let path = match doc_dir {
+        Some(dir) if image.starts_with(dir) => format!("./{}", relative_path(dir, image)),
+        _ => image.display().to_string(),
+    };
+    encode_destination(&path)
+}
+
+/// Store an image for a document following the image settings: optimize
+/// it, pick the folder, name it, and reuse an identical image already
+/// stored there.
+/// `name` is the original file name, if known.
+pub(crate) fn store_image(
+    doc_path: Option<&Path>,
+    bytes: &[u8],
+    name: Option<&str>,
+    settings: &AppSettings,
+    unsaved_dir: &Path,
+) -> Result<ImportedImage, ImageError> {
+    let name_extension = name
+        .and_then(|n| Path::new(n).extension())
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .filter(|e| IMAGE_EXTENSIONS.contains(&e.as_str()));
+    let extension = match (sniff_extension(bytes), name_extension) {
+        // Keep `.jpeg` rather than renaming it to `.jpg`
+        (Some("jpg"), Some(given)) if given == "jpeg" => given,
+        (Some(sniffed), _) => sniffed.to_string(),
+        (None, Some(given)) => given,
+        (None, None) => return Err(ImageError::Unsupported(name.unwrap_or("pasted data").to_string())),
+    };
+
+    // Optimization is best effort: the original is stored if it fails
+    let original_size = bytes.len() as u64;
+    let (bytes, extension) = if settings.image_optimization.enabled {
+        match optimize_image(bytes, &extension, &settings.image_optimization) {
+            Ok((optimized, extension)) => (Cow::Owned(optimized), extension),
+            Err(e) => {
+                log::warn!("Failed to optimize image, keeping the original: {}", e);
+                (Cow::Borrowed(bytes), extension)
+            }
+        }
+    } else {
+        (Cow::Borrowed(bytes), extension)
+    };
+
+    let doc_dir = doc_path.and_then(Path::parent);
+    let dir = match doc_dir {
+        None => unsaved_dir.to_path_buf(),
+        Some(dir) if settings.image_storage_location == "relative" => dir.to_path_buf(),
+        // Pasted data has no original location, so "absolute" stores it in assets too
+        Some(dir) => dir.join(&settings.image_assets_folder),
+    };
+    fs::create_dir_all(&dir)?;
+
+    let hash = content_hash(&bytes);
+    if let Some(existing) = find_duplicate(&dir, &bytes, &hash) {
+        return Ok(ImportedImage {
+            markdown_path: markdown_path(doc_dir, &existing),
+            path: existing.display().to_string(),
+            reused: true,
+            size: bytes.len() as u64,
+            original_size,
+        });
+    }
+
+    let stem = match NamingRule::from_setting(&settings.image_naming_rule) {
+        NamingRule::Original => sanitize_stem(name.and_then(|n| Path::new(n).file_stem()?.to_str()).unwrap_or("image")),
+        NamingRule::Timestamp => chrono::Local::now().format("%Y%m%d_%H%M%S").to_string(),
+        NamingRule::Uuid => uuid::Uuid::new_v4().to_string(),
+        NamingRule::Hash => hash[..16].to_string(),
+    };
+    let path = unique_path(&dir, &stem, &extension);
+    fs::write(&path, &bytes)?;
+
+    Ok(ImportedImage {
+        markdown_path: markdown_path(doc_dir, &path),
+        path: path.display().to_string(),
+        reused: false,
+        size: bytes.len() as u64,
+        original_size,
+    })
+}
+
+/// App settings with the workspace `images.toml` of a document applied
+pub(crate) fn document_image_settings(
+    app: &tauri::AppHandle,
+    doc_path: Option<&Path>,
+) -> Result<AppSettings, ImageError> {
+    let mut settings = read_settings(app).unwrap_or_default();
+    let workspace = doc_path.and_then(Path::parent);
+    settings.image_optimization = with_workspace_overrides(&settings.image_optimization, workspace, CONFIG_FILE)
+        .map_err(ImageError::Config)?;
+
+    Ok(settings)
+}
+
+/// Folder for images of documents that haven't been saved yet
+pub(crate) fn unsaved_images_dir(app: &tauri::AppHandle) -> Result<PathBuf, ImageError> {
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| ImageError::AppData(e.to_string()))?
+        .join(UNSAVED_IMAGES_DIR))
+}
+
+/// Import an image file for a document. With the "absolute" storage
+/// location the file is linked where it is instead of copied, and is
+/// reported as not reused since nothing was stored.
+pub(crate) fn import_file(
+    doc_path: Option<&Path>,
+    source: &Path,
+    name: Option<String>,
+    settings: &AppSettings,
+    unsaved_dir: &Path,
+) -> Result<ImportedImage, ImageError> {
+    if !source.is_file() {
+        return Err(ImageError::NotFound(source.display().to_string()));
+    }
+
+    if settings.image_storage_location == "absolute" && doc_path.is_some() {
+        let size = fs::metadata(source)?.len();
+        return Ok(ImportedImage {
+            markdown_path: encode_destination(&source.display().to_string()),
+            path: source.display().to_string(),
+            reused: false,
+            size,
+            original_size: size,
+        });
+    }
+
+    let name = name.or_else(|| source.file_name().map(|n| n.to_string_lossy().to_string()));
+    store_image(doc_path, &fs::read(source)?, name.as_deref(), settings, unsaved_dir)
+}
+
+/// A percent-encoded request header, if present
+fn header(request: &tauri::ipc::Request<'_>, name: &str) -> Option<String> {
+    let value = request.headers().get(name)?.to_str().ok()?;
+    Some(percent_decode(value))
+}
+
+/// Run an import off the main thread, since optimizing can take seconds
+async fn run_blocking(
+    task: impl FnOnce() -> Result<ImportedImage, ImageError> + Send + 'static,
+) -> Result<ImportedImage, ImageError> {
+    tauri::async_runtime::spawn_blocking(task)
+        .await
+        .map_err(|e| ImageError::Task(e.to_string()))?
+}
+
+/// Import an image file for a document, applying the configured storage
+/// location and naming rule. Returns the path to insert into the Markdown.
+#[tauri::command]
+pub async fn import_image(
+    app: tauri::AppHandle,
+    doc_path: Option<String>,
+    source_path: String,
+    file_name: Option<String>,
+) -> Result<ImportedImage, ImageError> {
+    let settings = document_image_settings(&app, doc_path.as_deref().map(Path::new))?;
+    let unsaved_dir = unsaved_images_dir(&app)?;
+
+    run_blocking(move || {
+        let doc_path = doc_path.as_deref().map(Path::new);
+        import_file(doc_path, Path::new(&source_path), file_name, &settings, &unsaved_dir)
+    })
+    .await
+}
+
+/// Import image data for a document. The raw bytes are the request body so
+/// they aren't serialized as a JSON array; the document path and file name
+/// come in the percent-encoded `doc-path` and `file-name` headers.
+#[tauri::command]
+pub async fn import_image_data(
+    app: tauri::AppHandle,
+    request: tauri::ipc::Request<'_>,
+) -> Result<ImportedImage, ImageError> {
+    let tauri::ipc::InvokeBody::Raw(bytes) = request.body() else {
+        return Err(ImageError::MissingInput);
+    };
+    let bytes = bytes.clone();
+    let doc_path = header(&request, "doc-path");
+    let file_name = header(&request, "file-name");
+    let settings = document_image_settings(&app, doc_path.as_deref().map(Path::new))?;
+    let unsaved_dir = unsaved_images_dir(&app)?;
+
+    run_blocking(move || {
+        let doc_path = doc_path.as_deref().map(Path::new);
+        store_image(doc_path, &bytes, file_name.as_deref(), &settings, &unsaved_dir)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::optimize::OutputFormat;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\nfirst";
+    const OTHER_PNG: &[u8] = b"\x89PNG\r\n\x1a\nsecond";
+
+    struct Folder(PathBuf);
+
+    impl Folder {
+        fn new(name: &str) -> Self {
+            let root = std::env::temp_dir().join(format!("ourea-images-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&root);
+            fs::create_dir_all(&root).unwrap();
+            Self(root)
+        }
+    }
+
+    impl Drop for Folder {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn settings(location: &str, naming: &str) -> AppSettings {
+        AppSettings {
+            image_storage_location: location.to_string(),
+            image_naming_rule: naming.to_string(),
+            ..AppSettings::default()
+        }
+    }
+
+    fn stored_name(image: &ImportedImage) -> String {
+        Path::new(&image.path).file_name().unwrap().to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn names_images_by_rule() {
+        let folder = Folder::new("naming");
+        let doc = folder.0.join("note.md");
+        let unsaved = folder.0.join("unsaved");
+        let store = |naming: &str, bytes: &[u8]| {
+            store_image(Some(&doc), bytes, Some("My: shot?.png"), &settings("relative", naming), &unsaved).unwrap()
+        };
+
+        assert_eq!(stored_name(&store("original", PNG)), "My_ shot_.png");
+
+        let hash = stored_name(&store("hash", OTHER_PNG));
+        assert_eq!(hash, format!("{}.png", &content_hash(OTHER_PNG)[..16]));
+
+        let uuid = stored_name(&store("uuid", b"\x89PNG\r\n\x1a\nthird"));
+        assert!(uuid::Uuid::parse_str(uuid.trim_end_matches(".png")).is_ok());
+
+        let timestamp = stored_name(&store("timestamp", b"\x89PNG\r\n\x1a\nfourth"));
+        assert!(chrono::NaiveDateTime::parse_from_str(timestamp.trim_end_matches(".png"), "%Y%m%d_%H%M%S").is_ok());
+    }
+
+    #[test]
+    fn reuses_identical_images_and_suffixes_name_clashes() {
+        let folder = Folder::new("dedup");
+        let doc = folder.0.join("note.md");
+        let unsaved = folder.0.join("unsaved");
+        let settings = settings("relative", "original");
+
+        let first = store_image(Some(&doc), PNG, Some("shot.png"), &settings, &unsaved).unwrap();
+        assert!(!first.reused);
+        assert_eq!(first.markdown_path, "./shot.png");
+
+        let again = store_image(Some(&doc), PNG, Some("other.png"), &settings, &unsaved).unwrap();
+        assert!(again.reused);
+        assert_eq!(again.path, first.path);
+
+        let clash = store_image(Some(&doc), OTHER_PNG, Some("shot.png"), &settings, &unsaved).unwrap();
+        assert!(!clash.reused);
+        assert_eq!(stored_name(&clash), "shot-1.png");
+        assert_eq!(fs::read(&clash.path).unwrap(), OTHER_PNG);
+    }
+
+    #[test]
+    fn places_images_by_storage_location() {
+        let folder = Folder::new("placement");
+        let doc = folder.0.join("notes").join("note.md");
+        let unsaved = folder.0.join("unsaved");
+
+        let relative = store_image(Some(&doc), PNG, Some("a.png"), &settings("relative", "original"), &unsaved).unwrap();
+        assert_eq!(Path::new(&relative.path), folder.0.join("notes").join("a.png"));
+
+        let assets = store_image(Some(&doc), OTHER_PNG, Some("b.png"), &settings("assets", "original"), &unsaved).unwrap();
+        assert_eq!(Path::new(&assets.path), folder.0.join("notes").join("assets").join("b.png"));
+        assert_eq!(assets.markdown_path, "./assets/b.png");
+
+        let unsaved_image = store_image(None, PNG, Some("c.png"), &settings("relative", "original"), &unsaved).unwrap();
+        assert_eq!(Path::new(&unsaved_image.path), unsaved.join("c.png"));
+
+        // Linked in place rather than copied
+        let source = folder.0.join("elsewhere.png");
+        fs::write(&source, PNG).unwrap();
+        let linked = import_file(Some(&doc), &source, None, &settings("absolute", "original"), &unsaved).unwrap();
+        assert_eq!((Path::new(&linked.path), linked.reused), (source.as_path(), false));
+    }
+
+    #[test]
+    fn trusts_content_over_the_file_extension() {
+        let folder = Folder::new("sniff");
+        let doc = folder.0.join("note.md");
+        let settings = settings("relative", "original");
+        let unsaved = folder.0.join("unsaved");
+
+        let image = store_image(Some(&doc), PNG, Some("photo.jpg"), &settings, &unsaved).unwrap();
+        assert_eq!(stored_name(&image), "photo.png");
+
+        let jpeg = store_image(Some(&doc), &[0xff, 0xd8, 0xff, 0xe0], Some("scan.jpeg"), &settings, &unsaved).unwrap();
+        assert_eq!(stored_name(&jpeg), "scan.jpeg");
+
+        assert!(store_image(Some(&doc), b"plain text", Some("notes.txt"), &settings, &unsaved).is_err());
+    }
+
+    #[test]
+    fn keeps_the_original_when_optimizing_fails() {
+        let folder = Folder::new("optimize");
+        let doc = folder.0.join("note.md");
+        let mut settings = settings("relative", "original");
+        settings.image_optimization.enabled = true;
+        settings.image_optimization.format = OutputFormat::Webp;
+
+        let image = store_image(Some(&doc), PNG, Some("broken.png"), &settings, &folder.0.join("unsaved")).unwrap();
+        assert_eq!(stored_name(&image), "broken.png");
+        assert_eq!((fs::read(&image.path).unwrap().as_slice(), image.size), (PNG, PNG.len() as u64));
+    }
+}
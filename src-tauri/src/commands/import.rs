@@ -0,0 +1,1688 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use scraper::{ElementRef, Html, Node};
+use serde::{Serialize, Serializer};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use super::images::{document_image_settings, store_image, unsaved_images_dir, ImageError};
+use super::settings::AppSettings;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedDocument {
+    pub title: String,
+    pub content: String,
+    /// Absolute paths of the images extracted from the document
+    pub images: Vec<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ImportError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("File not found: {0}")]
+    NotFound(String),
+    #[error("Unsupported format: {0}")]
+    Unsupported(String),
+    #[error("Failed to parse document: {0}")]
+    Parse(String),
+    #[error("Failed to store image: {0}")]
+    Image(#[from] ImageError),
+    #[error("Import task failed: {0}")]
+    Task(String),
+}
+
+impl Serialize for ImportError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl From<zip::result::ZipError> for ImportError {
+    fn from(e: zip::result::ZipError) -> Self {
+        ImportError::Parse(e.to_string())
+    }
+}
+
+impl From<roxmltree::Error> for ImportError {
+    fn from(e: roxmltree::Error) -> Self {
+        ImportError::Parse(e.to_string())
+    }
+}
+
+/// Inline content shared by every importer
+#[derive(Debug, Clone)]
+enum Inline {
+    Text(String),
+    Bold(Vec<Inline>),
+    Italic(Vec<Inline>),
+    Strike(Vec<Inline>),
+    Code(String),
+    Link { url: String, content: Vec<Inline> },
+    Image { src: String, alt: String },
+    LineBreak,
+}
+
+/// Block content shared by every importer
+#[derive(Debug)]
+enum Block {
+    Heading(usize, Vec<Inline>),
+    Paragraph(Vec<Inline>),
+    ListItem {
+        level: usize,
+        ordered: bool,
+        content: Vec<Inline>,
+    },
+    Table(Vec<Vec<Vec<Inline>>>),
+    Code(String),
+    Quote(Vec<Block>),
+    Rule,
+}
+
+/// Append an inline, merging it into the previous one when both have the
+/// same formatting so runs like `**a****b**` come out as `**ab**`
+fn push_inline(inlines: &mut Vec<Inline>, inline: Inline) {
+    match (inlines.last_mut(), inline) {
+        (Some(Inline::Text(last)), Inline::Text(text)) => last.push_str(&text),
+        (Some(Inline::Code(last)), Inline::Code(text)) => last.push_str(&text),
+        (Some(Inline::Bold(last)), Inline::Bold(content))
+        | (Some(Inline::Italic(last)), Inline::Italic(content))
+        | (Some(Inline::Strike(last)), Inline::Strike(content)) => {
+            for inline in content {
+                push_inline(last, inline);
+            }
+        }
+        (_, inline) => inlines.push(inline),
+    }
+}
+
+/// Wrap a text run in the enabled formatting
+fn styled_text(text: &str, bold: bool, italic: bool, strike: bool) -> Inline {
+    let mut inline = Inline::Text(text.to_string());
+    if strike {
+        inline = Inline::Strike(vec![inline]);
+    }
+    if italic {
+        inline = Inline::Italic(vec![inline]);
+    }
+    if bold {
+        inline = Inline::Bold(vec![inline]);
+    }
+    inline
+}
+
+/// Escape characters that would otherwise be read as Markdown syntax
+fn escape_markdown(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut escaped = String::with_capacity(text.len());
+
+    for (i, &c) in chars.iter().enumerate() {
+        match c {
+            '\\' | '*' | '`' | '[' | ']' | '<' => escaped.push('\\'),
+            '_' => {
+                let inside_word = i > 0
+                    && chars[i - 1].is_alphanumeric()
+                    && chars.get(i + 1).is_some_and(|n| n.is_alphanumeric());
+                if !inside_word {
+                    escaped.push('\\');
+                }
+            }
+            _ => {}
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+/// Put emphasis markers around content, keeping surrounding spaces outside
+fn wrap_markers(marker: &str, content: &[Inline]) -> String {
+    let text = render_inlines(content);
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return text;
+    }
+
+    let lead = &text[..text.len() - text.trim_start().len()];
+    let trail = &text[text.trim_end().len()..];
+    format!("{}{}{}{}{}", lead, marker, trimmed, marker, trail)
+}
+
+fn render_inlines(inlines: &[Inline]) -> String {
+    let mut out = String::new();
+
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => out.push_str(&escape_markdown(text)),
+            Inline::Bold(content) => out.push_str(&wrap_markers("**", content)),
+            Inline::Italic(content) => out.push_str(&wrap_markers("*", content)),
+            Inline::Strike(content) => out.push_str(&wrap_markers("~~", content)),
+            Inline::Code(code) => {
+                let fence = if code.contains('`') { "``" } else { "`" };
+                out.push_str(&format!("{}{}{}", fence, code, fence));
+            }
+            Inline::Link { url, content } => {
+                let text = render_inlines(content);
+                if text.trim().is_empty() {
+                    out.push_str(&format!("<{}>", url));
+                } else {
+                    out.push_str(&format!("[{}]({})", text.trim(), markdown_url(url)));
+                }
+            }
+            Inline::Image { src, alt } => {
+                out.push_str(&format!("![{}]({})", escape_markdown(alt), markdown_url(src)));
+            }
+            Inline::LineBreak => out.push_str("  \n"),
+        }
+    }
+
+    out
+}
+
+/// Wrap link targets containing spaces in angle brackets
+fn markdown_url(url: &str) -> String {
+    if url.contains(' ') {
+        format!("<{}>", url)
+    } else {
+        url.to_string()
+    }
+}
+
+/// Render inline content as a paragraph, escaping markers that would start a block
+fn render_paragraph(inlines: &[Inline]) -> String {
+    let text = render_inlines(inlines);
+    let text = text.trim();
+
+    let starts_block = text.starts_with('#')
+        || text.starts_with('>')
+        || text.starts_with("- ")
+        || text.starts_with("+ ")
+        || text
+            .split_once(". ")
+            .is_some_and(|(n, _)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+
+    if starts_block {
+        format!("\\{}", text)
+    } else {
+        text.to_string()
+    }
+}
+
+fn render_table(rows: &[Vec<Vec<Inline>>]) -> String {
+    let columns = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    if columns == 0 {
+        return String::new();
+    }
+
+    let render_row = |row: &Vec<Vec<Inline>>| {
+        let cells: Vec<String> = (0..columns)
+            .map(|i| {
+                row.get(i)
+                    .map(|cell| {
+                        render_inlines(cell)
+                            .trim()
+                            .replace('|', "\\|")
+                            .replace("  \n", "<br>")
+                            .replace('\n', " ")
+                    })
+                    .unwrap_or_default()
+            })
+            .collect();
+        format!("| {} |", cells.join(" | "))
+    };
+
+    let mut lines = vec![render_row(&rows[0])];
+    lines.push(format!("|{}", " --- |".repeat(columns)));
+    lines.extend(rows[1..].iter().map(render_row));
+    lines.join("\n")
+}
+
+fn render_blocks(blocks: &[Block]) -> String {
+    let mut out = String::new();
+    let mut previous_was_list = false;
+
+    for block in blocks {
+        let is_list = matches!(block, Block::ListItem { .. });
+        let rendered = match block {
+            Block::Heading(level, content) => {
+                // Headings styled bold as a whole don't need the extra markers
+                let content = match content.as_slice() {
+                    [Inline::Bold(inner)] => inner,
+                    _ => content,
+                };
+                let text = render_inlines(content);
+                if text.trim().is_empty() {
+                    continue;
+                }
+                format!("{} {}", "#".repeat((*level).clamp(1, 6)), text.trim())
+            }
+            Block::Paragraph(content) => {
+                let text = render_paragraph(content);
+                if text.is_empty() {
+                    continue;
+                }
+                text
+            }
+            Block::ListItem { level, ordered, content } => {
+                let marker = if *ordered { "1." } else { "-" };
+                format!("{}{} {}", "   ".repeat(*level), marker, render_inlines(content).trim())
+            }
+            Block::Table(rows) => render_table(rows),
+            Block::Code(code) => {
+                let fence = if code.contains("```") { "~~~" } else { "```" };
+                format!("{}\n{}\n{}", fence, code.trim_end_matches('\n'), fence)
+            }
+            Block::Quote(content) => render_blocks(content)
+                .lines()
+                .map(|line| if line.is_empty() { ">".to_string() } else { format!("> {}", line) })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Block::Rule => "---".to_string(),
+        };
+
+        if !out.is_empty() {
+            out.push_str(if is_list && previous_was_list { "\n" } else { "\n\n" });
+        }
+        out.push_str(&rendered);
+        previous_was_list = is_list;
+    }
+
+    out
+}
+
+/// Stores images found in an imported document through the image
+/// pipeline. The result opens as an unsaved tab, so they go where images of
+/// unsaved documents go and are linked by absolute path.
+struct AssetWriter {
+    settings: AppSettings,
+    unsaved_dir: PathBuf,
+    stem: String,
+    written: Vec<String>,
+}
+
+impl AssetWriter {
+    fn new(source: &Path, settings: AppSettings, unsaved_dir: PathBuf) -> Self {
+        let stem = source
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "imported".to_string());
+
+        Self {
+            settings,
+            unsaved_dir,
+            stem,
+            written: Vec::new(),
+        }
+    }
+
+    /// Store image bytes and return the path to use in the Markdown
+    fn save(&mut self, bytes: &[u8], ext: &str) -> Result<String, ImportError> {
+        let ext = ext.trim_start_matches('.').to_lowercase();
+        let name = format!("{}-image{}.{}", self.stem, self.written.len() + 1, ext);
+        let image = store_image(None, bytes, Some(&name), &self.settings, &self.unsaved_dir)?;
+
+        if !self.written.contains(&image.path) {
+            self.written.push(image.path);
+        }
+        Ok(image.markdown_path)
+    }
+}
+
+/// Collapse HTML whitespace runs into single spaces
+fn collapse_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut last_space = false;
+
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !last_space {
+                out.push(' ');
+            }
+            last_space = true;
+        } else {
+            out.push(c);
+            last_space = false;
+        }
+    }
+
+    out
+}
+
+struct HtmlImporter<'a> {
+    assets: &'a mut AssetWriter,
+}
+
+impl HtmlImporter<'_> {
+    fn image(&mut self, element: ElementRef) -> Option<Inline> {
+        let src = element.value().attr("src")?;
+        let alt = element.value().attr("alt").unwrap_or_default().to_string();
+
+        // Extract embedded data URIs; leave file and web references alone
+        let src = match src.strip_prefix("data:") {
+            Some(data) => {
+                let (meta, payload) = data.split_once(',')?;
+                let ext = meta
+                    .split(';')
+                    .next()
+                    .and_then(|mime| mime.strip_prefix("image/"))
+                    .map(|ext| match ext {
+                        "jpeg" => "jpg",
+                        "svg+xml" => "svg",
+                        other => other,
+                    })?;
+                let bytes = STANDARD.decode(payload.trim()).ok()?;
+                self.assets.save(&bytes, ext).ok()?
+            }
+            None => src.to_string(),
+        };
+
+        Some(Inline::Image { src, alt })
+    }
+
+    fn inlines(&mut self, element: ElementRef) -> Vec<Inline> {
+        let mut inlines = Vec::new();
+
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => push_inline(&mut inlines, Inline::Text(collapse_whitespace(text))),
+                Node::Element(_) => {
+                    let child = ElementRef::wrap(child).unwrap();
+                    match child.value().name() {
+                        "script" | "style" | "ul" | "ol" | "template" => {}
+                        "p" | "div" => {
+                            if !inlines.is_empty() {
+                                inlines.push(Inline::LineBreak);
+                            }
+                            for inline in self.inlines(child) {
+                                push_inline(&mut inlines, inline);
+                            }
+                        }
+                        _ => {
+                            for inline in self.inline_element(child) {
+                                push_inline(&mut inlines, inline);
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        inlines
+    }
+
+    fn list(&mut self, element: ElementRef, ordered: bool, level: usize, blocks: &mut Vec<Block>) {
+        for item in element.children().filter_map(ElementRef::wrap) {
+            if item.value().name() != "li" {
+                continue;
+            }
+
+            blocks.push(Block::ListItem {
+                level,
+                ordered,
+                content: self.inlines(item),
+            });
+
+            for nested in item.children().filter_map(ElementRef::wrap) {
+                match nested.value().name() {
+                    "ul" => self.list(nested, false, level + 1, blocks),
+                    "ol" => self.list(nested, true, level + 1, blocks),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn table(&mut self, element: ElementRef) -> Block {
+        // Only this table's own rows; nested tables end up as cell text
+        let rows: Vec<ElementRef> = element
+            .children()
+            .filter_map(ElementRef::wrap)
+            .flat_map(|child| match child.value().name() {
+                "thead" | "tbody" | "tfoot" => child.children().filter_map(ElementRef::wrap).collect(),
+                _ => vec![child],
+            })
+            .filter(|row| row.value().name() == "tr")
+            .collect();
+        let rows = rows
+            .into_iter()
+            .map(|row| {
+                row.children()
+                    .filter_map(ElementRef::wrap)
+                    .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+                    .map(|cell| self.inlines(cell))
+                    .collect()
+            })
+            .collect();
+
+        Block::Table(rows)
+    }
+
+    fn blocks(&mut self, element: ElementRef, blocks: &mut Vec<Block>) {
+        let mut pending: Vec<Inline> = Vec::new();
+
+        let flush = |pending: &mut Vec<Inline>, blocks: &mut Vec<Block>| {
+            let is_blank = pending
+                .iter()
+                .all(|inline| matches!(inline, Inline::Text(text) if text.trim().is_empty()));
+            if !is_blank {
+                blocks.push(Block::Paragraph(std::mem::take(pending)));
+            }
+            pending.clear();
+        };
+
+        for child in element.children() {
+            let child_element = match child.value() {
+                Node::Text(text) => {
+                    push_inline(&mut pending, Inline::Text(collapse_whitespace(text)));
+                    continue;
+                }
+                Node::Element(_) => ElementRef::wrap(child).unwrap(),
+                _ => continue,
+            };
+
+            let name = child_element.value().name();
+            match name {
+                "script" | "style" | "head" | "title" | "meta" | "link" | "noscript" | "template" => {}
+                "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                    flush(&mut pending, blocks);
+                    let level = name[1..].parse().unwrap_or(1);
+                    blocks.push(Block::Heading(level, self.inlines(child_element)));
+                }
+                "p" => {
+                    flush(&mut pending, blocks);
+                    blocks.push(Block::Paragraph(self.inlines(child_element)));
+                }
+                "ul" | "ol" => {
+                    flush(&mut pending, blocks);
+                    self.list(child_element, name == "ol", 0, blocks);
+                }
+                "table" => {
+                    flush(&mut pending, blocks);
+                    blocks.push(self.table(child_element));
+                }
+                "pre" => {
+                    flush(&mut pending, blocks);
+                    blocks.push(Block::Code(child_element.text().collect()));
+                }
+                "blockquote" => {
+                    flush(&mut pending, blocks);
+                    let mut quoted = Vec::new();
+                    self.blocks(child_element, &mut quoted);
+                    blocks.push(Block::Quote(quoted));
+                }
+                "hr" => {
+                    flush(&mut pending, blocks);
+                    blocks.push(Block::Rule);
+                }
+                "html" | "body" | "div" | "section" | "article" | "main" | "header" | "footer"
+                | "nav" | "aside" | "figure" | "figcaption" | "dl" | "dt" | "dd" | "form"
+                | "fieldset" | "details" | "summary" | "center" => {
+                    flush(&mut pending, blocks);
+                    self.blocks(child_element, blocks);
+                }
+                _ => {
+                    // Inline content at block level collects into an implicit paragraph
+                    for inline in self.inline_element(child_element) {
+                        push_inline(&mut pending, inline);
+                    }
+                }
+            }
+        }
+
+        flush(&mut pending, blocks);
+    }
+
+    /// Convert a single inline element (as opposed to its children)
+    fn inline_element(&mut self, element: ElementRef) -> Vec<Inline> {
+        match element.value().name() {
+            "img" => self.image(element).into_iter().collect(),
+            "br" => vec![Inline::LineBreak],
+            "strong" | "b" => vec![Inline::Bold(self.inlines(element))],
+            "em" | "i" => vec![Inline::Italic(self.inlines(element))],
+            "s" | "del" | "strike" => vec![Inline::Strike(self.inlines(element))],
+            "code" | "kbd" | "samp" | "tt" => vec![Inline::Code(element.text().collect())],
+            "a" => {
+                let content = self.inlines(element);
+                match element.value().attr("href") {
+                    Some(url) => vec![Inline::Link { url: url.to_string(), content }],
+                    None => content,
+                }
+            }
+            _ => self.inlines(element),
+        }
+    }
+}
+
+fn import_html(source: &str, assets: &mut AssetWriter) -> Vec<Block> {
+    let document = Html::parse_document(source);
+    let mut blocks = Vec::new();
+    HtmlImporter { assets }.blocks(document.root_element(), &mut blocks);
+    blocks
+}
+
+/// Find an attribute by local name, ignoring its namespace
+fn xml_attr<'a>(node: roxmltree::Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.attributes().find(|a| a.name() == name).map(|a| a.value())
+}
+
+fn xml_child<'a, 'input>(
+    node: roxmltree::Node<'a, 'input>,
+    name: &str,
+) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children().find(|c| c.tag_name().name() == name)
+}
+
+fn read_zip_entry(archive: &mut zip::ZipArchive<fs::File>, name: &str) -> Option<Vec<u8>> {
+    let mut entry = archive.by_name(name).ok()?;
+    let mut bytes = Vec::new();
+    entry.read_to_end(&mut bytes).ok()?;
+    Some(bytes)
+}
+
+fn read_zip_string(archive: &mut zip::ZipArchive<fs::File>, name: &str) -> Option<String> {
+    read_zip_entry(archive, name).map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn is_monospace_font(font: &str) -> bool {
+    let font = font.to_lowercase();
+    ["courier", "consolas", "mono", "menlo", "monaco"]
+        .iter()
+        .any(|name| font.contains(name))
+}
+
+struct DocxImporter<'a> {
+    archive: zip::ZipArchive<fs::File>,
+    assets: &'a mut AssetWriter,
+    relationships: HashMap<String, String>,
+    heading_styles: HashMap<String, usize>,
+    /// numId -> (ilvl -> is ordered)
+    numbering: HashMap<String, HashMap<String, bool>>,
+}
+
+impl DocxImporter<'_> {
+    fn load_relationships(&mut self) -> Result<(), ImportError> {
+        let Some(xml) = read_zip_string(&mut self.archive, "word/_rels/document.xml.rels") else {
+            return Ok(());
+        };
+        let doc = roxmltree::Document::parse(&xml)?;
+
+        for rel in doc.descendants().filter(|n| n.tag_name().name() == "Relationship") {
+            if let (Some(id), Some(target)) = (xml_attr(rel, "Id"), xml_attr(rel, "Target")) {
+                self.relationships.insert(id.to_string(), target.to_string());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn load_styles(&mut self) -> Result<(), ImportError> {
+        let Some(xml) = read_zip_string(&mut self.archive, "word/styles.xml") else {
+            return Ok(());
+        };
+        let doc = roxmltree::Document::parse(&xml)?;
+
+        for style in doc.descendants().filter(|n| n.tag_name().name() == "style") {
+            let Some(id) = xml_attr(style, "styleId") else {
+                continue;
+            };
+            let name = xml_child(style, "name")
+                .and_then(|n| xml_attr(n, "val"))
+                .unwrap_or(id)
+                .to_lowercase();
+
+            let level = if name == "title" {
+                Some(1)
+            } else {
+                name.strip_prefix("heading ").and_then(|n| n.trim().parse().ok())
+            };
+            if let Some(level) = level {
+                self.heading_styles.insert(id.to_string(), level);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn load_numbering(&mut self) -> Result<(), ImportError> {
+        let Some(xml) = read_zip_string(&mut self.archive, "word/numbering.xml") else {
+            return Ok(());
+        };
+        let doc = roxmltree::Document::parse(&xml)?;
+
+        let mut abstract_levels: HashMap<String, HashMap<String, bool>> = HashMap::new();
+        for abstract_num in doc.descendants().filter(|n| n.tag_name().name() == "abstractNum") {
+            let Some(id) = xml_attr(abstract_num, "abstractNumId") else {
+                continue;
+            };
+            let levels = abstract_num
+                .children()
+                .filter(|n| n.tag_name().name() == "lvl")
+                .filter_map(|lvl| {
+                    let ilvl = xml_attr(lvl, "ilvl")?;
+                    let format = xml_child(lvl, "numFmt").and_then(|f| xml_attr(f, "val"))?;
+                    Some((ilvl.to_string(), !matches!(format, "bullet" | "none")))
+                })
+                .collect();
+            abstract_levels.insert(id.to_string(), levels);
+        }
+
+        for num in doc.descendants().filter(|n| n.tag_name().name() == "num") {
+            let abstract_id = xml_child(num, "abstractNumId").and_then(|n| xml_attr(n, "val"));
+            if let (Some(id), Some(abstract_id)) = (xml_attr(num, "numId"), abstract_id) {
+                if let Some(levels) = abstract_levels.get(abstract_id) {
+                    self.numbering.insert(id.to_string(), levels.clone());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn drawing(&mut self, node: roxmltree::Node) -> Option<Inline> {
+        let blip = node
+            .descendants()
+            .find(|n| matches!(n.tag_name().name(), "blip" | "imagedata"))?;
+        let rel_id = xml_attr(blip, "embed").or_else(|| xml_attr(blip, "id"))?;
+        let target = self.relationships.get(rel_id)?.clone();
+
+        let entry = format!("word/{}", target.trim_start_matches('/').trim_start_matches("word/"));
+        let bytes = read_zip_entry(&mut self.archive, &entry)?;
+        let ext = Path::new(&target).extension()?.to_string_lossy().to_string();
+        let src = self.assets.save(&bytes, &ext).ok()?;
+
+        let alt = node
+            .descendants()
+            .find(|n| n.tag_name().name() == "docPr")
+            .and_then(|n| xml_attr(n, "descr").or_else(|| xml_attr(n, "title")))
+            .unwrap_or_default()
+            .to_string();
+
+        Some(Inline::Image { src, alt })
+    }
+
+    fn run(&mut self, run: roxmltree::Node, inlines: &mut Vec<Inline>) {
+        let properties = xml_child(run, "rPr");
+        let flag = |name: &str| {
+            properties
+                .and_then(|p| xml_child(p, name))
+                .is_some_and(|n| !matches!(xml_attr(n, "val"), Some("0" | "false" | "none")))
+        };
+        let bold = flag("b");
+        let italic = flag("i");
+        let strike = flag("strike") || flag("dstrike");
+        let monospace = properties
+            .and_then(|p| xml_child(p, "rFonts"))
+            .and_then(|f| xml_attr(f, "ascii"))
+            .is_some_and(is_monospace_font);
+
+        for child in run.children() {
+            match child.tag_name().name() {
+                "t" => {
+                    let text = child.text().unwrap_or_default();
+                    let inline = if monospace {
+                        Inline::Code(text.to_string())
+                    } else {
+                        styled_text(text, bold, italic, strike)
+                    };
+                    push_inline(inlines, inline);
+                }
+                "tab" => push_inline(inlines, Inline::Text(" ".to_string())),
+                "br" | "cr" => inlines.push(Inline::LineBreak),
+                "drawing" | "pict" | "object" => {
+                    if let Some(image) = self.drawing(child) {
+                        inlines.push(image);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn paragraph_inlines(&mut self, paragraph: roxmltree::Node) -> Vec<Inline> {
+        let mut inlines = Vec::new();
+
+        for child in paragraph.children() {
+            match child.tag_name().name() {
+                "r" => self.run(child, &mut inlines),
+                "hyperlink" => {
+                    let url = xml_attr(child, "id")
+                        .and_then(|id| self.relationships.get(id).cloned())
+                        .or_else(|| xml_attr(child, "anchor").map(|a| format!("#{}", a)));
+                    let content = self.paragraph_inlines(child);
+                    match url {
+                        Some(url) => inlines.push(Inline::Link { url, content }),
+                        None => inlines.extend(content),
+                    }
+                }
+                // Tracked insertions, smart tags and content controls wrap ordinary runs
+                "ins" | "smartTag" | "sdt" | "sdtContent" | "fldSimple" => {
+                    for inline in self.paragraph_inlines(child) {
+                        push_inline(&mut inlines, inline);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        inlines
+    }
+
+    fn paragraph(&mut self, paragraph: roxmltree::Node) -> Block {
+        let properties = xml_child(paragraph, "pPr");
+        let content = self.paragraph_inlines(paragraph);
+
+        let style = properties
+            .and_then(|p| xml_child(p, "pStyle"))
+            .and_then(|s| xml_attr(s, "val"));
+        if let Some(level) = style.and_then(|s| self.heading_styles.get(s)) {
+            return Block::Heading(*level, content);
+        }
+
+        if let Some(numbering) = properties.and_then(|p| xml_child(p, "numPr")) {
+            let level = xml_child(numbering, "ilvl")
+                .and_then(|n| xml_attr(n, "val"))
+                .unwrap_or("0");
+            let ordered = xml_child(numbering, "numId")
+                .and_then(|n| xml_attr(n, "val"))
+                .and_then(|id| self.numbering.get(id))
+                .and_then(|levels| levels.get(level))
+                .copied()
+                .unwrap_or(false);
+
+            return Block::ListItem {
+                level: level.parse().unwrap_or(0),
+                ordered,
+                content,
+            };
+        }
+
+        Block::Paragraph(content)
+    }
+
+    fn table(&mut self, table: roxmltree::Node) -> Block {
+        let mut rows = Vec::new();
+
+        for row in table.children().filter(|n| n.tag_name().name() == "tr") {
+            let mut cells = Vec::new();
+            for cell in row.children().filter(|n| n.tag_name().name() == "tc") {
+                let mut content = Vec::new();
+                for paragraph in cell.children().filter(|n| n.tag_name().name() == "p") {
+                    if !content.is_empty() {
+                        content.push(Inline::LineBreak);
+                    }
+                    content.extend(self.paragraph_inlines(paragraph));
+                }
+                cells.push(content);
+            }
+            rows.push(cells);
+        }
+
+        Block::Table(rows)
+    }
+
+    fn body(&mut self, node: roxmltree::Node, blocks: &mut Vec<Block>) {
+        for child in node.children() {
+            match child.tag_name().name() {
+                "p" => blocks.push(self.paragraph(child)),
+                "tbl" => blocks.push(self.table(child)),
+                "sdt" | "sdtContent" => self.body(child, blocks),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn import_docx(path: &Path, assets: &mut AssetWriter) -> Result<Vec<Block>, ImportError> {
+    let archive = zip::ZipArchive::new(fs::File::open(path)?)?;
+    let mut importer = DocxImporter {
+        archive,
+        assets,
+        relationships: HashMap::new(),
+        heading_styles: HashMap::new(),
+        numbering: HashMap::new(),
+    };
+    importer.load_relationships()?;
+    importer.load_styles()?;
+    importer.load_numbering()?;
+
+    let xml = read_zip_string(&mut importer.archive, "word/document.xml")
+        .ok_or_else(|| ImportError::Parse("missing word/document.xml".to_string()))?;
+    let doc = roxmltree::Document::parse(&xml)?;
+
+    let mut blocks = Vec::new();
+    if let Some(body) = doc.descendants().find(|n| n.tag_name().name() == "body") {
+        importer.body(body, &mut blocks);
+    }
+
+    Ok(blocks)
+}
+
+#[derive(Default, Clone, Copy)]
+struct OdtTextStyle {
+    bold: bool,
+    italic: bool,
+    strike: bool,
+    monospace: bool,
+}
+
+struct OdtImporter<'a> {
+    archive: zip::ZipArchive<fs::File>,
+    assets: &'a mut AssetWriter,
+    text_styles: HashMap<String, OdtTextStyle>,
+    /// list style name -> (level -> is ordered)
+    list_styles: HashMap<String, HashMap<usize, bool>>,
+}
+
+impl OdtImporter<'_> {
+    fn load_styles(&mut self, doc: &roxmltree::Document) {
+        for style in doc.descendants().filter(|n| n.tag_name().name() == "style") {
+            let (Some(name), Some(props)) = (xml_attr(style, "name"), xml_child(style, "text-properties")) else {
+                continue;
+            };
+            let text_style = OdtTextStyle {
+                bold: xml_attr(props, "font-weight").is_some_and(|w| w == "bold" || w.parse::<u32>().is_ok_and(|w| w >= 600)),
+                italic: xml_attr(props, "font-style").is_some_and(|s| s == "italic" || s == "oblique"),
+                strike: xml_attr(props, "text-line-through-style").is_some_and(|s| s != "none"),
+                monospace: xml_attr(props, "font-name").is_some_and(is_monospace_font),
+            };
+            self.text_styles.insert(name.to_string(), text_style);
+        }
+
+        for list_style in doc.descendants().filter(|n| n.tag_name().name() == "list-style") {
+            let Some(name) = xml_attr(list_style, "name") else {
+                continue;
+            };
+            let levels = list_style
+                .children()
+                .filter(|n| n.is_element())
+                .filter_map(|level| {
+                    let depth = xml_attr(level, "level")?.parse::<usize>().ok()?;
+                    Some((depth, level.tag_name().name() == "list-level-style-number"))
+                })
+                .collect();
+            self.list_styles.insert(name.to_string(), levels);
+        }
+    }
+
+    fn image(&mut self, frame: roxmltree::Node) -> Option<Inline> {
+        let image = frame.descendants().find(|n| n.tag_name().name() == "image")?;
+        let href = xml_attr(image, "href")?;
+        let bytes = read_zip_entry(&mut self.archive, href)?;
+        let ext = Path::new(href).extension()?.to_string_lossy().to_string();
+        let src = self.assets.save(&bytes, &ext).ok()?;
+
+        let alt = frame
+            .children()
+            .find(|n| matches!(n.tag_name().name(), "title" | "desc"))
+            .and_then(|n| n.text())
+            .unwrap_or_default()
+            .to_string();
+
+        Some(Inline::Image { src, alt })
+    }
+
+    fn inlines(&mut self, node: roxmltree::Node, style: OdtTextStyle, inlines: &mut Vec<Inline>) {
+        for child in node.children() {
+            if child.is_text() {
+                let text = child.text().unwrap_or_default();
+                let inline = if style.monospace {
+                    Inline::Code(text.to_string())
+                } else {
+                    styled_text(text, style.bold, style.italic, style.strike)
+                };
+                push_inline(inlines, inline);
+                continue;
+            }
+
+            match child.tag_name().name() {
+                "span" => {
+                    let span_style = xml_attr(child, "style-name")
+                        .and_then(|name| self.text_styles.get(name))
+                        .copied()
+                        .unwrap_or_default();
+                    let merged = OdtTextStyle {
+                        bold: style.bold || span_style.bold,
+                        italic: style.italic || span_style.italic,
+                        strike: style.strike || span_style.strike,
+                        monospace: style.monospace || span_style.monospace,
+                    };
+                    self.inlines(child, merged, inlines);
+                }
+                "a" => {
+                    let mut content = Vec::new();
+                    self.inlines(child, style, &mut content);
+                    match xml_attr(child, "href") {
+                        Some(url) => inlines.push(Inline::Link { url: url.to_string(), content }),
+                        None => inlines.extend(content),
+                    }
+                }
+                "s" => {
+                    let count = xml_attr(child, "c").and_then(|c| c.parse().ok()).unwrap_or(1);
+                    push_inline(inlines, Inline::Text(" ".repeat(count)));
+                }
+                "tab" => push_inline(inlines, Inline::Text(" ".to_string())),
+                "line-break" => inlines.push(Inline::LineBreak),
+                "frame" => {
+                    if let Some(image) = self.image(child) {
+                        inlines.push(image);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn paragraph_inlines(&mut self, node: roxmltree::Node) -> Vec<Inline> {
+        let style = xml_attr(node, "style-name")
+            .and_then(|name| self.text_styles.get(name))
+            .copied()
+            .unwrap_or_default();
+        let mut inlines = Vec::new();
+        self.inlines(node, style, &mut inlines);
+        inlines
+    }
+
+    fn list(&mut self, list: roxmltree::Node, style: Option<String>, level: usize, blocks: &mut Vec<Block>) {
+        // Nested lists inherit the outermost list's style unless they set their own
+        let style = xml_attr(list, "style-name").map(str::to_string).or(style);
+        let ordered = style
+            .as_ref()
+            .and_then(|name| self.list_styles.get(name))
+            .and_then(|levels| levels.get(&(level + 1)))
+            .copied()
+            .unwrap_or(false);
+
+        for item in list.children().filter(|n| matches!(n.tag_name().name(), "list-item" | "list-header")) {
+            let mut content = Vec::new();
+            let mut nested = Vec::new();
+
+            for child in item.children() {
+                match child.tag_name().name() {
+                    "p" | "h" => {
+                        if !content.is_empty() {
+                            content.push(Inline::LineBreak);
+                        }
+                        content.extend(self.paragraph_inlines(child));
+                    }
+                    "list" => nested.push(child),
+                    _ => {}
+                }
+            }
+
+            blocks.push(Block::ListItem { level, ordered, content });
+            for child in nested {
+                self.list(child, style.clone(), level + 1, blocks);
+            }
+        }
+    }
+
+    fn table(&mut self, table: roxmltree::Node) -> Block {
+        let mut rows = Vec::new();
+
+        for row in table.descendants().filter(|n| n.tag_name().name() == "table-row") {
+            let mut cells = Vec::new();
+            for cell in row.children().filter(|n| n.tag_name().name() == "table-cell") {
+                let mut content = Vec::new();
+                for paragraph in cell.children().filter(|n| matches!(n.tag_name().name(), "p" | "h")) {
+                    if !content.is_empty() {
+                        content.push(Inline::LineBreak);
+                    }
+                    content.extend(self.paragraph_inlines(paragraph));
+                }
+                cells.push(content);
+            }
+            rows.push(cells);
+        }
+
+        Block::Table(rows)
+    }
+
+    fn body(&mut self, node: roxmltree::Node, blocks: &mut Vec<Block>) {
+        for child in node.children() {
+            match child.tag_name().name() {
+                "h" => {
+                    let level = xml_attr(child, "outline-level")
+                        .and_then(|l| l.parse().ok())
+                        .unwrap_or(1);
+                    blocks.push(Block::Heading(level, self.paragraph_inlines(child)));
+                }
+                "p" => blocks.push(Block::Paragraph(self.paragraph_inlines(child))),
+                "list" => self.list(child, None, 0, blocks),
+                "table" => blocks.push(self.table(child)),
+                "section" => self.body(child, blocks),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn import_odt(path: &Path, assets: &mut AssetWriter) -> Result<Vec<Block>, ImportError> {
+    let archive = zip::ZipArchive::new(fs::File::open(path)?)?;
+    let mut importer = OdtImporter {
+        archive,
+        assets,
+        text_styles: HashMap::new(),
+        list_styles: HashMap::new(),
+    };
+
+    if let Some(xml) = read_zip_string(&mut importer.archive, "styles.xml") {
+        importer.load_styles(&roxmltree::Document::parse(&xml)?);
+    }
+
+    let xml = read_zip_string(&mut importer.archive, "content.xml")
+        .ok_or_else(|| ImportError::Parse("missing content.xml".to_string()))?;
+    let doc = roxmltree::Document::parse(&xml)?;
+    // Automatic styles in content.xml override the shared ones
+    importer.load_styles(&doc);
+
+    let mut blocks = Vec::new();
+    if let Some(text) = doc
+        .descendants()
+        .find(|n| n.tag_name().name() == "text" && n.parent().is_some_and(|p| p.tag_name().name() == "body"))
+    {
+        importer.body(text, &mut blocks);
+    }
+
+    Ok(blocks)
+}
+
+/// Decode a Windows-1252 byte as used by `\'hh` escapes
+fn decode_cp1252(byte: u8) -> char {
+    const HIGH: [char; 32] = [
+        '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}',
+        '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ',
+    ];
+    match byte {
+        0x80..=0x9f => HIGH[(byte - 0x80) as usize],
+        _ => byte as char,
+    }
+}
+
+/// RTF destinations whose content is not document text
+const RTF_SKIPPED_DESTINATIONS: &[&str] = &[
+    "fonttbl", "colortbl", "stylesheet", "info", "listtable", "listoverridetable", "generator",
+    "header", "headerl", "headerr", "headerf", "footer", "footerl", "footerr", "footerf",
+    "xmlnstbl", "rsidtbl", "themedata", "colorschememapping", "datastore", "latentstyles",
+    "pgdsctbl", "pntxtb", "pntxta", "nonshppict", "footnote", "annotation", "bkmkstart", "bkmkend",
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum RtfDestination {
+    Text,
+    Skip,
+    ListText,
+    FieldInstruction,
+    Picture,
+}
+
+#[derive(Clone, Copy)]
+struct RtfGroupState {
+    destination: RtfDestination,
+    bold: bool,
+    italic: bool,
+    strike: bool,
+    unicode_skip: usize,
+    /// Set on the group that opened a `\field`, so its end can emit the link
+    field: bool,
+    /// Set on the group that opened a `\pict`, so its end can save the image
+    picture: bool,
+}
+
+struct RtfImporter<'a> {
+    assets: &'a mut AssetWriter,
+    blocks: Vec<Block>,
+    /// Inline buffers; fields push one for their result text
+    inlines: Vec<Vec<Inline>>,
+    /// Open fields: instruction text and the inline buffer depth at their start
+    fields: Vec<(String, usize)>,
+    list_text: String,
+    picture_hex: String,
+    picture_ext: &'static str,
+    pending_skip: usize,
+    high_surrogate: Option<u16>,
+    outline_level: Option<usize>,
+    list_level: Option<usize>,
+    in_table: bool,
+    row: Vec<Vec<Inline>>,
+    table: Vec<Vec<Vec<Inline>>>,
+}
+
+impl RtfImporter<'_> {
+    fn push_text(&mut self, state: &RtfGroupState, text: &str) {
+        match state.destination {
+            RtfDestination::Text => {
+                let inline = styled_text(text, state.bold, state.italic, state.strike);
+                push_inline(self.inlines.last_mut().unwrap(), inline);
+            }
+            RtfDestination::ListText => self.list_text.push_str(text),
+            RtfDestination::FieldInstruction => {
+                if let Some((instruction, _)) = self.fields.last_mut() {
+                    instruction.push_str(text);
+                }
+            }
+            RtfDestination::Picture => self.picture_hex.push_str(text),
+            RtfDestination::Skip => {}
+        }
+    }
+
+    fn push_char(&mut self, state: &RtfGroupState, c: char) {
+        if self.pending_skip > 0 {
+            self.pending_skip -= 1;
+            return;
+        }
+        self.push_text(state, &c.to_string());
+    }
+
+    fn flush_table(&mut self) {
+        if !self.row.is_empty() {
+            let row = std::mem::take(&mut self.row);
+            self.table.push(row);
+        }
+        if !self.table.is_empty() {
+            let table = std::mem::take(&mut self.table);
+            self.blocks.push(Block::Table(table));
+        }
+    }
+
+    fn end_paragraph(&mut self) {
+        let content = std::mem::take(self.inlines.last_mut().unwrap());
+
+        if self.in_table {
+            // Paragraphs inside a cell are joined with line breaks
+            let cell = self.inlines.last_mut().unwrap();
+            cell.extend(content);
+            cell.push(Inline::LineBreak);
+            return;
+        }
+
+        self.flush_table();
+
+        let block = if let Some(level) = self.outline_level {
+            Block::Heading(level + 1, content)
+        } else if let Some(level) = self.list_level {
+            let marker = std::mem::take(&mut self.list_text);
+            Block::ListItem {
+                level,
+                ordered: marker.trim().starts_with(|c: char| c.is_ascii_digit()),
+                content,
+            }
+        } else {
+            Block::Paragraph(content)
+        };
+
+        self.blocks.push(block);
+    }
+
+    fn end_cell(&mut self) {
+        let mut content = std::mem::take(self.inlines.last_mut().unwrap());
+        while matches!(content.last(), Some(Inline::LineBreak)) {
+            content.pop();
+        }
+        self.row.push(content);
+    }
+
+    fn end_picture(&mut self) {
+        let hex: Vec<u8> = self.picture_hex.bytes().filter(u8::is_ascii_hexdigit).collect();
+        self.picture_hex.clear();
+
+        let bytes: Vec<u8> = hex
+            .chunks_exact(2)
+            .filter_map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+            .collect();
+
+        if bytes.is_empty() || self.picture_ext.is_empty() {
+            return;
+        }
+
+        if let Ok(src) = self.assets.save(&bytes, self.picture_ext) {
+            self.inlines
+                .last_mut()
+                .unwrap()
+                .push(Inline::Image { src, alt: String::new() });
+        }
+    }
+
+    fn end_field(&mut self) {
+        let Some((instruction, depth)) = self.fields.pop() else {
+            return;
+        };
+        let content = if self.inlines.len() > depth {
+            self.inlines.pop().unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+
+        let url = instruction
+            .trim()
+            .strip_prefix("HYPERLINK")
+            .map(|rest| rest.trim().trim_matches('"').to_string());
+
+        let target = self.inlines.last_mut().unwrap();
+        match url {
+            Some(url) if !url.is_empty() => target.push(Inline::Link { url, content }),
+            _ => {
+                for inline in content {
+                    push_inline(target, inline);
+                }
+            }
+        }
+    }
+
+    fn control_word(&mut self, state: &mut RtfGroupState, word: &str, param: Option<i32>, first_in_group: bool) {
+        if first_in_group && RTF_SKIPPED_DESTINATIONS.contains(&word) {
+            state.destination = RtfDestination::Skip;
+            return;
+        }
+
+        let on = param != Some(0);
+        match word {
+            "par" | "sect" => self.end_paragraph(),
+            "line" => self.inlines.last_mut().unwrap().push(Inline::LineBreak),
+            "tab" => self.push_text(state, " "),
+            "pard" => {
+                self.outline_level = None;
+                self.list_level = None;
+                self.in_table = false;
+            }
+            "plain" => {
+                state.bold = false;
+                state.italic = false;
+                state.strike = false;
+            }
+            "b" => state.bold = on,
+            "i" => state.italic = on,
+            "strike" | "striked" => state.strike = on,
+            "uc" => state.unicode_skip = param.unwrap_or(1).max(0) as usize,
+            "u" => {
+                let code = param.unwrap_or(0);
+                let unit = if code < 0 { (code + 65536) as u16 } else { code as u16 };
+                let c = match (self.high_surrogate.take(), unit) {
+                    (None, 0xD800..=0xDBFF) => {
+                        self.high_surrogate = Some(unit);
+                        None
+                    }
+                    (Some(high), 0xDC00..=0xDFFF) => char::decode_utf16([high, unit]).next().and_then(Result::ok),
+                    (_, unit) => char::from_u32(unit as u32),
+                };
+                if let Some(c) = c {
+                    self.push_text(state, &c.to_string());
+                }
+                self.pending_skip = state.unicode_skip;
+            }
+            "outlinelevel" => self.outline_level = param.map(|p| p.max(0) as usize),
+            "ls" => self.list_level = Some(self.list_level.unwrap_or(0)),
+            "ilvl" => self.list_level = param.map(|p| p.max(0) as usize),
+            "intbl" => self.in_table = true,
+            "cell" => self.end_cell(),
+            "row" => {
+                let row = std::mem::take(&mut self.row);
+                self.table.push(row);
+            }
+            "listtext" | "pntext" => state.destination = RtfDestination::ListText,
+            "field" => {
+                state.field = true;
+                self.fields.push((String::new(), self.inlines.len()));
+            }
+            "fldinst" => state.destination = RtfDestination::FieldInstruction,
+            "fldrslt" => self.inlines.push(Vec::new()),
+            "pict" => {
+                state.destination = RtfDestination::Picture;
+                state.picture = true;
+                self.picture_ext = "";
+            }
+            "pngblip" => self.picture_ext = "png",
+            "jpegblip" => self.picture_ext = "jpg",
+            "emdash" => self.push_text(state, "—"),
+            "endash" => self.push_text(state, "–"),
+            "lquote" => self.push_text(state, "‘"),
+            "rquote" => self.push_text(state, "’"),
+            "ldblquote" => self.push_text(state, "“"),
+            "rdblquote" => self.push_text(state, "”"),
+            "bullet" => self.push_text(state, "•"),
+            _ => {}
+        }
+    }
+
+    fn parse(&mut self, source: &str) {
+        let bytes = source.as_bytes();
+        let mut stack: Vec<RtfGroupState> = Vec::new();
+        let mut state = RtfGroupState {
+            destination: RtfDestination::Text,
+            bold: false,
+            italic: false,
+            strike: false,
+            unicode_skip: 1,
+            field: false,
+            picture: false,
+        };
+        let mut first_in_group = false;
+        let mut i = 0;
+
+        while i < bytes.len() {
+            match bytes[i] {
+                b'{' => {
+                    stack.push(state);
+                    state.field = false;
+                    state.picture = false;
+                    first_in_group = true;
+                    i += 1;
+                    continue;
+                }
+                b'}' => {
+                    if state.picture {
+                        self.end_picture();
+                    }
+                    if state.field {
+                        self.end_field();
+                    }
+                    if let Some(previous) = stack.pop() {
+                        state = previous;
+                    }
+                    first_in_group = false;
+                    i += 1;
+                    continue;
+                }
+                b'\\' => {
+                    i += 1;
+                    let Some(&next) = bytes.get(i) else {
+                        break;
+                    };
+
+                    if next.is_ascii_alphabetic() {
+                        let start = i;
+                        while i < bytes.len() && bytes[i].is_ascii_alphabetic() {
+                            i += 1;
+                        }
+                        let word = &source[start..i];
+
+                        let param_start = i;
+                        if i < bytes.len() && bytes[i] == b'-' {
+                            i += 1;
+                        }
+                        while i < bytes.len() && bytes[i].is_ascii_digit() {
+                            i += 1;
+                        }
+                        let param = source[param_start..i].parse().ok();
+
+                        // A single space delimiter belongs to the control word
+                        if i < bytes.len() && bytes[i] == b' ' {
+                            i += 1;
+                        }
+
+                        if state.destination != RtfDestination::Skip {
+                            self.control_word(&mut state, word, param, first_in_group);
+                        }
+                    } else {
+                        i += 1;
+                        match next {
+                            b'*' => {
+                                // Ignorable destination: keep only the ones we understand
+                                let rest = &source[i..];
+                                let known = ["\\fldinst", "\\shppict", "\\pict"]
+                                    .iter()
+                                    .any(|word| rest.starts_with(word));
+                                if !known {
+                                    state.destination = RtfDestination::Skip;
+                                }
+                            }
+                            b'\'' => {
+                                let hex = source.get(i..i + 2).unwrap_or_default();
+                                i += hex.len();
+                                if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                                    self.push_char(&state, decode_cp1252(byte));
+                                }
+                            }
+                            b'~' => self.push_char(&state, '\u{a0}'),
+                            b'_' => self.push_char(&state, '-'),
+                            b'\n' | b'\r' => self.end_paragraph(),
+                            b'\\' | b'{' | b'}' => self.push_char(&state, next as char),
+                            _ => {}
+                        }
+                    }
+                    first_in_group = false;
+                    continue;
+                }
+                b'\r' | b'\n' => {
+                    i += 1;
+                    continue;
+                }
+                _ => {}
+            }
+
+            // Plain text run up to the next control character
+            let start = i;
+            while i < bytes.len() && !matches!(bytes[i], b'{' | b'}' | b'\\' | b'\r' | b'\n') {
+                i += 1;
+            }
+            let text = &source[start..i];
+
+            if self.pending_skip > 0 {
+                let skipped: String = text.chars().skip(self.pending_skip).collect();
+                self.pending_skip = self.pending_skip.saturating_sub(text.chars().count());
+                self.push_text(&state, &skipped);
+            } else {
+                self.push_text(&state, text);
+            }
+            first_in_group = false;
+        }
+
+        if !self.inlines.last().is_some_and(|inlines| inlines.is_empty()) {
+            self.end_paragraph();
+        }
+        self.flush_table();
+    }
+}
+
+fn import_rtf(source: &str, assets: &mut AssetWriter) -> Vec<Block> {
+    let mut importer = RtfImporter {
+        assets,
+        blocks: Vec::new(),
+        inlines: vec![Vec::new()],
+        fields: Vec::new(),
+        list_text: String::new(),
+        picture_hex: String::new(),
+        picture_ext: "",
+        pending_skip: 0,
+        high_surrogate: None,
+        outline_level: None,
+        list_level: None,
+        in_table: false,
+        row: Vec::new(),
+        table: Vec::new(),
+    };
+    importer.parse(source);
+    importer.blocks
+}
+
+fn convert_document(source: &Path, mut assets: AssetWriter) -> Result<ImportedDocument, ImportError> {
+    let extension = source
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    let blocks = match extension.as_str() {
+        "html" | "htm" | "xhtml" => {
+            let bytes = fs::read(source)?;
+            import_html(&String::from_utf8_lossy(&bytes), &mut assets)
+        }
+        "docx" => import_docx(source, &mut assets)?,
+        "odt" => import_odt(source, &mut assets)?,
+        "rtf" => {
+            // RTF is 7-bit; anything else is escaped inside the stream
+            let bytes = fs::read(source)?;
+            import_rtf(&String::from_utf8_lossy(&bytes), &mut assets)
+        }
+        other => return Err(ImportError::Unsupported(other.to_string())),
+    };
+
+    let mut content = render_blocks(&blocks);
+    content.push('\n');
+
+    let title = source
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    Ok(ImportedDocument {
+        title,
+        content,
+        images: assets.written,
+    })
+}
+
+/// Convert an HTML, DOCX, ODT or RTF file into Markdown
+#[tauri::command]
+pub async fn import_document(app: tauri::AppHandle, path: String) -> Result<ImportedDocument, ImportError> {
+    let source = PathBuf::from(&path);
+
+    if !source.exists() {
+        return Err(ImportError::NotFound(path));
+    }
+
+    let settings = document_image_settings(&app, None)?;
+    let assets = AssetWriter::new(&source, settings, unsaved_images_dir(&app)?);
+
+    // Unzipping, parsing and storing images can take a while on large documents
+    tauri::async_runtime::spawn_blocking(move || convert_document(&source, assets))
+        .await
+        .map_err(|e| ImportError::Task(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ourea-import-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn asset_writer(dir: &Path) -> AssetWriter {
+        let mut settings = AppSettings::default();
+        settings.image_optimization.enabled = false;
+        settings.image_naming_rule = "original".to_string();
+        AssetWriter::new(&dir.join("report.docx"), settings, dir.join("unsaved"))
+    }
+
+    #[test]
+    fn converts_html() {
+        let dir = temp_dir("html");
+        let mut assets = asset_writer(&dir);
+        let html = r#"<html><head><title>x</title><style>p{}</style></head><body>
+            <h2>Intro <em>here</em></h2>
+            <p>Some <b>bold</b> and <a href="https://example.com/a b">a link</a>. 1*2</p>
+            <ul><li>one<ul><li>nested</li></ul></li></ul>
+            <ol><li>first</li></ol>
+            <blockquote><p>quoted</p></blockquote>
+            <pre>let x = 1;</pre>
+            <p><img alt="dot" src="data:image/png;base64,iVBORw0KGgoAAAA="></p>
+        </body></html>"#;
+
+        let content = render_blocks(&import_html(html, &mut assets));
+        let image = dir.join("unsaved").join("report-image1.png");
+        assert!(image.exists());
+        assert_eq!(assets.written, vec![image.display().to_string()]);
+
+        let expected = format!(
+            "## Intro *here*\n\n\
+             Some **bold** and [a link](<https://example.com/a b>). 1\\*2\n\n\
+             - one\n   - nested\n\
+             1. first\n\n\
+             > quoted\n\n\
+             ```\nlet x = 1;\n```\n\n\
+             ![dot]({})",
+            image.display()
+        );
+        assert_eq!(content, expected);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_nested_table_rows_in_their_cell() {
+        let dir = temp_dir("table");
+        let html = "<table><thead><tr><th>A</th><th>B</th></tr></thead><tbody>\
+            <tr><td>1</td><td><table><tr><td>x</td></tr><tr><td>y</td></tr></table></td></tr>\
+            </tbody></table>";
+
+        let content = render_blocks(&import_html(html, &mut asset_writer(&dir)));
+        assert_eq!(content, "| A | B |\n| --- | --- |\n| 1 | xy |");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn converts_rtf() {
+        let dir = temp_dir("rtf");
+        let rtf = r#"{\rtf1\ansi{\fonttbl{\f0 Arial;}}
+{\pard\outlinelevel0 Title\par}
+{\pard Plain \b bold\b0  and \i italic\i0 , caf\'e9 \u8364?5\par}
+{\pard {\field{\*\fldinst HYPERLINK "https://example.com"}{\fldrslt Example}}\par}
+{\pard\intbl a\cell b\cell\row\pard}
+}"#;
+
+        let content = render_blocks(&import_rtf(rtf, &mut asset_writer(&dir)));
+        assert_eq!(
+            content,
+            "# Title\n\nPlain **bold** and *italic*, café €5\n\n[Example](https://example.com)\n\n| a | b |\n| --- | --- |"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn converts_docx() {
+        let dir = temp_dir("docx");
+        let path = dir.join("report.docx");
+        let document = r#"<?xml version="1.0"?>
+<w:document xmlns:w="w" xmlns:r="r"><w:body>
+  <w:p><w:pPr><w:pStyle w:val="Heading2"/></w:pPr><w:r><w:t>Section</w:t></w:r></w:p>
+  <w:p><w:r><w:t xml:space="preserve">Some </w:t></w:r><w:r><w:rPr><w:b/></w:rPr><w:t>bold</w:t></w:r>
+    <w:hyperlink r:id="rId1"><w:r><w:t> link</w:t></w:r></w:hyperlink></w:p>
+  <w:p><w:pPr><w:numPr><w:ilvl w:val="0"/><w:numId w:val="1"/></w:numPr></w:pPr><w:r><w:t>item</w:t></w:r></w:p>
+  <w:tbl><w:tr><w:tc><w:p><w:r><w:t>a</w:t></w:r></w:p></w:tc><w:tc><w:p><w:r><w:t>b</w:t></w:r></w:p></w:tc></w:tr></w:tbl>
+</w:body></w:document>"#;
+        let styles = r#"<w:styles xmlns:w="w"><w:style w:styleId="Heading2"><w:name w:val="heading 2"/></w:style></w:styles>"#;
+        let numbering = r#"<w:numbering xmlns:w="w">
+  <w:abstractNum w:abstractNumId="0"><w:lvl w:ilvl="0"><w:numFmt w:val="decimal"/></w:lvl></w:abstractNum>
+  <w:num w:numId="1"><w:abstractNumId w:val="0"/></w:num>
+</w:numbering>"#;
+        let rels = r#"<Relationships><Relationship Id="rId1" Target="https://example.com"/></Relationships>"#;
+
+        let mut zip = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+        for (name, xml) in [
+            ("word/document.xml", document),
+            ("word/styles.xml", styles),
+            ("word/numbering.xml", numbering),
+            ("word/_rels/document.xml.rels", rels),
+        ] {
+            zip.start_file(name, zip::write::SimpleFileOptions::default()).unwrap();
+            zip.write_all(xml.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let blocks = import_docx(&path, &mut asset_writer(&dir)).unwrap();
+        assert_eq!(
+            render_blocks(&blocks),
+            "## Section\n\nSome **bold**[link](https://example.com)\n\n1. item\n\n| a | b |\n| --- | --- |"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn escapes_markdown_syntax() {
+        assert_eq!(escape_markdown("snake_case _x_ *a* [b] <c>"), r"snake_case \_x\_ \*a\* \[b\] \<c>");
+        assert_eq!(render_paragraph(&[Inline::Text("# not a heading".into())]), r"\# not a heading");
+        assert_eq!(render_paragraph(&[Inline::Text("2. not a list".into())]), r"\2. not a list");
+    }
+}
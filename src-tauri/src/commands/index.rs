@@ -0,0 +1,892 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::file::write_atomic;
+use super::frontmatter::parse_front_matter;
+use super::markdown::{is_markdown_file, markdown_files};
+use super::outline::extract_headings;
+use super::stats::is_cjk;
+use super::watcher::{watch_workspace, WatcherError, WorkspaceSubscription, WorkspaceWatchers};
+
+/// Bumped whenever the on-disk layout or tokenizer changes
+const INDEX_VERSION: u32 = 2;
+/// Folder under the app data directory holding one folder per workspace,
+/// with a file per indexed document
+const INDEX_DIR: &str = "search-index";
+/// How often pending index changes are flushed to disk
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+const DEFAULT_QUERY_LIMIT: usize = 50;
+/// Most index terms a single prefix query expands to
+const MAX_PREFIX_TERMS: usize = 200;
+
+// BM25 ranking parameters
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+
+/// Token positions of one term, by document id
+type Postings = HashMap<u32, Vec<u32>>;
+
+#[derive(Debug)]
+struct IndexedDocument {
+    path: String,
+    title: String,
+    /// Modification time in seconds, to skip unchanged files on startup
+    modified: u64,
+    length: u32,
+    terms: Vec<String>,
+    front_matter: Map<String, Value>,
+}
+
+/// One document as saved on disk, with the positions of its terms
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredDocument {
+    version: u32,
+    path: String,
+    title: String,
+    modified: u64,
+    length: u32,
+    front_matter: Map<String, Value>,
+    positions: BTreeMap<String, Vec<u32>>,
+}
+
+#[derive(Debug)]
+struct SearchIndex {
+    next_id: u32,
+    ids: HashMap<String, u32>,
+    documents: HashMap<u32, IndexedDocument>,
+    postings: BTreeMap<String, Postings>,
+    /// Paths added, changed or removed since the last save
+    dirty: HashSet<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexHit {
+    pub path: String,
+    pub title: String,
+    pub score: f64,
+    pub modified: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexReady {
+    pub root: String,
+    pub documents: usize,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum IndexError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Path not found: {0}")]
+    NotFound(String),
+    #[error("No index is open for {0}")]
+    NotOpen(String),
+    #[error("{0}")]
+    Watch(#[from] WatcherError),
+    #[error("Index error: {0}")]
+    Index(String),
+}
+
+impl Serialize for IndexError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+struct OpenIndex {
+    index: Arc<Mutex<SearchIndex>>,
+    dir: PathBuf,
+    closed: Arc<AtomicBool>,
+    // Kept alive for as long as the index is open
+    _subscription: WorkspaceSubscription,
+}
+
+/// Indexes of the open workspaces, by root path
+pub struct IndexState {
+    indexes: HashMap<String, OpenIndex>,
+}
+
+impl IndexState {
+    pub fn new() -> Self {
+        Self {
+            indexes: HashMap::new(),
+        }
+    }
+}
+
+impl Default for IndexState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Lowercase terms of some text with their positions
+#[derive(Default)]
+struct Tokens {
+    terms: Vec<(String, u32)>,
+    /// Number of positions used
+    length: u32,
+    /// Also add every CJK character on its own, for one-character queries
+    characters: bool,
+}
+
+impl Tokens {
+    fn push_word(&mut self, word: &mut String) {
+        if !word.is_empty() {
+            self.terms.push((std::mem::take(word), self.length));
+            self.length += 1;
+        }
+    }
+
+    /// CJK text has no spaces, so runs of it are indexed as overlapping
+    /// bigrams. A single character shares the position of the bigram it
+    /// starts, or of the last one, so phrases still line up around it.
+    fn push_cjk_run(&mut self, run: &mut Vec<char>) {
+        match run.len() {
+            0 => {}
+            1 => {
+                self.terms.push((run[0].to_string(), self.length));
+                self.length += 1;
+            }
+            n => {
+                let start = self.length;
+                for (i, pair) in run.windows(2).enumerate() {
+                    self.terms.push((pair.iter().collect(), start + i as u32));
+                }
+                if self.characters {
+                    for (i, c) in run.iter().enumerate() {
+                        self.terms.push((c.to_string(), start + i.min(n - 2) as u32));
+                    }
+                }
+                self.length += n as u32 - 1;
+            }
+        }
+        run.clear();
+    }
+}
+
+/// Split text into lowercase terms
+fn tokenize_with(text: &str, characters: bool) -> Tokens {
+    let mut tokens = Tokens {
+        characters,
+        ..Default::default()
+    };
+    let mut run: Vec<char> = Vec::new();
+
+    for segment in text.split_word_bounds() {
+        let mut word = String::new();
+
+        for c in segment.chars() {
+            if is_cjk(c) {
+                tokens.push_word(&mut word);
+                run.push(c);
+            } else {
+                tokens.push_cjk_run(&mut run);
+                // Apostrophes and the like are dropped inside a word: "don't" is "dont"
+                if c.is_alphanumeric() {
+                    word.extend(c.to_lowercase());
+                }
+            }
+        }
+
+        tokens.push_word(&mut word);
+    }
+
+    tokens.push_cjk_run(&mut run);
+    tokens
+}
+
+/// Split a query into lowercase terms, one per position
+fn tokenize(text: &str) -> Vec<String> {
+    tokenize_with(text, false).terms.into_iter().map(|(term, _)| term).collect()
+}
+
+enum Clause {
+    Term(String),
+    Prefix(String),
+    Phrase(Vec<String>),
+}
+
+/// Turn a list of tokens from one query word or quote into a clause
+fn clause_for(mut tokens: Vec<String>, prefix: bool) -> Option<Clause> {
+    match tokens.len() {
+        0 => None,
+        1 if prefix => Some(Clause::Prefix(tokens.remove(0))),
+        1 => Some(Clause::Term(tokens.remove(0))),
+        _ => Some(Clause::Phrase(tokens)),
+    }
+}
+
+/// Parse a query: bare words are required terms, `"quoted text"` is a
+/// phrase and a trailing `*` matches a prefix
+fn parse_query(query: &str) -> Vec<Clause> {
+    let mut clauses = Vec::new();
+
+    for (i, part) in query.split('"').enumerate() {
+        if i % 2 == 1 {
+            clauses.extend(clause_for(tokenize(part), false));
+            continue;
+        }
+
+        for word in part.split_whitespace() {
+            let prefix = word.ends_with('*');
+            let mut tokens = tokenize(word);
+            if prefix && tokens.len() > 1 {
+                // `foo-ba*` needs "foo" and something starting with "ba"
+                let last = tokens.pop();
+                clauses.extend(tokens.into_iter().map(Clause::Term));
+                clauses.extend(last.map(Clause::Prefix));
+            } else {
+                clauses.extend(clause_for(tokens, prefix));
+            }
+        }
+    }
+
+    clauses
+}
+
+fn value_matches(value: &Value, expected: &Value) -> bool {
+    match (value, expected) {
+        (Value::Array(items), _) => items.iter().any(|item| value_matches(item, expected)),
+        (Value::String(actual), Value::String(wanted)) => actual.eq_ignore_ascii_case(wanted),
+        (actual, Value::String(wanted)) => actual.to_string().eq_ignore_ascii_case(wanted),
+        (actual, wanted) => actual == wanted,
+    }
+}
+
+impl SearchIndex {
+    fn new() -> Self {
+        Self {
+            next_id: 0,
+            ids: HashMap::new(),
+            documents: HashMap::new(),
+            postings: BTreeMap::new(),
+            dirty: HashSet::new(),
+        }
+    }
+
+    /// Load the saved documents of a workspace. Missing, unreadable or
+    /// outdated ones are left for `reconcile` to index again.
+    fn load(dir: &Path) -> Self {
+        let mut index = Self::new();
+
+        for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+            let stored = fs::read(entry.path())
+                .ok()
+                .and_then(|bytes| serde_json::from_slice::<StoredDocument>(&bytes).ok())
+                .filter(|stored| stored.version == INDEX_VERSION);
+            match stored {
+                Some(stored) => index.insert(stored),
+                None => {
+                    let _ = fs::remove_file(entry.path());
+                }
+            }
+        }
+
+        index
+    }
+
+    /// Write the documents that changed since the last save, and delete the
+    /// files of removed ones
+    fn save(&mut self, dir: &Path) -> Result<(), IndexError> {
+        fs::create_dir_all(dir)?;
+
+        let pending: Vec<String> = self.dirty.iter().cloned().collect();
+        for path in pending {
+            let file = document_file(dir, &path);
+            match self.stored(&path) {
+                Some(stored) => {
+                    let bytes = serde_json::to_vec(&stored).map_err(|e| IndexError::Index(e.to_string()))?;
+                    write_atomic(&file, &bytes)?;
+                }
+                None => match fs::remove_file(&file) {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                    _ => {}
+                },
+            }
+            self.dirty.remove(&path);
+        }
+
+        Ok(())
+    }
+
+    fn stored(&self, path: &str) -> Option<StoredDocument> {
+        let id = self.ids.get(path)?;
+        let doc = self.documents.get(id)?;
+        let positions = doc
+            .terms
+            .iter()
+            .filter_map(|term| Some((term.clone(), self.postings.get(term)?.get(id)?.clone())))
+            .collect();
+
+        Some(StoredDocument {
+            version: INDEX_VERSION,
+            path: doc.path.clone(),
+            title: doc.title.clone(),
+            modified: doc.modified,
+            length: doc.length,
+            front_matter: doc.front_matter.clone(),
+            positions,
+        })
+    }
+
+    /// Add a document to the in-memory index
+    fn insert(&mut self, stored: StoredDocument) {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        for (term, positions) in &stored.positions {
+            self.postings.entry(term.clone()).or_default().insert(id, positions.clone());
+        }
+
+        self.ids.insert(stored.path.clone(), id);
+        self.documents.insert(
+            id,
+            IndexedDocument {
+                path: stored.path,
+                title: stored.title,
+                modified: stored.modified,
+                length: stored.length,
+                terms: stored.positions.into_keys().collect(),
+                front_matter: stored.front_matter,
+            },
+        );
+    }
+
+    fn is_current(&self, path: &str, modified: u64) -> bool {
+        self.ids
+            .get(path)
+            .and_then(|id| self.documents.get(id))
+            .is_some_and(|doc| doc.modified == modified)
+    }
+
+    fn remove(&mut self, path: &str) {
+        let Some(id) = self.ids.remove(path) else {
+            return;
+        };
+
+        if let Some(doc) = self.documents.remove(&id) {
+            for term in &doc.terms {
+                if let Some(postings) = self.postings.get_mut(term) {
+                    postings.remove(&id);
+                    if postings.is_empty() {
+                        self.postings.remove(term);
+                    }
+                }
+            }
+        }
+        self.dirty.insert(path.to_string());
+    }
+
+    /// Remove every document inside a deleted folder
+    fn remove_under(&mut self, folder: &Path) {
+        let paths: Vec<String> = self
+            .ids
+            .keys()
+            .filter(|path| Path::new(path).starts_with(folder))
+            .cloned()
+            .collect();
+
+        for path in paths {
+            self.remove(&path);
+        }
+    }
+
+    fn add(&mut self, path: &Path, content: &str, modified: u64) {
+        let key = path.display().to_string();
+        self.remove(&key);
+
+        let front_matter = parse_front_matter(content).ok().flatten().map(|fm| fm.data).unwrap_or_default();
+        let title = front_matter
+            .get("title")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .or_else(|| extract_headings(content).into_iter().next().map(|h| h.text))
+            .unwrap_or_else(|| {
+                path.file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            });
+
+        let tokens = tokenize_with(content, true);
+        let mut positions: BTreeMap<String, Vec<u32>> = BTreeMap::new();
+        for (term, position) in tokens.terms {
+            positions.entry(term).or_default().push(position);
+        }
+
+        self.dirty.insert(key.clone());
+        self.insert(StoredDocument {
+            version: INDEX_VERSION,
+            path: key,
+            title,
+            modified,
+            length: tokens.length,
+            front_matter,
+            positions,
+        });
+    }
+
+    /// Term frequency of a clause in each document that contains it
+    fn clause_frequencies(&self, clause: &Clause) -> HashMap<u32, f64> {
+        let mut frequencies: HashMap<u32, f64> = HashMap::new();
+
+        match clause {
+            Clause::Term(term) => {
+                if let Some(postings) = self.postings.get(term) {
+                    for (id, positions) in postings {
+                        frequencies.insert(*id, positions.len() as f64);
+                    }
+                }
+            }
+            Clause::Prefix(prefix) => {
+                let expanded = self
+                    .postings
+                    .range(prefix.clone()..)
+                    .take_while(|(term, _)| term.starts_with(prefix.as_str()))
+                    .take(MAX_PREFIX_TERMS);
+                for (_, postings) in expanded {
+                    for (id, positions) in postings {
+                        *frequencies.entry(*id).or_default() += positions.len() as f64;
+                    }
+                }
+            }
+            Clause::Phrase(terms) => {
+                let Some(lists) = terms
+                    .iter()
+                    .map(|term| self.postings.get(term))
+                    .collect::<Option<Vec<&Postings>>>()
+                else {
+                    return frequencies;
+                };
+
+                for (id, starts) in lists[0] {
+                    let Some(following) = lists[1..]
+                        .iter()
+                        .map(|postings| postings.get(id))
+                        .collect::<Option<Vec<&Vec<u32>>>>()
+                    else {
+                        continue;
+                    };
+
+                    // Positions are pushed in order, so they can be binary searched
+                    let count = starts
+                        .iter()
+                        .filter(|&&start| {
+                            following
+                                .iter()
+                                .enumerate()
+                                .all(|(i, positions)| positions.binary_search(&(start + i as u32 + 1)).is_ok())
+                        })
+                        .count();
+                    if count > 0 {
+                        frequencies.insert(*id, count as f64);
+                    }
+                }
+            }
+        }
+
+        frequencies
+    }
+
+    fn query(&self, query: &str, filters: &Map<String, Value>, limit: usize) -> Vec<IndexHit> {
+        let clauses = parse_query(query);
+        let passes_filters = |doc: &IndexedDocument| {
+            filters.iter().all(|(key, expected)| {
+                doc.front_matter
+                    .get(key)
+                    .is_some_and(|value| value_matches(value, expected))
+            })
+        };
+
+        let hit = |doc: &IndexedDocument, score: f64| IndexHit {
+            path: doc.path.clone(),
+            title: doc.title.clone(),
+            score,
+            modified: doc.modified,
+        };
+
+        // Filters alone list every matching document
+        if clauses.is_empty() {
+            let mut hits: Vec<IndexHit> = self
+                .documents
+                .values()
+                .filter(|doc| !filters.is_empty() && passes_filters(doc))
+                .map(|doc| hit(doc, 0.0))
+                .collect();
+            hits.sort_by(|a, b| a.path.cmp(&b.path));
+            hits.truncate(limit);
+            return hits;
+        }
+
+        let total = self.documents.len() as f64;
+        let average_length = self.documents.values().map(|d| d.length as f64).sum::<f64>() / total.max(1.0);
+        let mut scores: Option<HashMap<u32, f64>> = None;
+
+        // Every clause is required; each one adds its BM25 score
+        for clause in &clauses {
+            let frequencies = self.clause_frequencies(clause);
+            let matched = frequencies.len() as f64;
+            let idf = (1.0 + (total - matched + 0.5) / (matched + 0.5)).ln();
+
+            let mut next = HashMap::new();
+            for (id, frequency) in frequencies {
+                let previous = match &scores {
+                    Some(scores) => match scores.get(&id) {
+                        Some(score) => *score,
+                        None => continue,
+                    },
+                    None => 0.0,
+                };
+                let Some(doc) = self.documents.get(&id) else {
+                    continue;
+                };
+
+                let norm = 1.0 - BM25_B + BM25_B * doc.length as f64 / average_length.max(1.0);
+                let score = idf * frequency * (BM25_K1 + 1.0) / (frequency + BM25_K1 * norm);
+                next.insert(id, previous + score);
+            }
+            scores = Some(next);
+        }
+
+        let mut hits: Vec<IndexHit> = scores
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(id, score)| self.documents.get(&id).map(|doc| (doc, score)))
+            .filter(|(doc, _)| passes_filters(doc))
+            .map(|(doc, score)| hit(doc, score))
+            .collect();
+
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+        hits.truncate(limit);
+        hits
+    }
+}
+
+fn modified_secs(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_secs())
+}
+
+/// Bring one path up to date after a change on disk
+fn update_path(index: &Mutex<SearchIndex>, path: &Path) {
+    if path.is_dir() {
+        return;
+    }
+
+    if !path.exists() {
+        let mut index = index.lock().unwrap();
+        if is_markdown_file(path) {
+            index.remove(&path.display().to_string());
+        } else {
+            index.remove_under(path);
+        }
+        return;
+    }
+
+    if !is_markdown_file(path) {
+        return;
+    }
+
+    if let (Ok(content), Some(modified)) = (fs::read_to_string(path), modified_secs(path)) {
+        index.lock().unwrap().add(path, &content, modified);
+    }
+}
+
+/// Index new and changed files and drop the ones that disappeared
+fn reconcile(index: &Mutex<SearchIndex>, root: &Path, closed: &AtomicBool) {
+    let files = markdown_files(root);
+    let present: HashSet<String> = files.iter().map(|path| path.display().to_string()).collect();
+
+    {
+        let mut index = index.lock().unwrap();
+        let stale: Vec<String> = index.ids.keys().filter(|path| !present.contains(*path)).cloned().collect();
+        for path in stale {
+            index.remove(&path);
+        }
+    }
+
+    for path in files {
+        if closed.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let Some(modified) = modified_secs(&path) else {
+            continue;
+        };
+        if index.lock().unwrap().is_current(&path.display().to_string(), modified) {
+            continue;
+        }
+
+        // Read outside the lock so queries keep working during a rebuild
+        if let Ok(content) = fs::read_to_string(&path) {
+            index.lock().unwrap().add(&path, &content, modified);
+        }
+    }
+}
+
+/// 64-bit FNV-1a, used to give workspaces and documents stable file names
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn document_file(dir: &Path, path: &str) -> PathBuf {
+    dir.join(format!("{:016x}.json", fnv1a(path)))
+}
+
+fn index_dir(app: &AppHandle, root: &str) -> Result<PathBuf, IndexError> {
+    let parent = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| IndexError::Index(e.to_string()))?
+        .join(INDEX_DIR);
+    let name = format!("{:016x}", fnv1a(root));
+
+    // Indexes before version 2 were a single file per workspace
+    let _ = fs::remove_file(parent.join(format!("{}.json", name)));
+
+    let dir = parent.join(name);
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Open the full-text index of a workspace. The saved index is loaded and
+/// brought up to date in the background, which emits `index-ready` when
+/// done. File changes keep it current while open.
+#[tauri::command]
+pub fn open_index(
+    app: AppHandle,
+    root: String,
+    state: tauri::State<'_, Arc<Mutex<IndexState>>>,
+    watchers: tauri::State<'_, Arc<Mutex<WorkspaceWatchers>>>,
+) -> Result<(), IndexError> {
+    let root_path = PathBuf::from(&root);
+
+    if !root_path.exists() {
+        return Err(IndexError::NotFound(root));
+    }
+
+    // Held until the index is registered, so two opens can't both build it
+    let mut state = state.lock().unwrap();
+    let Entry::Vacant(entry) = state.indexes.entry(root.clone()) else {
+        return Ok(());
+    };
+
+    // Empty until the background thread has read the saved documents
+    let dir = index_dir(&app, &root)?;
+    let index = Arc::new(Mutex::new(SearchIndex::new()));
+    let closed = Arc::new(AtomicBool::new(false));
+
+    let watched = index.clone();
+    let subscription = watch_workspace(&watchers, &root_path, move |path| update_path(&watched, path))?;
+
+    let background = (index.clone(), dir.clone(), closed.clone());
+    std::thread::spawn(move || {
+        let (index, dir, closed) = background;
+
+        // Loading a large index takes a while, so it's read without the lock.
+        // Changes the watcher applied meanwhile are picked up by the reconcile.
+        let loaded = SearchIndex::load(&dir);
+        if closed.load(Ordering::Relaxed) {
+            return;
+        }
+        *index.lock().unwrap() = loaded;
+        reconcile(&index, &root_path, &closed);
+
+        let documents = index.lock().unwrap().documents.len();
+        let _ = app.emit(
+            "index-ready",
+            IndexReady {
+                root: root_path.display().to_string(),
+                documents,
+            },
+        );
+
+        // Flush pending changes until the index is closed
+        while !closed.load(Ordering::Relaxed) {
+            {
+                let mut index = index.lock().unwrap();
+                if !index.dirty.is_empty() {
+                    if let Err(e) = index.save(&dir) {
+                        log::error!("Failed to save search index: {}", e);
+                    }
+                }
+            }
+            std::thread::sleep(SAVE_INTERVAL);
+        }
+    });
+
+    entry.insert(OpenIndex {
+        index,
+        dir,
+        closed,
+        _subscription: subscription,
+    });
+
+    Ok(())
+}
+
+/// Query the index of an open workspace. Results are ranked by relevance;
+/// `filters` keeps only documents whose front matter has the given values.
+#[tauri::command]
+pub fn query_index(
+    root: String,
+    query: String,
+    filters: Option<Map<String, Value>>,
+    limit: Option<usize>,
+    state: tauri::State<'_, Arc<Mutex<IndexState>>>,
+) -> Result<Vec<IndexHit>, IndexError> {
+    let index = match state.lock().unwrap().indexes.get(&root) {
+        Some(open) => open.index.clone(),
+        None => return Err(IndexError::NotOpen(root)),
+    };
+
+    let hits = index.lock().unwrap().query(
+        &query,
+        &filters.unwrap_or_default(),
+        limit.unwrap_or(DEFAULT_QUERY_LIMIT),
+    );
+    Ok(hits)
+}
+
+/// Stop updating a workspace index and save it to disk
+#[tauri::command]
+pub fn close_index(root: String, state: tauri::State<'_, Arc<Mutex<IndexState>>>) -> Result<(), IndexError> {
+    let Some(open) = state.lock().unwrap().indexes.remove(&root) else {
+        return Ok(());
+    };
+
+    open.closed.store(true, Ordering::Relaxed);
+    let mut index = open.index.lock().unwrap();
+    if !index.dirty.is_empty() {
+        index.save(&open.dir)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index_of(documents: &[(&str, &str)]) -> SearchIndex {
+        let mut index = SearchIndex::new();
+        for (path, content) in documents {
+            index.add(Path::new(path), content, 1);
+        }
+        index
+    }
+
+    fn paths(hits: Vec<IndexHit>) -> Vec<String> {
+        hits.into_iter().map(|hit| hit.path).collect()
+    }
+
+    #[test]
+    fn tokenizes_words_and_cjk_bigrams() {
+        assert_eq!(tokenize("Don't STOP-me now"), ["dont", "stop", "me", "now"]);
+        assert_eq!(tokenize("写中文字"), ["写中", "中文", "文字"]);
+        assert_eq!(tokenize("a中b"), ["a", "中", "b"]);
+
+        let tokens = tokenize_with("x 中文字 y", true);
+        assert_eq!(tokens.length, 4);
+        let positions = |wanted: &str| -> Vec<u32> {
+            tokens.terms.iter().filter(|(term, _)| term == wanted).map(|(_, p)| *p).collect()
+        };
+        assert_eq!(positions("文字"), [2]);
+        assert_eq!((positions("中"), positions("文"), positions("字")), (vec![1], vec![2], vec![2]));
+        assert_eq!(positions("y"), [3]);
+    }
+
+    #[test]
+    fn finds_a_single_cjk_character_in_either_half_of_a_bigram() {
+        let index = index_of(&[("/w/a.md", "我们说中文"), ("/w/b.md", "文学"), ("/w/c.md", "English only")]);
+        let none = Map::new();
+
+        assert_eq!(paths(index.query("文", &none, 10)).len(), 2);
+        assert_eq!(paths(index.query("中", &none, 10)), ["/w/a.md"]);
+        assert_eq!(paths(index.query("说中文", &none, 10)), ["/w/a.md"]);
+        assert!(index.query("中学", &none, 10).is_empty());
+        assert_eq!(paths(index.query("\"说 中文\"", &none, 10)), ["/w/a.md"]);
+    }
+
+    #[test]
+    fn ranks_terms_phrases_and_prefixes() {
+        let index = index_of(&[
+            ("/w/a.md", "# Rust notes\n\nrust rust rust and more"),
+            ("/w/b.md", "A note that mentions rust once among many other words here"),
+            ("/w/c.md", "quick brown fox, brown quick fox"),
+        ]);
+        let none = Map::new();
+
+        assert_eq!(paths(index.query("rust", &none, 10)), ["/w/a.md", "/w/b.md"]);
+        assert_eq!(paths(index.query("rust notes", &none, 10)), ["/w/a.md"]);
+        assert_eq!(paths(index.query("\"brown fox\"", &none, 10)), ["/w/c.md"]);
+        assert!(index.query("\"fox quick\"", &none, 10).is_empty());
+        assert_eq!(paths(index.query("ment*", &none, 10)), ["/w/b.md"]);
+        assert_eq!(index.query("rust", &none, 10)[0].title, "Rust notes");
+    }
+
+    #[test]
+    fn filters_on_front_matter() {
+        let index = index_of(&[
+            ("/w/a.md", "---\ntags: [Work, idea]\n---\nplan"),
+            ("/w/b.md", "---\ntags: [home]\n---\nplan"),
+        ]);
+        let mut filters = Map::new();
+        filters.insert("tags".to_string(), Value::String("work".to_string()));
+
+        assert_eq!(paths(index.query("plan", &filters, 10)), ["/w/a.md"]);
+        assert_eq!(paths(index.query("", &filters, 10)), ["/w/a.md"]);
+        assert!(index.query("", &Map::new(), 10).is_empty());
+    }
+
+    #[test]
+    fn saves_changed_documents_to_their_own_files() {
+        let dir = std::env::temp_dir().join(format!("ourea-index-save-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut index = index_of(&[("/w/a.md", "alpha 中文"), ("/w/b.md", "beta")]);
+        index.save(&dir).unwrap();
+        assert!(index.dirty.is_empty());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        // Only the changed document is rewritten
+        let untouched = document_file(&dir, "/w/b.md");
+        fs::write(&untouched, b"not json").unwrap();
+        index.remove("/w/a.md");
+        index.add(Path::new("/w/c.md"), "gamma", 2);
+        index.save(&dir).unwrap();
+        assert!(!document_file(&dir, "/w/a.md").exists());
+        assert_eq!(fs::read(&untouched).unwrap(), b"not json");
+
+        // Unreadable documents are dropped on load, to be indexed again
+        let loaded = SearchIndex::load(&dir);
+        assert_eq!(loaded.documents.len(), 1);
+        assert!(!untouched.exists());
+        assert!(loaded.is_current("/w/c.md", 2));
+        assert_eq!(paths(loaded.query("gamma", &Map::new(), 10)), ["/w/c.md"]);
+
+        index.add(Path::new("/w/a.md"), "alpha 中文", 3);
+        index.save(&dir).unwrap();
+        let loaded = SearchIndex::load(&dir);
+        assert_eq!(paths(loaded.query("文", &Map::new(), 10)), ["/w/a.md"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
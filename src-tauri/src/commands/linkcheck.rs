@@ -0,0 +1,360 @@
+use pulldown_cmark::{Event, Parser, Tag};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::links::{parse_links, LinkIndex, LinkKind, ParsedLink};
+use super::markdown::{is_markdown_file, markdown_files, parser_options, slugify, LineIndex};
+use super::outline::extract_headings;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+/// Concurrent requests when checking external links
+const HTTP_WORKERS: usize = 8;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkCheckOptions {
+    /// Workspace root for wiki and root-relative links; defaults to the scope
+    pub root: Option<String>,
+    /// Also request `http(s)` links
+    #[serde(default)]
+    pub check_external: bool,
+    /// Service to check external links through, called as `<endpoint>?url=<link>`.
+    /// Links are requested directly when unset.
+    pub external_endpoint: Option<String>,
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiagnosticSeverity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkDiagnostic {
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+    /// Byte range of the link in the document
+    pub start: usize,
+    pub end: usize,
+    pub severity: DiagnosticSeverity,
+    /// Stable identifier such as `missing-file` or `missing-anchor`
+    pub code: String,
+    pub message: String,
+    pub target: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LinkCheckError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Path not found: {0}")]
+    NotFound(String),
+    #[error("Link check failed: {0}")]
+    Task(String),
+}
+
+impl Serialize for LinkCheckError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// An `http(s)` link and where it appears
+struct ExternalLink {
+    url: String,
+    start: usize,
+    end: usize,
+}
+
+fn external_links(content: &str) -> Vec<ExternalLink> {
+    let mut links = Vec::new();
+
+    for (event, range) in Parser::new_ext(content, parser_options()).into_offset_iter() {
+        if let Event::Start(Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. }) = event {
+            if dest_url.starts_with("http://") || dest_url.starts_with("https://") {
+                links.push(ExternalLink {
+                    url: dest_url.to_string(),
+                    start: range.start,
+                    end: range.end,
+                });
+            }
+        }
+    }
+
+    links
+}
+
+/// Anchors of a document's headings, computed once per file
+struct AnchorCache {
+    anchors: HashMap<PathBuf, Option<HashSet<String>>>,
+}
+
+impl AnchorCache {
+    fn has_anchor(&mut self, path: &Path, heading: &str) -> bool {
+        let anchors = self.anchors.entry(path.to_path_buf()).or_insert_with(|| {
+            let content = fs::read_to_string(path).ok()?;
+            Some(extract_headings(&content).into_iter().map(|h| h.anchor).collect())
+        });
+
+        // Unreadable files were already reported, so don't pile on
+        let Some(anchors) = anchors else {
+            return true;
+        };
+        anchors.contains(heading) || anchors.contains(&slugify(heading))
+    }
+}
+
+struct Checker<'a> {
+    index: &'a LinkIndex,
+    anchors: AnchorCache,
+    diagnostics: Vec<LinkDiagnostic>,
+}
+
+impl Checker<'_> {
+    fn report(&mut self, source: &Path, link: &ParsedLink, severity: DiagnosticSeverity, code: &str, message: String) {
+        self.diagnostics.push(LinkDiagnostic {
+            path: source.display().to_string(),
+            line: link.line,
+            column: link.column,
+            start: link.start,
+            end: link.end,
+            severity,
+            code: code.to_string(),
+            message,
+            target: link.target.clone(),
+        });
+    }
+
+    fn check_link(&mut self, source: &Path, link: &ParsedLink) {
+        let Some(target) = self.index.resolve(link, Some(source)) else {
+            let (code, what) = if link.embed {
+                ("missing-image", "Image")
+            } else {
+                ("missing-file", "Linked file")
+            };
+            let message = format!("{} not found: {}", what, link.target);
+            let severity = match link.kind {
+                LinkKind::Markdown => DiagnosticSeverity::Error,
+                // Wiki links to notes that don't exist yet are normal while writing
+                LinkKind::Wiki => DiagnosticSeverity::Warning,
+            };
+            self.report(source, link, severity, code, message);
+            return;
+        };
+
+        let Some(heading) = link.heading.as_deref() else {
+            return;
+        };
+        // `#^block` references point at block ids, not headings
+        if heading.is_empty() || heading.starts_with('^') || !is_markdown_file(&target) {
+            return;
+        }
+
+        if !self.anchors.has_anchor(&target, heading) {
+            let message = format!("Heading \"{}\" not found in {}", heading, target.display());
+            self.report(source, link, DiagnosticSeverity::Warning, "missing-anchor", message);
+        }
+    }
+}
+
+/// Status of an external link, or none if it is fine
+fn check_url(agent: &ureq::Agent, url: &str, endpoint: Option<&str>) -> Option<(String, String)> {
+    let response = match endpoint {
+        Some(endpoint) => agent.get(endpoint).query("url", url).call(),
+        None => match agent.head(url).call() {
+            // Some servers don't support HEAD
+            Err(ureq::Error::Status(405, _)) | Err(ureq::Error::Status(501, _)) => agent.get(url).call(),
+            other => other,
+        },
+    };
+
+    match response {
+        Ok(_) => None,
+        Err(ureq::Error::Status(status, _)) => {
+            Some(("http-error".to_string(), format!("Link returned HTTP {}: {}", status, url)))
+        }
+        Err(e) => Some(("unreachable".to_string(), format!("Link unreachable: {} ({})", url, e))),
+    }
+}
+
+/// Check external links, a few at a time
+fn check_external(
+    documents: &[(PathBuf, String)],
+    options: &LinkCheckOptions,
+    diagnostics: &mut Vec<LinkDiagnostic>,
+) {
+    let timeout = Duration::from_secs(options.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+    let agent = ureq::AgentBuilder::new().timeout(timeout).build();
+
+    let mut occurrences: Vec<(&Path, &str, ExternalLink)> = Vec::new();
+    for (path, content) in documents {
+        occurrences.extend(external_links(content).into_iter().map(|link| (path.as_path(), content.as_str(), link)));
+    }
+
+    // Each URL is requested once, however often it is linked
+    let mut urls: Vec<String> = occurrences.iter().map(|(_, _, link)| link.url.clone()).collect();
+    urls.sort_unstable();
+    urls.dedup();
+
+    let chunk_size = urls.len().div_ceil(HTTP_WORKERS).max(1);
+    let failures: HashMap<String, (String, String)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = urls
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let agent = agent.clone();
+                let endpoint = options.external_endpoint.as_deref();
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .filter_map(|url| check_url(&agent, url, endpoint).map(|failure| (url.clone(), failure)))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+            .collect()
+    });
+
+    for (path, content, link) in occurrences {
+        let Some((code, message)) = failures.get(&link.url) else {
+            continue;
+        };
+
+        let lines = LineIndex::new(content);
+        let line = lines.line(link.start);
+        diagnostics.push(LinkDiagnostic {
+            path: path.display().to_string(),
+            line,
+            column: content[lines.line_start(line)..link.start].chars().count() + 1,
+            start: link.start,
+            end: link.end,
+            severity: DiagnosticSeverity::Warning,
+            code: code.clone(),
+            message: message.clone(),
+            target: link.url,
+        });
+    }
+}
+
+fn check_scope(scope_path: PathBuf, options: LinkCheckOptions) -> Vec<LinkDiagnostic> {
+    let root = match &options.root {
+        Some(root) => PathBuf::from(root),
+        None if scope_path.is_dir() => scope_path.clone(),
+        None => scope_path.parent().map(Path::to_path_buf).unwrap_or_default(),
+    };
+
+    let files = if scope_path.is_dir() {
+        markdown_files(&scope_path)
+    } else {
+        vec![scope_path]
+    };
+
+    let mut documents = Vec::new();
+    let mut unreadable = Vec::new();
+    for path in files {
+        // One bad file shouldn't hide the problems in the rest
+        match fs::read_to_string(&path) {
+            Ok(content) => documents.push((path, content)),
+            Err(e) => unreadable.push(LinkDiagnostic {
+                path: path.display().to_string(),
+                line: 1,
+                column: 1,
+                start: 0,
+                end: 0,
+                severity: DiagnosticSeverity::Error,
+                code: "unreadable-file".to_string(),
+                message: format!("Could not read file: {}", e),
+                target: path.display().to_string(),
+            }),
+        }
+    }
+
+    let index = LinkIndex::file_names(&root);
+    let mut checker = Checker {
+        index: &index,
+        anchors: AnchorCache {
+            anchors: HashMap::new(),
+        },
+        diagnostics: unreadable,
+    };
+
+    for (path, content) in &documents {
+        for link in parse_links(content) {
+            checker.check_link(path, &link);
+        }
+    }
+
+    let mut diagnostics = checker.diagnostics;
+    if options.check_external {
+        check_external(&documents, &options, &mut diagnostics);
+    }
+
+    diagnostics.sort_by(|a, b| a.path.cmp(&b.path).then(a.start.cmp(&b.start)));
+    diagnostics
+}
+
+/// Check links, anchors and images in a document or every document of a
+/// folder, returning diagnostics for a problems panel
+#[tauri::command]
+pub async fn check_links(
+    scope: String,
+    options: Option<LinkCheckOptions>,
+) -> Result<Vec<LinkDiagnostic>, LinkCheckError> {
+    let scope_path = PathBuf::from(&scope);
+
+    if !scope_path.exists() {
+        return Err(LinkCheckError::NotFound(scope));
+    }
+
+    // Reading files and requesting links block, so keep them off the async runtime
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || check_scope(scope_path, options))
+        .await
+        .map_err(|e| LinkCheckError::Task(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_unreadable_files_and_checks_the_rest() {
+        let root = std::env::temp_dir().join(format!("ourea-linkcheck-unreadable-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("bad.md"), [0xff, 0xfe, b'x']).unwrap();
+        fs::write(root.join("good.md"), "# Top\n\n[ok](good.md#top) [gone](missing.md) [[Later]] [x](good.md#nope)").unwrap();
+
+        let diagnostics = check_scope(root.clone(), LinkCheckOptions::default());
+        let codes: Vec<(&str, &str)> = diagnostics
+            .iter()
+            .map(|d| (Path::new(&d.path).file_name().unwrap().to_str().unwrap(), d.code.as_str()))
+            .collect();
+        assert_eq!(
+            codes,
+            [
+                ("bad.md", "unreadable-file"),
+                ("good.md", "missing-file"),
+                ("good.md", "missing-file"),
+                ("good.md", "missing-anchor"),
+            ]
+        );
+        assert_eq!(diagnostics[1].severity, DiagnosticSeverity::Error);
+        assert_eq!(diagnostics[2].severity, DiagnosticSeverity::Warning);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,983 @@
+use pulldown_cmark::{Event as MdEvent, LinkType, Parser, Tag, TagEnd};
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::{AppHandle, Emitter};
+
+use super::frontmatter::parse_front_matter;
+use super::markdown::{is_markdown_file, markdown_files, parser_options, LineIndex};
+use super::search::find_matches;
+use super::stats::is_cjk;
+use super::watcher::{watch_workspace, WatcherError, WorkspaceSubscription, WorkspaceWatchers};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LinkKind {
+    /// `[[Note#Heading|alias]]`
+    Wiki,
+    /// `[text](path.md#heading)` or `![alt](image.png)`
+    Markdown,
+}
+
+/// A link as written in a document, before resolution
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedLink {
+    pub kind: LinkKind,
+    /// Note name or path, without the heading
+    pub target: String,
+    pub heading: Option<String>,
+    /// Alias of a wiki link, or the text of a Markdown link
+    pub label: Option<String>,
+    /// Whether this is an image or `![[embed]]`
+    pub embed: bool,
+    /// Byte range of the whole link in the document
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+    /// The line holding the link
+    pub context: String,
+    /// Byte range of the target as written, for rewriting it in place.
+    /// Missing for reference-style links.
+    #[serde(skip)]
+    pub target_range: Option<Range<usize>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutgoingLink {
+    #[serde(flatten)]
+    pub link: ParsedLink,
+    /// Resolved file, or none if the link is broken or external
+    pub resolved: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Backlink {
+    pub source: String,
+    #[serde(flatten)]
+    pub link: ParsedLink,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkMention {
+    pub source: String,
+    /// The title or alias that was found
+    pub text: String,
+    pub line: usize,
+    pub column: usize,
+    pub start: usize,
+    pub end: usize,
+    pub preview: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkIndexReady {
+    pub root: String,
+    pub files: usize,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LinkError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Path not found: {0}")]
+    NotFound(String),
+    #[error("No workspace is open for links")]
+    NotOpen,
+    #[error("{0}")]
+    Watch(#[from] WatcherError),
+    #[error("Link task failed: {0}")]
+    Task(String),
+}
+
+impl Serialize for LinkError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[derive(Debug, Default)]
+struct FileLinks {
+    /// File name and front-matter title and aliases, for unlinked mentions
+    names: Vec<String>,
+    links: Vec<ParsedLink>,
+    /// Where each link currently resolves
+    resolved: Vec<Option<PathBuf>>,
+}
+
+/// Links of every Markdown file in a workspace
+#[derive(Debug, Default)]
+pub(crate) struct LinkIndex {
+    root: PathBuf,
+    files: HashMap<PathBuf, FileLinks>,
+    /// Lowercase file stems to the files with that name
+    by_stem: HashMap<String, Vec<PathBuf>>,
+    /// Files to the notes with links resolving to them
+    linked_from: HashMap<PathBuf, HashSet<PathBuf>>,
+    /// Lowercase stems of link targets to the notes linking that way, to
+    /// find the links a new or removed file can change
+    by_target_stem: HashMap<String, HashSet<PathBuf>>,
+}
+
+/// Link index of the open workspace
+pub struct LinkState {
+    index: Option<Arc<Mutex<LinkIndex>>>,
+    subscription: Option<WorkspaceSubscription>,
+}
+
+impl LinkState {
+    pub fn new() -> Self {
+        Self {
+            index: None,
+            subscription: None,
+        }
+    }
+}
+
+impl Default for LinkState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn wiki_link_regex() -> &'static Regex {
+    static WIKI_LINK: OnceLock<Regex> = OnceLock::new();
+    WIKI_LINK.get_or_init(|| {
+        Regex::new(r"(!?)\[\[([^\[\]|#\n]*)(?:#([^\[\]|\n]*))?(?:\|([^\[\]\n]*))?\]\]").unwrap()
+    })
+}
+
+/// Decode `%XX` escapes in a link destination
+pub(crate) fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escape = bytes.get(i + 1..i + 3).filter(|hex| bytes[i] == b'%' && hex.iter().all(u8::is_ascii_hexdigit));
+        match escape {
+            Some(hex) => {
+                let hex = std::str::from_utf8(hex).unwrap_or_default();
+                decoded.push(u8::from_str_radix(hex, 16).unwrap_or_default());
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Whether a link destination points outside the workspace
+pub(crate) fn is_external(destination: &str) -> bool {
+    let scheme_end = destination.find(':');
+    let slash = destination.find('/');
+    // `C:\notes` or `c:/notes` is a Windows path rather than a scheme
+    let is_drive = scheme_end == Some(1);
+    match scheme_end {
+        Some(end) if !is_drive => match slash {
+            Some(slash) => end < slash,
+            None => true,
+        },
+        _ => destination.starts_with("//"),
+    }
+}
+
+/// Resolve `.` and `..` without touching the disk
+pub(crate) fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+
+    normalized
+}
+
+/// Byte ranges of code, raw HTML and front matter, where nothing is a link
+pub(crate) fn code_ranges(content: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+
+    for (event, range) in Parser::new_ext(content, parser_options()).into_offset_iter() {
+        match event {
+            MdEvent::Start(Tag::CodeBlock(_))
+            | MdEvent::Start(Tag::MetadataBlock(_))
+            | MdEvent::Start(Tag::HtmlBlock)
+            | MdEvent::Code(_)
+            | MdEvent::InlineHtml(_) => ranges.push(range),
+            _ => {}
+        }
+    }
+
+    ranges
+}
+
+fn in_ranges(ranges: &[Range<usize>], offset: usize) -> bool {
+    ranges.iter().any(|range| range.contains(&offset))
+}
+
+/// A Markdown link or image whose end hasn't been reached yet
+struct OpenLink {
+    is_image: bool,
+    destination: String,
+    start: usize,
+    label: String,
+    /// End of the last event inside the label
+    label_end: usize,
+}
+
+/// Find the destination of an inline link `[label](dest "title")`, without
+/// its `#fragment`. Reference links have none.
+fn destination_range(content: &str, label_end: usize, end: usize) -> Option<Range<usize>> {
+    let inner = content.get(label_end..end)?.strip_prefix("](")?.strip_suffix(')')?;
+    let start = end - 1 - inner.len() + (inner.len() - inner.trim_start().len());
+    let text = &content[start..end - 1];
+
+    let (start, raw) = match text.strip_prefix('<') {
+        Some(rest) => (start + 1, &rest[..rest.find('>')?]),
+        None => (start, &text[..text.find(char::is_whitespace).unwrap_or(text.len())]),
+    };
+    let length = raw.find('#').unwrap_or(raw.len());
+    Some(start..start + length)
+}
+
+fn line_context(content: &str, lines: &LineIndex, line: usize) -> String {
+    let start = lines.line_start(line);
+    let end = content[start..].find('\n').map_or(content.len(), |i| start + i);
+    content[start..end].trim().chars().take(200).collect()
+}
+
+/// Find every wiki link and Markdown link or image in a document
+pub(crate) fn parse_links(content: &str) -> Vec<ParsedLink> {
+    let lines = LineIndex::new(content);
+    let skipped = code_ranges(content);
+    let mut links = Vec::new();
+
+    let make_link = |kind, target: String, heading, label, embed, range: Range<usize>, target_range| {
+        let line = lines.line(range.start);
+        ParsedLink {
+            kind,
+            target,
+            heading,
+            label,
+            embed,
+            start: range.start,
+            end: range.end,
+            line,
+            column: content[lines.line_start(line)..range.start].chars().count() + 1,
+            context: line_context(content, &lines, line),
+            target_range,
+        }
+    };
+
+    let mut open: Vec<OpenLink> = Vec::new();
+    for (event, range) in Parser::new_ext(content, parser_options()).into_offset_iter() {
+        match event {
+            MdEvent::Start(Tag::Link { link_type, dest_url, .. }) => {
+                // Autolinks and email links are never local files
+                let local = !matches!(link_type, LinkType::Autolink | LinkType::Email);
+                open.push(OpenLink {
+                    is_image: false,
+                    destination: if local { dest_url.to_string() } else { String::new() },
+                    start: range.start,
+                    label: String::new(),
+                    label_end: range.start + 1,
+                });
+            }
+            MdEvent::Start(Tag::Image { dest_url, .. }) => {
+                open.push(OpenLink {
+                    is_image: true,
+                    destination: dest_url.to_string(),
+                    start: range.start,
+                    label: String::new(),
+                    label_end: range.start + 2,
+                });
+            }
+            MdEvent::End(TagEnd::Link) | MdEvent::End(TagEnd::Image) => {
+                let Some(link) = open.pop() else {
+                    continue;
+                };
+                if let Some(parent) = open.last_mut() {
+                    parent.label_end = range.end;
+                }
+                if link.destination.is_empty() || is_external(&link.destination) {
+                    continue;
+                }
+
+                let (target, heading) = match link.destination.split_once('#') {
+                    Some((target, heading)) => (target, Some(percent_decode(heading))),
+                    None => (link.destination.as_str(), None),
+                };
+                links.push(make_link(
+                    LinkKind::Markdown,
+                    percent_decode(target),
+                    heading,
+                    Some(link.label).filter(|l| !l.is_empty()),
+                    link.is_image,
+                    link.start..range.end,
+                    destination_range(content, link.label_end, range.end),
+                ));
+            }
+            other => {
+                if let Some(link) = open.last_mut() {
+                    if let MdEvent::Text(text) | MdEvent::Code(text) = other {
+                        link.label.push_str(&text);
+                    }
+                    link.label_end = range.end;
+                }
+            }
+        }
+    }
+
+    for captures in wiki_link_regex().captures_iter(content) {
+        let whole = captures.get(0).unwrap();
+        if in_ranges(&skipped, whole.start()) {
+            continue;
+        }
+
+        let text = |i: usize| captures.get(i).map(|m| m.as_str().trim().to_string());
+        let target = captures.get(2).unwrap();
+        let leading = target.as_str().len() - target.as_str().trim_start().len();
+        let target_start = target.start() + leading;
+        links.push(make_link(
+            LinkKind::Wiki,
+            text(2).unwrap_or_default(),
+            text(3).filter(|h| !h.is_empty()),
+            text(4).filter(|a| !a.is_empty()),
+            !captures[1].is_empty(),
+            whole.range(),
+            Some(target_start..target_start + target.as_str().trim().len()),
+        ));
+    }
+
+    links.sort_by_key(|link| link.start);
+    links
+}
+
+/// Names a note can be mentioned by: its file name, title and aliases
+fn note_names(path: &Path, content: &str) -> Vec<String> {
+    let mut names: Vec<String> = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .into_iter()
+        .collect();
+
+    if let Ok(Some(front_matter)) = parse_front_matter(content) {
+        if let Some(title) = front_matter.data.get("title").and_then(Value::as_str) {
+            names.push(title.to_string());
+        }
+        match front_matter.data.get("aliases") {
+            Some(Value::Array(aliases)) => {
+                names.extend(aliases.iter().filter_map(Value::as_str).map(str::to_string));
+            }
+            Some(Value::String(alias)) => names.push(alias.clone()),
+            _ => {}
+        }
+    }
+
+    let mut seen = HashSet::new();
+    names.retain(|name| !name.trim().is_empty() && seen.insert(name.to_lowercase()));
+    names
+}
+
+fn stem_key(path: &Path) -> Option<String> {
+    path.file_stem().map(|stem| stem.to_string_lossy().to_lowercase())
+}
+
+/// The stem a link target names: `note` for `[[Folder/Note]]` or
+/// `../folder/note.md`
+fn target_stem(link: &ParsedLink) -> Option<String> {
+    let target = link.target.replace('\\', "/");
+    let name = target.rsplit('/').next()?;
+    stem_key(Path::new(name)).filter(|stem| !stem.is_empty())
+}
+
+impl LinkIndex {
+    fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            ..Default::default()
+        }
+    }
+
+    /// Index only the file names of a workspace, which is enough to
+    /// resolve links without parsing every note
+    pub(crate) fn file_names(root: &Path) -> Self {
+        let mut index = Self::new(root.to_path_buf());
+
+        for path in markdown_files(root) {
+            if let Some(key) = stem_key(&path) {
+                index.by_stem.entry(key).or_default().push(path.clone());
+            }
+            index.files.insert(path, FileLinks::default());
+        }
+
+        index
+    }
+
+    /// Drop a note's links from the reverse maps
+    fn unlink(&mut self, source: &Path) {
+        let Some(file) = self.files.get(source) else {
+            return;
+        };
+
+        for target in file.resolved.iter().flatten() {
+            if let Some(sources) = self.linked_from.get_mut(target) {
+                sources.remove(source);
+                if sources.is_empty() {
+                    self.linked_from.remove(target);
+                }
+            }
+        }
+        for stem in file.links.iter().filter_map(target_stem) {
+            if let Some(sources) = self.by_target_stem.get_mut(&stem) {
+                sources.remove(source);
+                if sources.is_empty() {
+                    self.by_target_stem.remove(&stem);
+                }
+            }
+        }
+    }
+
+    /// Resolve a note's links again and add them to the reverse maps
+    fn relink(&mut self, source: &Path) {
+        self.unlink(source);
+        let Some(file) = self.files.get(source) else {
+            return;
+        };
+
+        let resolved: Vec<Option<PathBuf>> = file.links.iter().map(|link| self.resolve(link, Some(source))).collect();
+        let stems: Vec<String> = file.links.iter().filter_map(target_stem).collect();
+
+        for target in resolved.iter().flatten() {
+            self.linked_from.entry(target.clone()).or_default().insert(source.to_path_buf());
+        }
+        for stem in stems {
+            self.by_target_stem.entry(stem).or_default().insert(source.to_path_buf());
+        }
+        if let Some(file) = self.files.get_mut(source) {
+            file.resolved = resolved;
+        }
+    }
+
+    /// Notes whose links may resolve differently once a file appears or
+    /// disappears
+    fn affected_by(&self, path: &Path) -> HashSet<PathBuf> {
+        let mut sources: HashSet<PathBuf> = stem_key(path)
+            .and_then(|stem| self.by_target_stem.get(&stem))
+            .cloned()
+            .unwrap_or_default();
+        sources.extend(self.linked_from.get(path).into_iter().flatten().cloned());
+        sources
+    }
+
+    fn remove(&mut self, path: &Path) {
+        self.unlink(path);
+        if self.files.remove(path).is_some() {
+            if let Some(paths) = stem_key(path).and_then(|key| self.by_stem.get_mut(&key)) {
+                paths.retain(|p| p != path);
+            }
+        }
+
+        for source in self.affected_by(path) {
+            self.relink(&source);
+        }
+    }
+
+    fn update(&mut self, path: &Path, content: &str) {
+        let file = FileLinks {
+            names: note_names(path, content),
+            links: parse_links(content),
+            resolved: Vec::new(),
+        };
+
+        self.unlink(path);
+        let added = self.files.insert(path.to_path_buf(), file).is_none();
+        if added {
+            if let Some(key) = stem_key(path) {
+                self.by_stem.entry(key).or_default().push(path.to_path_buf());
+            }
+        }
+        self.relink(path);
+
+        if added {
+            for source in self.affected_by(path) {
+                if source != path {
+                    self.relink(&source);
+                }
+            }
+        }
+    }
+
+    /// Resolve a wiki link target like Obsidian: by note name, or by a path
+    /// suffix when it contains a slash. Ties go to the note in the same
+    /// folder as the source, then to the shortest path.
+    fn resolve_wiki(&self, target: &str, from: Option<&Path>) -> Option<PathBuf> {
+        let target = target.trim().replace('\\', "/");
+        if target.is_empty() {
+            return from.map(Path::to_path_buf);
+        }
+
+        let lower = target.to_lowercase();
+        let stripped = lower
+            .strip_suffix(".md")
+            .or_else(|| lower.strip_suffix(".markdown"))
+            .unwrap_or(&lower);
+        let has_other_extension = stripped == lower && Path::new(&lower).extension().is_some();
+
+        // Attachments such as `![[diagram.png]]` are looked up as paths
+        if has_other_extension {
+            let candidates = from
+                .and_then(Path::parent)
+                .map(|dir| dir.join(&target))
+                .into_iter()
+                .chain(std::iter::once(self.root.join(&target)));
+            return candidates.map(|p| normalize_path(&p)).find(|p| p.exists());
+        }
+
+        let name = stripped.rsplit('/').next().unwrap_or(stripped);
+        let candidates = self.by_stem.get(name)?.iter().filter(|path| {
+            if !stripped.contains('/') {
+                return true;
+            }
+            let relative = path.strip_prefix(&self.root).unwrap_or(path).with_extension("");
+            let relative = relative.to_string_lossy().replace('\\', "/").to_lowercase();
+            relative == stripped || relative.ends_with(&format!("/{}", stripped))
+        });
+
+        let folder = from.and_then(Path::parent);
+        candidates
+            .min_by_key(|path| {
+                let same_folder = folder.is_some_and(|folder| path.parent() == Some(folder));
+                (!same_folder, path.components().count(), path.to_path_buf())
+            })
+            .cloned()
+    }
+
+    /// Resolve a relative Markdown link destination against its document
+    fn resolve_markdown(&self, target: &str, from: Option<&Path>) -> Option<PathBuf> {
+        if target.is_empty() {
+            return from.map(Path::to_path_buf);
+        }
+
+        let path = match target.strip_prefix('/') {
+            // Root-relative links start at the workspace
+            Some(rest) => self.root.join(rest),
+            None => from.and_then(Path::parent).unwrap_or(&self.root).join(target),
+        };
+        let path = normalize_path(&path);
+
+        if path.exists() {
+            return Some(path);
+        }
+        // `[Note](Note)` commonly leaves out the extension
+        let with_extension = path.with_extension("md");
+        (path.extension().is_none() && with_extension.exists()).then_some(with_extension)
+    }
+
+    pub(crate) fn resolve(&self, link: &ParsedLink, from: Option<&Path>) -> Option<PathBuf> {
+        match link.kind {
+            LinkKind::Wiki => self.resolve_wiki(&link.target, from),
+            LinkKind::Markdown => self.resolve_markdown(&link.target, from),
+        }
+    }
+
+    /// Notes other than the target itself that link to it
+    fn linking_notes<'a>(&'a self, target: &'a Path) -> impl Iterator<Item = &'a PathBuf> + 'a {
+        self.linked_from
+            .get(target)
+            .into_iter()
+            .flatten()
+            // Links to a heading of the same note aren't backlinks
+            .filter(move |source| *source != target)
+    }
+
+    fn backlinks(&self, target: &Path) -> Vec<Backlink> {
+        let mut backlinks: Vec<Backlink> = self
+            .linking_notes(target)
+            .filter_map(|source| Some((source, self.files.get(source)?)))
+            .flat_map(|(source, file)| {
+                file.links
+                    .iter()
+                    .zip(&file.resolved)
+                    .filter(|(_, resolved)| resolved.as_deref() == Some(target))
+                    .map(move |(link, _)| Backlink {
+                        source: source.display().to_string(),
+                        link: link.clone(),
+                    })
+            })
+            .collect();
+
+        backlinks.sort_by(|a, b| a.source.cmp(&b.source).then(a.link.start.cmp(&b.link.start)));
+        backlinks
+    }
+}
+
+fn update_path(index: &Mutex<LinkIndex>, path: &Path) {
+    if path.is_dir() {
+        return;
+    }
+
+    if !path.exists() {
+        let mut index = index.lock().unwrap();
+        let removed: Vec<PathBuf> = index.files.keys().filter(|p| p.starts_with(path)).cloned().collect();
+        for file in removed {
+            index.remove(&file);
+        }
+        return;
+    }
+
+    if is_markdown_file(path) {
+        if let Ok(content) = fs::read_to_string(path) {
+            index.lock().unwrap().update(path, &content);
+        }
+    }
+}
+
+fn current_index(state: &tauri::State<'_, Arc<Mutex<LinkState>>>) -> Result<Arc<Mutex<LinkIndex>>, LinkError> {
+    state.lock().unwrap().index.clone().ok_or(LinkError::NotOpen)
+}
+
+/// Build the link index of a workspace in the background, emitting
+/// `link-index-ready` when done. It is kept current by a file watcher.
+#[tauri::command]
+pub fn open_link_index(
+    app: AppHandle,
+    root: String,
+    state: tauri::State<'_, Arc<Mutex<LinkState>>>,
+    watchers: tauri::State<'_, Arc<Mutex<WorkspaceWatchers>>>,
+) -> Result<(), LinkError> {
+    let root_path = PathBuf::from(&root);
+
+    if !root_path.exists() {
+        return Err(LinkError::NotFound(root));
+    }
+
+    let index = Arc::new(Mutex::new(LinkIndex::new(root_path.clone())));
+
+    let watched = index.clone();
+    let subscription = watch_workspace(&watchers, &root_path, move |path| update_path(&watched, path))?;
+
+    let building = index.clone();
+    std::thread::spawn(move || {
+        for path in markdown_files(&root_path) {
+            if let Ok(content) = fs::read_to_string(&path) {
+                building.lock().unwrap().update(&path, &content);
+            }
+        }
+
+        let files = building.lock().unwrap().files.len();
+        let _ = app.emit(
+            "link-index-ready",
+            LinkIndexReady {
+                root: root_path.display().to_string(),
+                files,
+            },
+        );
+    });
+
+    let mut state = state.lock().unwrap();
+    state.index = Some(index);
+    state.subscription = Some(subscription);
+
+    Ok(())
+}
+
+/// Get every link in other notes that resolves to a file
+#[tauri::command]
+pub fn get_backlinks(path: String, state: tauri::State<'_, Arc<Mutex<LinkState>>>) -> Result<Vec<Backlink>, LinkError> {
+    let index = current_index(&state)?;
+    let index = index.lock().unwrap();
+    Ok(index.backlinks(Path::new(&path)))
+}
+
+/// Get the links of a document, resolved to files where possible
+#[tauri::command]
+pub fn get_outgoing_links(
+    path: String,
+    state: tauri::State<'_, Arc<Mutex<LinkState>>>,
+) -> Result<Vec<OutgoingLink>, LinkError> {
+    let source = Path::new(&path);
+
+    if !source.exists() {
+        return Err(LinkError::NotFound(path));
+    }
+
+    // Parse from disk so links in files outside the index work too
+    let content = fs::read_to_string(source)?;
+    let index = current_index(&state)?;
+    let index = index.lock().unwrap();
+
+    Ok(parse_links(&content)
+        .into_iter()
+        .map(|link| OutgoingLink {
+            resolved: index.resolve(&link, Some(source)).map(|p| p.display().to_string()),
+            link,
+        })
+        .collect())
+}
+
+/// Resolve link text, such as `Note#Heading|alias` or `../notes/a.md`, to
+/// a file. Relative paths are resolved against `from` when given.
+#[tauri::command]
+pub fn resolve_link(
+    text: String,
+    from: Option<String>,
+    state: tauri::State<'_, Arc<Mutex<LinkState>>>,
+) -> Result<Option<String>, LinkError> {
+    let index = current_index(&state)?;
+    let index = index.lock().unwrap();
+    let from = from.as_deref().map(Path::new);
+
+    let text = text.trim();
+    let inner = text.strip_prefix("[[").and_then(|t| t.strip_suffix("]]")).unwrap_or(text);
+    let target = inner.split(['|', '#']).next().unwrap_or_default();
+
+    // Anything that looks like a path is tried as one first
+    let resolved = if target.contains('/') || target.contains('\\') || Path::new(target).extension().is_some() {
+        index
+            .resolve_markdown(&percent_decode(target), from)
+            .or_else(|| index.resolve_wiki(target, from))
+    } else {
+        index.resolve_wiki(target, from)
+    };
+
+    Ok(resolved.map(|p| p.display().to_string()))
+}
+
+/// Regex for mentions of a note name. Names are only matched as whole
+/// words at ends that are word characters: punctuation has no boundary
+/// next to a space, and CJK text has none between characters.
+fn mention_pattern(name: &str) -> String {
+    let is_word = |c: char| (c.is_alphanumeric() || c == '_') && !is_cjk(c);
+    let start = if name.starts_with(is_word) { r"\b" } else { "" };
+    let end = if name.ends_with(is_word) { r"\b" } else { "" };
+    format!("{}{}{}", start, regex::escape(name), end)
+}
+
+fn unlinked_mentions(index: &Mutex<LinkIndex>, target: &Path) -> Vec<LinkMention> {
+    let (names, sources) = {
+        let index = index.lock().unwrap();
+        let names = match index.files.get(target) {
+            Some(file) => file.names.clone(),
+            None => note_names(target, &fs::read_to_string(target).unwrap_or_default()),
+        };
+        let linking: HashSet<&PathBuf> = index.linking_notes(target).collect();
+        let mut sources: Vec<PathBuf> = index
+            .files
+            .keys()
+            .filter(|p| *p != target && !linking.contains(p))
+            .cloned()
+            .collect();
+        sources.sort();
+        (names, sources)
+    };
+
+    let patterns: Vec<(String, Regex)> = names
+        .into_iter()
+        .filter_map(|name| {
+            let regex = RegexBuilder::new(&mention_pattern(&name)).case_insensitive(true).build().ok()?;
+            Some((name, regex))
+        })
+        .collect();
+
+    let mut mentions: Vec<LinkMention> = Vec::new();
+    for source in sources {
+        let Ok(content) = fs::read_to_string(&source) else {
+            continue;
+        };
+
+        // Mentions inside links, code or front matter don't count
+        let mut skipped = code_ranges(&content);
+        skipped.extend(parse_links(&content).into_iter().map(|link| link.start..link.end));
+
+        let source = source.display().to_string();
+        // A title and an alias can overlap
+        let mut starts = HashSet::new();
+        for (name, regex) in &patterns {
+            for found in find_matches(&content, regex) {
+                if in_ranges(&skipped, found.start) || !starts.insert(found.start) {
+                    continue;
+                }
+                mentions.push(LinkMention {
+                    source: source.clone(),
+                    text: name.clone(),
+                    line: found.line,
+                    column: found.column,
+                    start: found.start,
+                    end: found.end,
+                    preview: found.preview,
+                });
+            }
+        }
+    }
+
+    mentions
+}
+
+/// Find plain-text mentions of a note's name, title or aliases in the
+/// other notes that don't link to it yet
+#[tauri::command]
+pub async fn get_unlinked_mentions(
+    path: String,
+    state: tauri::State<'_, Arc<Mutex<LinkState>>>,
+) -> Result<Vec<LinkMention>, LinkError> {
+    let index = current_index(&state)?;
+
+    // Every other note is read and scanned, so keep it off the main thread
+    tauri::async_runtime::spawn_blocking(move || unlinked_mentions(&index, Path::new(&path)))
+        .await
+        .map_err(|e| LinkError::Task(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Workspace(PathBuf);
+
+    impl Workspace {
+        fn new(name: &str) -> Self {
+            let root = std::env::temp_dir().join(format!("ourea-links-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&root);
+            fs::create_dir_all(&root).unwrap();
+            Self(root)
+        }
+
+        fn write(&self, index: &Mutex<LinkIndex>, name: &str, content: &str) -> PathBuf {
+            let path = self.0.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, content).unwrap();
+            update_path(index, &path);
+            path
+        }
+
+        fn delete(&self, index: &Mutex<LinkIndex>, name: &str) {
+            let path = self.0.join(name);
+            fs::remove_file(&path).unwrap();
+            update_path(index, &path);
+        }
+    }
+
+    impl Drop for Workspace {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn sources(index: &Mutex<LinkIndex>, target: &Path) -> Vec<String> {
+        let index = index.lock().unwrap();
+        let mut sources: Vec<String> = index
+            .backlinks(target)
+            .into_iter()
+            .map(|b| Path::new(&b.source).file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        sources.dedup();
+        sources
+    }
+
+    #[test]
+    fn parses_wiki_and_markdown_links() {
+        let links = parse_links("See [[Note#Part|alias]], ![img](a%20b.png) and `[[code]]`.\n\n[x](https://e.com)");
+
+        assert_eq!(links.len(), 2);
+        assert_eq!((links[0].kind, links[0].target.as_str()), (LinkKind::Wiki, "Note"));
+        assert_eq!((links[0].heading.as_deref(), links[0].label.as_deref()), (Some("Part"), Some("alias")));
+        assert_eq!((links[1].kind, links[1].target.as_str(), links[1].embed), (LinkKind::Markdown, "a b.png", true));
+    }
+
+    #[test]
+    fn keeps_backlinks_current_as_files_change() {
+        let workspace = Workspace::new("backlinks");
+        let index = Mutex::new(LinkIndex::new(workspace.0.clone()));
+
+        let a = workspace.write(&index, "a.md", "[[Target]] and [again](sub/target.md#top) and [[Self]]");
+        workspace.write(&index, "b.md", "Link to [[target]]");
+        // Nothing to resolve to yet
+        let target = workspace.0.join("sub/target.md");
+        assert!(index.lock().unwrap().linked_from.is_empty());
+
+        workspace.write(&index, "sub/target.md", "# Target");
+        assert_eq!(sources(&index, &target), ["a.md", "b.md"]);
+        assert_eq!(index.lock().unwrap().backlinks(&target).len(), 3);
+
+        workspace.write(&index, "b.md", "No links any more");
+        assert_eq!(sources(&index, &target), ["a.md"]);
+
+        // A closer note with the same name takes over the wiki link
+        let closer = workspace.write(&index, "target.md", "");
+        assert_eq!(sources(&index, &closer), ["a.md"]);
+        assert_eq!(index.lock().unwrap().backlinks(&target).len(), 1);
+
+        workspace.delete(&index, "target.md");
+        assert_eq!(index.lock().unwrap().backlinks(&target).len(), 2);
+
+        workspace.delete(&index, "sub/target.md");
+        assert!(index.lock().unwrap().backlinks(&target).is_empty());
+        assert_eq!(index.lock().unwrap().files[&a].resolved, vec![None, None, None]);
+    }
+
+    #[test]
+    fn finds_unlinked_mentions_in_notes_without_links() {
+        let workspace = Workspace::new("mentions");
+        let index = Mutex::new(LinkIndex::new(workspace.0.clone()));
+
+        let target = workspace.write(&index, "Rust.md", "---\naliases: [Rust language, rust]\n---\n");
+        workspace.write(&index, "linked.md", "[[Rust]] is nice, and Rust is fast");
+        workspace.write(&index, "plain.md", "I like Rust language.\n\n`Rust` in code\n\nrusty");
+
+        let mentions = unlinked_mentions(&index, &target);
+        assert_eq!(mentions.len(), 1);
+        assert!(mentions[0].source.ends_with("plain.md"));
+        assert_eq!((mentions[0].line, mentions[0].column), (1, 8));
+    }
+
+    #[test]
+    fn finds_cjk_and_punctuated_mentions() {
+        let workspace = Workspace::new("mentions-cjk");
+        let index = Mutex::new(LinkIndex::new(workspace.0.clone()));
+
+        let target = workspace.write(&index, "读书笔记.md", "---\naliases: [C++]\n---\n");
+        workspace.write(&index, "diary.md", "今天整理了读书笔记。\n\nC++ is fine, but C++11 counts too\n");
+        workspace.write(&index, "other.md", "Notes on CPP and读书笔记s\n");
+
+        let mut mentions: Vec<(String, usize, usize)> = unlinked_mentions(&index, &target)
+            .into_iter()
+            .map(|m| (m.text, m.line, m.column))
+            .collect();
+        mentions.sort();
+        assert_eq!(
+            mentions,
+            [
+                ("C++".to_string(), 3, 1),
+                ("C++".to_string(), 3, 18),
+                ("读书笔记".to_string(), 1, 6),
+                ("读书笔记".to_string(), 1, 17)
+            ]
+        );
+        assert_eq!(mention_pattern("Rust"), r"\bRust\b");
+        assert_eq!(mention_pattern("笔记"), "笔记");
+    }
+}
@@ -0,0 +1,496 @@
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use super::linkcheck::DiagnosticSeverity;
+use super::markdown::{parser_options, LineIndex};
+use super::settings::{read_settings, with_workspace_overrides};
+
+/// Lint configuration looked up in the workspace `.ourea` folder
+const CONFIG_FILE: &str = "lint.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListMarkerStyle {
+    /// Whatever marker the document uses first
+    Consistent,
+    Dash,
+    Asterisk,
+    Plus,
+    Off,
+}
+
+impl ListMarkerStyle {
+    fn marker(self) -> Option<char> {
+        match self {
+            ListMarkerStyle::Dash => Some('-'),
+            ListMarkerStyle::Asterisk => Some('*'),
+            ListMarkerStyle::Plus => Some('+'),
+            ListMarkerStyle::Consistent | ListMarkerStyle::Off => None,
+        }
+    }
+}
+
+/// Which rules run. Keys are the same in `AppSettings` and in
+/// `.ourea/lint.toml`, where any key given overrides the app setting.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LintConfig {
+    pub heading_increment: bool,
+    pub no_duplicate_heading: bool,
+    pub no_trailing_spaces: bool,
+    pub ul_style: ListMarkerStyle,
+    pub no_bare_urls: bool,
+    /// Longest allowed line in characters, or 0 to allow any. Off by
+    /// default since paragraphs are usually written on one line.
+    pub line_length: usize,
+    pub no_alt_text: bool,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            heading_increment: true,
+            no_duplicate_heading: true,
+            no_trailing_spaces: true,
+            ul_style: ListMarkerStyle::Consistent,
+            no_bare_urls: true,
+            line_length: 0,
+            no_alt_text: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LintFix {
+    /// Byte range to replace
+    pub start: usize,
+    pub end: usize,
+    pub replacement: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LintDiagnostic {
+    /// Rule name, such as `heading-increment`
+    pub rule: String,
+    pub message: String,
+    pub severity: DiagnosticSeverity,
+    pub line: usize,
+    pub column: usize,
+    /// Byte range of the problem in the document
+    pub start: usize,
+    pub end: usize,
+    pub fix: Option<LintFix>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LintError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("File not found: {0}")]
+    NotFound(String),
+    #[error("Either a path or content is required")]
+    MissingInput,
+    #[error("Invalid lint config: {0}")]
+    Config(String),
+}
+
+impl Serialize for LintError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+fn url_regex() -> &'static Regex {
+    static URL: OnceLock<Regex> = OnceLock::new();
+    URL.get_or_init(|| Regex::new(r"\bhttps?://[^\s<>]+").unwrap())
+}
+
+struct Linter<'a> {
+    content: &'a str,
+    lines: LineIndex,
+    diagnostics: Vec<LintDiagnostic>,
+}
+
+impl Linter<'_> {
+    fn report(&mut self, rule: &str, range: Range<usize>, message: String, fix: Option<(Range<usize>, String)>) {
+        let line = self.lines.line(range.start);
+        let column = self.content[self.lines.line_start(line)..range.start].chars().count() + 1;
+
+        self.diagnostics.push(LintDiagnostic {
+            rule: rule.to_string(),
+            message,
+            severity: DiagnosticSeverity::Warning,
+            line,
+            column,
+            start: range.start,
+            end: range.end,
+            fix: fix.map(|(range, replacement)| LintFix {
+                start: range.start,
+                end: range.end,
+                replacement,
+            }),
+        });
+    }
+
+    /// Range of the `#` run of an ATX heading, none for setext headings
+    fn atx_marker(&self, heading: &Range<usize>) -> Option<Range<usize>> {
+        let text = &self.content[heading.clone()];
+        let start = heading.start + text.len() - text.trim_start_matches(' ').len();
+        let hashes = self.content[start..].bytes().take_while(|&b| b == b'#').count();
+        (hashes > 0).then(|| start..start + hashes)
+    }
+
+    fn check_bare_urls(&mut self, text: Range<usize>) {
+        let content = self.content;
+        for found in url_regex().find_iter(&content[text.clone()]) {
+            // Sentence punctuation after a URL is not part of it
+            let url = found.as_str().trim_end_matches(['.', ',', ';', ':', '!', '?', '\'', '"', ')']);
+            let start = text.start + found.start();
+            let range = start..start + url.len();
+            let message = format!("Bare URL used: {}", url);
+            self.report("no-bare-urls", range.clone(), message, Some((range, format!("<{}>", url))));
+        }
+    }
+
+    /// Rules that follow the document structure
+    fn check_events(&mut self, config: &LintConfig, skipped: &mut Vec<Range<usize>>) {
+        let content = self.content;
+        let mut previous_level: Option<u8> = None;
+        let mut heading: Option<(Range<usize>, u8, String)> = None;
+        let mut seen_headings: HashMap<String, usize> = HashMap::new();
+        let mut list_stack: Vec<bool> = Vec::new();
+        let mut expected_marker = config.ul_style.marker();
+        let mut image: Option<(Range<usize>, String)> = None;
+        // Inside code, links, images and metadata, text is not prose
+        let mut skip_depth = 0;
+        let mut text_run: Option<Range<usize>> = None;
+
+        for (event, range) in Parser::new_ext(content, parser_options()).into_offset_iter() {
+            match &event {
+                Event::Text(_) if skip_depth == 0 => {
+                    // Text can arrive in pieces, so URLs are looked for in whole runs
+                    match text_run.as_mut() {
+                        Some(run) if run.end == range.start => run.end = range.end,
+                        _ => {
+                            if let Some(run) = text_run.replace(range.clone()) {
+                                if config.no_bare_urls {
+                                    self.check_bare_urls(run);
+                                }
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    if let Some(run) = text_run.take() {
+                        if config.no_bare_urls {
+                            self.check_bare_urls(run);
+                        }
+                    }
+                }
+            }
+
+            match event {
+                Event::Start(Tag::Heading { level, .. }) => {
+                    let end = range.start + content[range.clone()].trim_end().len();
+                    heading = Some((range.start..end, level as u8, String::new()));
+                }
+                Event::End(TagEnd::Heading(_)) => {
+                    let Some((range, level, text)) = heading.take() else {
+                        continue;
+                    };
+                    let text = text.trim().to_string();
+
+                    if config.heading_increment {
+                        if let Some(previous) = previous_level.filter(|&p| level > p + 1) {
+                            let message = format!("Heading level jumps from {} to {}", previous, level);
+                            let fix = self
+                                .atx_marker(&range)
+                                .map(|marker| (marker, "#".repeat(previous as usize + 1)));
+                            self.report("heading-increment", range.clone(), message, fix);
+                        }
+                    }
+                    previous_level = Some(level);
+
+                    if config.no_duplicate_heading && !text.is_empty() {
+                        if let Some(&line) = seen_headings.get(&text) {
+                            let message = format!("Duplicate heading \"{}\", first used on line {}", text, line);
+                            self.report("no-duplicate-heading", range, message, None);
+                        } else {
+                            seen_headings.insert(text, self.lines.line(range.start));
+                        }
+                    }
+                }
+                Event::Text(text) | Event::Code(text) => {
+                    if let Some((_, _, heading_text)) = heading.as_mut() {
+                        heading_text.push_str(&text);
+                    }
+                    if let Some((_, alt)) = image.as_mut() {
+                        alt.push_str(&text);
+                    }
+                }
+                Event::Start(Tag::List(first)) => list_stack.push(first.is_none()),
+                Event::End(TagEnd::List(_)) => {
+                    list_stack.pop();
+                }
+                Event::Start(Tag::Item) if list_stack.last() == Some(&true) => {
+                    if config.ul_style == ListMarkerStyle::Off {
+                        continue;
+                    }
+                    let text = &content[range.clone()];
+                    let offset = range.start + text.len() - text.trim_start().len();
+                    let Some(marker) = content[offset..].chars().next() else {
+                        continue;
+                    };
+                    match expected_marker {
+                        None => expected_marker = Some(marker),
+                        Some(expected) if expected != marker => {
+                            let message = format!("List marker '{}' should be '{}'", marker, expected);
+                            let marker_range = offset..offset + marker.len_utf8();
+                            self.report("ul-style", marker_range.clone(), message, Some((marker_range, expected.to_string())));
+                        }
+                        Some(_) => {}
+                    }
+                }
+                Event::Start(Tag::Image { .. }) => {
+                    skip_depth += 1;
+                    image = Some((range, String::new()));
+                }
+                Event::End(TagEnd::Image) => {
+                    skip_depth -= 1;
+                    if let Some((range, alt)) = image.take() {
+                        if config.no_alt_text && alt.trim().is_empty() {
+                            self.report("no-alt-text", range, "Image has no alt text".to_string(), None);
+                        }
+                    }
+                }
+                Event::Start(Tag::CodeBlock(_)) | Event::Start(Tag::MetadataBlock(_)) => {
+                    skip_depth += 1;
+                    skipped.push(range);
+                }
+                Event::End(TagEnd::CodeBlock) | Event::End(TagEnd::MetadataBlock(_)) => skip_depth -= 1,
+                // Table rows are prose but can't be wrapped
+                Event::Start(Tag::Table(_)) => skipped.push(range),
+                Event::Start(Tag::Link { .. }) => skip_depth += 1,
+                Event::End(TagEnd::Link) => skip_depth -= 1,
+                _ => {}
+            }
+        }
+
+        if let Some(run) = text_run.take() {
+            if config.no_bare_urls {
+                self.check_bare_urls(run);
+            }
+        }
+    }
+
+    /// Rules that look at raw lines, outside code blocks, tables and front matter
+    fn check_lines(&mut self, config: &LintConfig, skipped: &[Range<usize>]) {
+        let content = self.content;
+        let mut offset = 0;
+
+        for raw_line in content.split_inclusive('\n') {
+            let start = offset;
+            offset += raw_line.len();
+            if skipped.iter().any(|range| range.contains(&start)) {
+                continue;
+            }
+
+            let line = raw_line.trim_end_matches(['\n', '\r']);
+            let trimmed = line.trim_end_matches([' ', '\t']);
+            let trailing = &line[trimmed.len()..];
+
+            // Two spaces at the end of a line are a hard break
+            let hard_break = trailing == "  " && !trimmed.trim().is_empty();
+            if config.no_trailing_spaces && !trailing.is_empty() && !hard_break {
+                let range = start + trimmed.len()..start + line.len();
+                let message = format!("Trailing whitespace ({} characters)", trailing.chars().count());
+                self.report("no-trailing-spaces", range.clone(), message, Some((range, String::new())));
+            }
+
+            let length = line.chars().count();
+            if config.line_length > 0 && length > config.line_length {
+                // A long word or URL at the end is allowed to run over
+                let limit = line.char_indices().nth(config.line_length).map_or(line.len(), |(i, _)| i);
+                if line[limit..].contains(char::is_whitespace) {
+                    let range = start + limit..start + line.len();
+                    let message = format!("Line is {} characters long, the limit is {}", length, config.line_length);
+                    self.report("line-length", range, message, None);
+                }
+            }
+        }
+    }
+}
+
+/// Lint a document with the given rules
+pub(crate) fn lint(content: &str, config: &LintConfig) -> Vec<LintDiagnostic> {
+    let mut linter = Linter {
+        content,
+        lines: LineIndex::new(content),
+        diagnostics: Vec::new(),
+    };
+
+    let mut skipped = Vec::new();
+    linter.check_events(config, &mut skipped);
+    linter.check_lines(config, &skipped);
+
+    let mut diagnostics = linter.diagnostics;
+    diagnostics.sort_by_key(|d| d.start);
+    diagnostics
+}
+
+/// Lint a document from its path or unsaved content, returning positioned
+/// diagnostics with fixes where one is safe to apply
+#[tauri::command]
+pub fn lint_markdown(
+    app: tauri::AppHandle,
+    path: Option<String>,
+    content: Option<String>,
+    workspace: Option<String>,
+) -> Result<Vec<LintDiagnostic>, LintError> {
+    let content = match (content, &path) {
+        (Some(content), _) => content,
+        (None, Some(path)) => {
+            if !Path::new(path).exists() {
+                return Err(LintError::NotFound(path.clone()));
+            }
+            fs::read_to_string(path)?
+        }
+        (None, None) => return Err(LintError::MissingInput),
+    };
+
+    let start = workspace
+        .as_deref()
+        .map(Path::new)
+        .or_else(|| path.as_deref().and_then(|path| Path::new(path).parent()));
+    let settings = read_settings(&app).unwrap_or_default();
+    let config = with_workspace_overrides(&settings.lint, start, CONFIG_FILE).map_err(LintError::Config)?;
+
+    Ok(lint(&content, &config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(content: &str, config: &LintConfig) -> Vec<(String, usize)> {
+        lint(content, config).into_iter().map(|d| (d.rule, d.line)).collect()
+    }
+
+    /// Apply every fix, last first so earlier ranges stay valid
+    fn fixed(content: &str, config: &LintConfig) -> String {
+        let mut output = content.to_string();
+        let mut fixes: Vec<LintFix> = lint(content, config).into_iter().filter_map(|d| d.fix).collect();
+        fixes.sort_by_key(|fix| std::cmp::Reverse(fix.start));
+        for fix in fixes {
+            output.replace_range(fix.start..fix.end, &fix.replacement);
+        }
+        output
+    }
+
+    #[test]
+    fn checks_heading_levels_and_duplicates() {
+        let content = "# Title\n\n### Skipped\n\n## Other\n\n# Title\n";
+        let config = LintConfig::default();
+
+        assert_eq!(
+            rules(content, &config),
+            [("heading-increment".to_string(), 3), ("no-duplicate-heading".to_string(), 7)]
+        );
+        assert_eq!(fixed(content, &config), "# Title\n\n## Skipped\n\n## Other\n\n# Title\n");
+
+        let off = LintConfig {
+            heading_increment: false,
+            no_duplicate_heading: false,
+            ..LintConfig::default()
+        };
+        assert!(rules(content, &off).is_empty());
+    }
+
+    #[test]
+    fn fixes_trailing_spaces_but_keeps_hard_breaks() {
+        let content = "one \ntwo  \nthree\t\n```\ncode   \n```\n";
+        let found = lint(content, &LintConfig::default());
+
+        assert_eq!(found.iter().map(|d| d.line).collect::<Vec<_>>(), [1, 3]);
+        assert_eq!((found[0].column, found[0].message.as_str()), (4, "Trailing whitespace (1 characters)"));
+        assert_eq!(fixed(content, &LintConfig::default()), "one\ntwo  \nthree\n```\ncode   \n```\n");
+    }
+
+    #[test]
+    fn keeps_list_markers_consistent() {
+        let content = "- a\n* b\n\n1. one\n\n+ c\n";
+        assert_eq!(fixed(content, &LintConfig::default()), "- a\n- b\n\n1. one\n\n- c\n");
+
+        let asterisk = LintConfig {
+            ul_style: ListMarkerStyle::Asterisk,
+            ..LintConfig::default()
+        };
+        assert_eq!(fixed(content, &asterisk), "* a\n* b\n\n1. one\n\n* c\n");
+
+        let off = LintConfig {
+            ul_style: ListMarkerStyle::Off,
+            ..LintConfig::default()
+        };
+        assert!(rules(content, &off).is_empty());
+    }
+
+    #[test]
+    fn wraps_bare_urls_outside_links_and_code() {
+        let content = "See https://example.com/a_b. And [x](https://e.com) `https://code` <https://ok.com>\n";
+        let found = lint(content, &LintConfig::default());
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].message, "Bare URL used: https://example.com/a_b");
+        assert_eq!(
+            fixed(content, &LintConfig::default()),
+            "See <https://example.com/a_b>. And [x](https://e.com) `https://code` <https://ok.com>\n"
+        );
+    }
+
+    #[test]
+    fn flags_missing_alt_text_and_long_lines() {
+        let content = "![](a.png) ![ok](b.png)\n\nshort words over the limit here\n\nhttps://a-very-long-url-at-the-end\n";
+        let config = LintConfig {
+            line_length: 20,
+            no_bare_urls: false,
+            ..LintConfig::default()
+        };
+
+        assert_eq!(
+            rules(content, &config),
+            [("no-alt-text".to_string(), 1), ("line-length".to_string(), 3)]
+        );
+    }
+
+    #[test]
+    fn merges_workspace_overrides_into_the_app_settings() {
+        let root = std::env::temp_dir().join(format!("ourea-lint-config-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join(".ourea")).unwrap();
+        fs::create_dir_all(root.join("notes")).unwrap();
+
+        let base = LintConfig {
+            line_length: 100,
+            ..LintConfig::default()
+        };
+        fs::write(root.join(".ourea").join(CONFIG_FILE), "ulStyle = \"dash\"\nnoBareUrls = false\n").unwrap();
+        let config = with_workspace_overrides(&base, Some(&root.join("notes")), CONFIG_FILE).unwrap();
+        assert_eq!((config.ul_style, config.no_bare_urls, config.line_length), (ListMarkerStyle::Dash, false, 100));
+
+        fs::write(root.join(".ourea").join(CONFIG_FILE), "ulStyle = \"wavy\"\n").unwrap();
+        assert!(with_workspace_overrides(&base, Some(&root), CONFIG_FILE).is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,376 @@
+use pulldown_cmark::{Event, Parser, Tag};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io::Read;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::assets::html_image_sources;
+use super::file::write_atomic;
+use super::images::{document_image_settings, store_image, unsaved_images_dir, ImageError};
+use super::links::percent_decode;
+use super::markdown::parser_options;
+use super::settings::AppSettings;
+
+/// Concurrent downloads
+const DOWNLOAD_WORKERS: usize = 4;
+/// Larger responses are not images worth keeping in a notes folder
+const MAX_IMAGE_BYTES: u64 = 50 * 1024 * 1024;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalizeOptions {
+    /// Overrides the download timeout from settings
+    pub timeout_secs: Option<u64>,
+    /// Overrides the proxy from settings, such as `http://localhost:8080`
+    pub proxy: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalizedImage {
+    pub url: String,
+    /// Path written into the document in place of the URL
+    pub markdown_path: String,
+    /// Absolute path of the stored image
+    pub path: String,
+    /// How many references to the URL were rewritten
+    pub references: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadFailure {
+    pub url: String,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalizeResult {
+    /// The document with its references rewritten
+    pub content: String,
+    pub localized: Vec<LocalizedImage>,
+    pub failures: Vec<DownloadFailure>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LocalizeError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("File not found: {0}")]
+    NotFound(String),
+    #[error("Invalid proxy: {0}")]
+    Proxy(String),
+    #[error("{0}")]
+    Image(#[from] ImageError),
+    #[error("Download task failed: {0}")]
+    Task(String),
+}
+
+impl Serialize for LocalizeError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+pub(crate) fn is_remote(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+/// Byte range of a reference-style definition `[id]: url`
+fn definition_range(content: &str, url: &str) -> Option<Range<usize>> {
+    content
+        .match_indices(url)
+        .map(|(start, _)| start)
+        .find(|&start| content[..start].trim_end_matches([' ', '\t', '<']).ends_with("]:"))
+        .map(|start| start..start + url.len())
+}
+
+/// Byte ranges of every remote image URL in a document, by URL
+fn remote_images(content: &str) -> HashMap<String, BTreeSet<(usize, usize)>> {
+    let mut images: HashMap<String, BTreeSet<(usize, usize)>> = HashMap::new();
+
+    for (event, range) in Parser::new_ext(content, parser_options()).into_offset_iter() {
+        let Event::Start(Tag::Image { dest_url, .. }) = event else {
+            continue;
+        };
+        if !is_remote(&dest_url) {
+            continue;
+        }
+
+        let source = &content[range.clone()];
+        let inline = source
+            .rfind("](")
+            .and_then(|open| source[open..].find(&*dest_url).map(|i| range.start + open + i));
+        let found = match inline {
+            Some(start) => Some(start..start + dest_url.len()),
+            // `![alt][id]` points at a definition elsewhere in the document
+            None => definition_range(content, &dest_url),
+        };
+
+        if let Some(found) = found {
+            images.entry(dest_url.to_string()).or_default().insert((found.start, found.end));
+        }
+    }
+
+    for (src, offset) in html_image_sources(content) {
+        if is_remote(&src) {
+            images.entry(src.clone()).or_default().insert((offset, offset + src.len()));
+        }
+    }
+
+    images
+}
+
+/// File name at the end of a URL's path, if it has one
+fn url_file_name(url: &str) -> Option<String> {
+    let path = url.split(['?', '#']).next()?;
+    let name = percent_decode(path.rsplit('/').next()?);
+    (!name.is_empty() && !path.ends_with("//")).then_some(name)
+}
+
+pub(crate) fn download(agent: &ureq::Agent, url: &str) -> Result<Vec<u8>, String> {
+    let response = agent.get(url).call().map_err(|e| match e {
+        ureq::Error::Status(status, _) => format!("HTTP {}", status),
+        e => e.to_string(),
+    })?;
+
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .take(MAX_IMAGE_BYTES + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| e.to_string())?;
+
+    if bytes.len() as u64 > MAX_IMAGE_BYTES {
+        return Err(format!("Image is larger than {} MB", MAX_IMAGE_BYTES / 1024 / 1024));
+    }
+
+    Ok(bytes)
+}
+
+pub(crate) fn build_agent(settings: &AppSettings, options: &LocalizeOptions) -> Result<ureq::Agent, LocalizeError> {
+    let timeout = options.timeout_secs.unwrap_or(settings.download_timeout_secs);
+    let mut builder = ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(timeout))
+        .try_proxy_from_env(true);
+
+    let proxy = options.proxy.as_deref().unwrap_or(&settings.http_proxy).trim();
+    if !proxy.is_empty() {
+        builder = builder.proxy(ureq::Proxy::new(proxy).map_err(|e| LocalizeError::Proxy(e.to_string()))?);
+    }
+
+    Ok(builder.build())
+}
+
+/// Download the remote images of a document, store them next to it and
+/// point the references at the local copies
+pub(crate) fn localize(
+    doc_path: &Path,
+    content: &str,
+    settings: &AppSettings,
+    options: &LocalizeOptions,
+    unsaved_dir: &Path,
+) -> Result<LocalizeResult, LocalizeError> {
+    let agent = build_agent(settings, options)?;
+    let mut images: Vec<(String, BTreeSet<(usize, usize)>)> = remote_images(content).into_iter().collect();
+    images.sort();
+
+    let chunk_size = images.len().div_ceil(DOWNLOAD_WORKERS).max(1);
+    let downloads: Vec<Result<Vec<u8>, String>> = std::thread::scope(|scope| {
+        let workers: Vec<_> = images
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let agent = agent.clone();
+                scope.spawn(move || chunk.iter().map(|(url, _)| download(&agent, url)).collect::<Vec<_>>())
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+            .collect()
+    });
+
+    let mut localized = Vec::new();
+    let mut failures = Vec::new();
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+
+    // Images are stored one at a time so naming and dedup see earlier ones
+    for ((url, ranges), download) in images.into_iter().zip(downloads) {
+        let stored = download.and_then(|bytes| {
+            store_image(Some(doc_path), &bytes, url_file_name(&url).as_deref(), settings, unsaved_dir)
+                .map_err(|e| e.to_string())
+        });
+
+        match stored {
+            Ok(image) => {
+                edits.extend(ranges.iter().map(|&(start, end)| (start..end, image.markdown_path.clone())));
+                localized.push(LocalizedImage {
+                    url,
+                    markdown_path: image.markdown_path,
+                    path: image.path,
+                    references: ranges.len(),
+                });
+            }
+            Err(error) => failures.push(DownloadFailure { url, error }),
+        }
+    }
+
+    edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+    let mut rewritten = content.to_string();
+    for (range, path) in edits {
+        rewritten.replace_range(range, &path);
+    }
+
+    Ok(LocalizeResult {
+        content: rewritten,
+        localized,
+        failures,
+    })
+}
+
+/// Download every remote image in a document into its assets, following
+/// the image settings, and rewrite the references. With `content` the
+/// unsaved editor text is rewritten and returned; otherwise the file is
+/// updated on disk.
+#[tauri::command]
+pub async fn localize_remote_images(
+    app: tauri::AppHandle,
+    doc: String,
+    content: Option<String>,
+    options: Option<LocalizeOptions>,
+) -> Result<LocalizeResult, LocalizeError> {
+    let doc_path = PathBuf::from(&doc);
+
+    if !doc_path.is_file() {
+        return Err(LocalizeError::NotFound(doc));
+    }
+
+    let settings = document_image_settings(&app, Some(&doc_path))?;
+    let unsaved_dir = unsaved_images_dir(&app)?;
+
+    // Downloads can take up to the timeout, so keep them off the async runtime
+    tauri::async_runtime::spawn_blocking(move || {
+        let write = content.is_none();
+        let content = match content {
+            Some(content) => content,
+            None => fs::read_to_string(&doc_path)?,
+        };
+
+        let result = localize(&doc_path, &content, &settings, &options.unwrap_or_default(), &unsaved_dir)?;
+
+        if write && !result.localized.is_empty() {
+            write_atomic(&doc_path, result.content.as_bytes())?;
+        }
+
+        Ok(result)
+    })
+    .await
+    .map_err(|e| LocalizeError::Task(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::{TcpListener, TcpStream};
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\nnot really pixels";
+
+    fn respond(mut stream: TcpStream) {
+        let mut request = Vec::new();
+        let mut buffer = [0; 1024];
+        while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+            match stream.read(&mut buffer) {
+                Ok(0) | Err(_) => return,
+                Ok(read) => request.extend_from_slice(&buffer[..read]),
+            }
+        }
+        let request = String::from_utf8_lossy(&request);
+        let path = request.split_whitespace().nth(1).unwrap_or_default().to_string();
+
+        let _ = match path.as_str() {
+            "/ok.png" => {
+                let head = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", PNG.len());
+                stream.write_all(head.as_bytes()).and_then(|_| stream.write_all(PNG))
+            }
+            "/slow.png" => {
+                std::thread::sleep(Duration::from_secs(3));
+                stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+            }
+            "/huge.png" => {
+                let head = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", MAX_IMAGE_BYTES + 1);
+                let chunk = vec![0; 1024 * 1024];
+                stream.write_all(head.as_bytes()).and_then(|_| {
+                    (0..=MAX_IMAGE_BYTES / chunk.len() as u64).try_for_each(|_| stream.write_all(&chunk))
+                })
+            }
+            _ => stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n"),
+        };
+    }
+
+    /// Serve the test images on a free local port
+    fn serve() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                std::thread::spawn(move || respond(stream));
+            }
+        });
+        address
+    }
+
+    #[test]
+    fn downloads_images_and_reports_failures() {
+        let root = std::env::temp_dir().join(format!("ourea-localize-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let doc = root.join("note.md");
+
+        let server = serve();
+        let content = format!(
+            "![ok]({0}/ok.png) and ![again]({0}/ok.png)\n\n![missing]({0}/missing.png)\n\n\
+             ![slow]({0}/slow.png)\n\n![huge]({0}/huge.png)\n\n![local](./kept.png)\n",
+            server
+        );
+        let settings = AppSettings {
+            image_naming_rule: "original".to_string(),
+            ..AppSettings::default()
+        };
+        let options = LocalizeOptions {
+            timeout_secs: Some(1),
+            proxy: None,
+        };
+
+        let result = localize(&doc, &content, &settings, &options, &root.join("unsaved")).unwrap();
+
+        assert_eq!(
+            result.content,
+            format!(
+                "![ok](./ok.png) and ![again](./ok.png)\n\n![missing]({0}/missing.png)\n\n\
+                 ![slow]({0}/slow.png)\n\n![huge]({0}/huge.png)\n\n![local](./kept.png)\n",
+                server
+            )
+        );
+        assert_eq!(result.localized.len(), 1);
+        assert_eq!((result.localized[0].markdown_path.as_str(), result.localized[0].references), ("./ok.png", 2));
+        assert_eq!(fs::read(root.join("ok.png")).unwrap(), PNG);
+
+        let failures: Vec<(&str, &str)> =
+            result.failures.iter().map(|f| (f.url.strip_prefix(&server).unwrap(), f.error.as_str())).collect();
+        assert_eq!(failures.len(), 3);
+        assert_eq!(failures[0], ("/huge.png", "Image is larger than 50 MB"));
+        assert_eq!(failures[1], ("/missing.png", "HTTP 404"));
+        assert_eq!(failures[2].0, "/slow.png");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,251 @@
+use ignore::gitignore::Gitignore;
+use ignore::Match;
+use pulldown_cmark::Options;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Per-workspace ignore file, in `.gitignore` syntax
+pub(crate) const IGNORE_FILE: &str = ".oureaignore";
+/// Ignore files read by `workspace_walker`, highest precedence first
+const IGNORE_FILES: [&str; 3] = [IGNORE_FILE, ".ignore", ".gitignore"];
+
+/// Markdown extensions enabled for every backend parse
+pub(crate) fn parser_options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_MATH
+        | Options::ENABLE_GFM
+        | Options::ENABLE_HEADING_ATTRIBUTES
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+}
+
+/// Turn heading text into a GitHub-style anchor
+pub(crate) fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+
+    for c in text.trim().chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if c == ' ' || c == '-' {
+            slug.push('-');
+        } else if c == '_' {
+            slug.push('_');
+        }
+    }
+
+    slug
+}
+
+/// Hands out unique anchors within one document: `intro`, `intro-1`, ...
+#[derive(Default)]
+pub(crate) struct Slugger {
+    seen: HashMap<String, usize>,
+}
+
+impl Slugger {
+    pub fn slug(&mut self, text: &str) -> String {
+        let base = slugify(text);
+        let mut slug = base.clone();
+
+        while let Some(count) = self.seen.get_mut(&slug) {
+            *count += 1;
+            slug = format!("{}-{}", base, count);
+        }
+
+        self.seen.insert(slug.clone(), 0);
+        slug
+    }
+}
+
+/// Maps byte offsets to 1-based line numbers
+pub(crate) struct LineIndex {
+    starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        let mut starts = vec![0];
+        starts.extend(text.match_indices('\n').map(|(i, _)| i + 1));
+        Self { starts }
+    }
+
+    /// Line containing the byte offset
+    pub fn line(&self, offset: usize) -> usize {
+        match self.starts.binary_search(&offset) {
+            Ok(index) => index + 1,
+            Err(index) => index,
+        }
+    }
+
+    /// Byte offset where a 1-based line starts
+    pub fn line_start(&self, line: usize) -> usize {
+        self.starts[line.saturating_sub(1).min(self.starts.len() - 1)]
+    }
+}
+
+/// Check if a path has a Markdown extension
+pub(crate) fn is_markdown_file(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("md") | Some("markdown")
+    )
+}
+
+/// Walker over a workspace that skips hidden entries and anything matched
+/// by `.gitignore` or `.oureaignore`
+pub(crate) fn workspace_walker(root: &Path) -> ignore::WalkBuilder {
+    let mut builder = ignore::WalkBuilder::new(root);
+    builder.add_custom_ignore_filename(IGNORE_FILE);
+    builder
+}
+
+/// Tells whether a path would be skipped by `workspace_walker`, for
+/// filtering file watcher events. Ignore files are read once per folder.
+pub(crate) struct WorkspaceIgnore {
+    root: PathBuf,
+    /// `.gitignore` only counts inside a git repository, as for the walker
+    in_git: bool,
+    rules: HashMap<PathBuf, Vec<Gitignore>>,
+}
+
+impl WorkspaceIgnore {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            in_git: root.ancestors().any(|dir| dir.join(".git").exists()),
+            rules: HashMap::new(),
+        }
+    }
+
+    /// Forget the rules of a folder whose ignore file changed. Returns
+    /// whether the path is an ignore file.
+    pub fn reload(&mut self, path: &Path) -> bool {
+        let is_ignore_file = path
+            .file_name()
+            .is_some_and(|name| IGNORE_FILES.iter().any(|file| name == *file));
+        if is_ignore_file {
+            if let Some(folder) = path.parent() {
+                self.rules.remove(folder);
+            }
+        }
+        is_ignore_file
+    }
+
+    fn folder_rules(&mut self, folder: &Path) -> &[Gitignore] {
+        let in_git = self.in_git;
+        self.rules.entry(folder.to_path_buf()).or_insert_with(|| {
+            IGNORE_FILES
+                .iter()
+                .filter(|name| in_git || **name != ".gitignore")
+                .map(|name| folder.join(name))
+                .filter(|file| file.is_file())
+                .map(|file| Gitignore::new(file).0)
+                .collect()
+        })
+    }
+
+    /// Check one entry against its own name and the ignore files above it,
+    /// where the closest one decides
+    fn skips(&mut self, path: &Path, is_dir: bool) -> bool {
+        if path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.')) {
+            return true;
+        }
+
+        for folder in path.ancestors().skip(1) {
+            if !folder.starts_with(&self.root) {
+                break;
+            }
+            for rules in self.folder_rules(folder) {
+                match rules.matched(path, is_dir) {
+                    Match::Ignore(_) => return true,
+                    Match::Whitelist(_) => return false,
+                    Match::None => {}
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Whether a path is hidden or ignored, or inside a folder that is
+    pub fn is_ignored(&mut self, path: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return true;
+        };
+
+        let components: Vec<_> = relative.components().collect();
+        let mut current = self.root.clone();
+        for (i, component) in components.iter().enumerate() {
+            current.push(component);
+            let is_dir = i + 1 < components.len() || path.is_dir();
+            if self.skips(&current, is_dir) {
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+/// Collect Markdown files under a workspace root
+pub(crate) fn markdown_files(root: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = workspace_walker(root)
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| entry.into_path())
+        .filter(|path| is_markdown_file(path))
+        .collect();
+
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn ignores_what_the_walker_skips() {
+        let root = std::env::temp_dir().join(format!("ourea-markdown-ignore-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for dir in ["notes/drafts", "notes/keep", "build", ".obsidian"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        fs::write(root.join(IGNORE_FILE), "build/\n*.tmp\n").unwrap();
+        fs::write(root.join("notes").join(IGNORE_FILE), "drafts/\n!keep.tmp\n").unwrap();
+        for file in ["a.md", "b.tmp", "build/out.md", ".obsidian/app.json", "notes/c.md", "notes/keep.tmp"] {
+            fs::write(root.join(file), "x").unwrap();
+        }
+        fs::write(root.join("notes/drafts/d.md"), "x").unwrap();
+
+        let walked: Vec<PathBuf> = workspace_walker(&root)
+            .build()
+            .flatten()
+            .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+            .map(|entry| entry.into_path())
+            .collect();
+
+        let mut ignore = WorkspaceIgnore::new(&root);
+        for file in ["a.md", "notes/c.md", "notes/keep.tmp"] {
+            assert!(!ignore.is_ignored(&root.join(file)), "{}", file);
+            assert!(walked.contains(&root.join(file)), "{}", file);
+        }
+        for file in ["b.tmp", "build/out.md", "build", ".obsidian/app.json", "notes/drafts/d.md", "notes/drafts/new.md"] {
+            assert!(ignore.is_ignored(&root.join(file)), "{}", file);
+            assert!(!walked.contains(&root.join(file)), "{}", file);
+        }
+        assert!(ignore.is_ignored(Path::new("/elsewhere/a.md")));
+
+        // Rules are cached until their ignore file changes
+        fs::write(root.join(IGNORE_FILE), "*.md\n").unwrap();
+        assert!(!ignore.is_ignored(&root.join("a.md")));
+        assert!(ignore.reload(&root.join(IGNORE_FILE)));
+        assert!(ignore.is_ignored(&root.join("a.md")));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,152 @@
+use serde::Serialize;
+use tauri::menu::{IsMenuItem, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::{Emitter, Manager, Wry};
+
+use super::session::{clear_recent_lists, take_closed_tab, RecentKind, RecentLists};
+
+pub(crate) const FILE_MENU_ID: &str = "file";
+const OPEN_RECENT_ID: &str = "open_recent";
+const RECENT_FILE_PREFIX: &str = "recent_file:";
+const RECENT_FOLDER_PREFIX: &str = "recent_folder:";
+const REOPEN_CLOSED_ID: &str = "reopen_closed_tab";
+const CLEAR_RECENT_ID: &str = "clear_recent";
+/// Entries of each recent list shown in the menu
+const MENU_LIMIT: usize = 10;
+
+/// Sent to the editor when a menu item asks for a path to be opened
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenPath {
+    pub path: String,
+    pub kind: RecentKind,
+}
+
+/// Menu text for a path, shortened under the home folder. `&` is doubled
+/// so Windows doesn't take it as a mnemonic.
+fn label(path: &str) -> String {
+    let home = dirs::home_dir().map(|home| home.display().to_string());
+    let path = match home.as_deref().and_then(|home| path.strip_prefix(home)) {
+        Some(rest) if rest.starts_with(['/', '\\']) => format!("~{}", rest),
+        _ => path.to_string(),
+    };
+    path.replace('&', "&&")
+}
+
+fn recent_items(
+    app: &tauri::AppHandle,
+    recent: &RecentLists,
+    has_closed: bool,
+) -> tauri::Result<Vec<Box<dyn IsMenuItem<Wry>>>> {
+    let mut items: Vec<Box<dyn IsMenuItem<Wry>>> = Vec::new();
+
+    for entry in recent.files.iter().take(MENU_LIMIT) {
+        let id = format!("{}{}", RECENT_FILE_PREFIX, entry.path);
+        items.push(Box::new(MenuItem::with_id(app, id, label(&entry.path), true, None::<&str>)?));
+    }
+    if !recent.folders.is_empty() {
+        if !items.is_empty() {
+            items.push(Box::new(PredefinedMenuItem::separator(app)?));
+        }
+        for entry in recent.folders.iter().take(MENU_LIMIT) {
+            let id = format!("{}{}", RECENT_FOLDER_PREFIX, entry.path);
+            items.push(Box::new(MenuItem::with_id(app, id, label(&entry.path), true, None::<&str>)?));
+        }
+    }
+    if items.is_empty() {
+        items.push(Box::new(MenuItem::with_id(app, "no_recent", "No Recent Items", false, None::<&str>)?));
+    }
+
+    let has_recent = !recent.files.is_empty() || !recent.folders.is_empty();
+    items.push(Box::new(PredefinedMenuItem::separator(app)?));
+    items.push(Box::new(MenuItem::with_id(
+        app,
+        REOPEN_CLOSED_ID,
+        "Reopen Closed Tab",
+        has_closed,
+        Some("CmdOrCtrl+Shift+T"),
+    )?));
+    items.push(Box::new(PredefinedMenuItem::separator(app)?));
+    items.push(Box::new(MenuItem::with_id(app, CLEAR_RECENT_ID, "Clear Recent", has_recent, None::<&str>)?));
+
+    Ok(items)
+}
+
+/// The Open Recent submenu, filled in by `refresh_open_recent` once the
+/// recent lists are loaded
+pub(crate) fn build_open_recent(app: &tauri::AppHandle) -> tauri::Result<Submenu<Wry>> {
+    let submenu = Submenu::with_id_and_items(app, OPEN_RECENT_ID, "Open Recent", true, &[])?;
+    for item in recent_items(app, &RecentLists::default(), false)? {
+        submenu.append(item.as_ref())?;
+    }
+    Ok(submenu)
+}
+
+/// Replace the items of the Open Recent submenu
+pub(crate) fn refresh_open_recent(app: &tauri::AppHandle, recent: &RecentLists, has_closed: bool) {
+    let Some(menu) = app.menu() else {
+        return;
+    };
+    let Some(file_menu) = menu.get(FILE_MENU_ID) else {
+        return;
+    };
+    let Some(open_recent) = file_menu.as_submenu().and_then(|file| file.get(OPEN_RECENT_ID)) else {
+        return;
+    };
+    let Some(submenu) = open_recent.as_submenu() else {
+        return;
+    };
+
+    let result = (|| -> tauri::Result<()> {
+        for _ in 0..submenu.items()?.len() {
+            submenu.remove_at(0)?;
+        }
+        for item in recent_items(app, recent, has_closed)? {
+            submenu.append(item.as_ref())?;
+        }
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        log::warn!("Failed to update the Open Recent menu: {}", e);
+    }
+}
+
+fn open_path(app: &tauri::AppHandle, path: String, kind: RecentKind) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.emit("open-path", OpenPath { path, kind });
+    }
+}
+
+/// Handle the Open Recent items. Returns false for other menu items, which
+/// are passed on to the editor.
+pub(crate) fn handle_menu_event(app: &tauri::AppHandle, id: &str) -> bool {
+    if let Some(path) = id.strip_prefix(RECENT_FILE_PREFIX) {
+        open_path(app, path.to_string(), RecentKind::File);
+        return true;
+    }
+    if let Some(path) = id.strip_prefix(RECENT_FOLDER_PREFIX) {
+        open_path(app, path.to_string(), RecentKind::Folder);
+        return true;
+    }
+
+    match id {
+        REOPEN_CLOSED_ID => {
+            if let Some(path) = take_closed_tab(app) {
+                open_path(app, path, RecentKind::File);
+            }
+            true
+        }
+        CLEAR_RECENT_ID => {
+            match clear_recent_lists(app) {
+                Ok(recent) => {
+                    if let Some(window) = app.get_webview_window("main") {
+                        let _ = window.emit("recent-changed", recent);
+                    }
+                }
+                Err(e) => log::warn!("Failed to clear recent items: {}", e),
+            }
+            true
+        }
+        _ => false,
+    }
+}
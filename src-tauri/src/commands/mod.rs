@@ -1,3 +1,28 @@
+pub mod assets;
+pub mod clipboard;
+pub mod export;
 pub mod file;
+pub mod finder;
+pub mod frontmatter;
+pub mod images;
+pub mod import;
+pub mod index;
+pub mod linkcheck;
+pub mod lint;
+pub mod links;
+pub mod localize;
+pub mod markdown;
+pub mod menu;
+pub mod optimize;
+pub mod outline;
+pub mod recovery;
+pub mod relink;
+pub mod replace;
+pub mod search;
+pub mod session;
 pub mod settings;
+pub mod stats;
+pub mod tags;
+pub mod thumbnail;
+pub mod versions;
 pub mod watcher;
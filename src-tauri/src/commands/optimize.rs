@@ -0,0 +1,369 @@
+use image::codecs::avif::AvifEncoder;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+
+/// Optimization settings looked up in the workspace `.ourea` folder
+pub(crate) const CONFIG_FILE: &str = "images.toml";
+
+/// AVIF encoder speed, from 1 (slowest, smallest) to 10
+const AVIF_SPEED: u8 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Same format as the input; BMP becomes PNG
+    Keep,
+    Png,
+    Jpeg,
+    Webp,
+    Avif,
+}
+
+/// How imported images are optimized. Keys are the same in `AppSettings`
+/// and in `.ourea/images.toml`, where any key given overrides the app setting.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ImageOptimization {
+    pub enabled: bool,
+    /// Wider images are scaled down to this width, 0 keeps the size
+    pub max_width: u32,
+    pub format: OutputFormat,
+    /// Quality for JPEG, WebP and AVIF, from 1 to 100
+    pub quality: u8,
+    /// Drop EXIF, GPS and other metadata
+    pub strip_metadata: bool,
+}
+
+impl Default for ImageOptimization {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_width: 1920,
+            format: OutputFormat::Keep,
+            quality: 85,
+            strip_metadata: true,
+        }
+    }
+}
+
+/// Remove APP1 (EXIF, XMP) and APP13 (IPTC) segments from a JPEG
+fn strip_jpeg_metadata(bytes: &[u8]) -> Option<Vec<u8>> {
+    if !bytes.starts_with(&[0xff, 0xd8]) {
+        return None;
+    }
+
+    let mut output = vec![0xff, 0xd8];
+    let mut pos = 2;
+
+    while pos + 4 <= bytes.len() {
+        if bytes[pos] != 0xff {
+            return None;
+        }
+        let marker = bytes[pos + 1];
+        // Start of scan: the rest is image data
+        if marker == 0xda {
+            output.extend_from_slice(&bytes[pos..]);
+            return Some(output);
+        }
+
+        let length = u16::from_be_bytes([bytes[pos + 2], bytes[pos + 3]]) as usize;
+        let end = pos + 2 + length;
+        if length < 2 || end > bytes.len() {
+            return None;
+        }
+        if marker != 0xe1 && marker != 0xed {
+            output.extend_from_slice(&bytes[pos..end]);
+        }
+        pos = end;
+    }
+
+    None
+}
+
+/// Remove EXIF and XMP chunks from a WebP
+fn strip_webp_metadata(bytes: &[u8]) -> Option<Vec<u8>> {
+    if bytes.len() < 12 || &bytes[..4] != b"RIFF" || &bytes[8..12] != b"WEBP" {
+        return None;
+    }
+
+    let mut output = b"RIFF\0\0\0\0WEBP".to_vec();
+    let mut pos = 12;
+
+    while pos + 8 <= bytes.len() {
+        let name = &bytes[pos..pos + 4];
+        let size = u32::from_le_bytes(bytes[pos + 4..pos + 8].try_into().ok()?) as usize;
+        // Chunks are padded to an even length
+        let end = (pos + 8 + size + (size & 1)).min(bytes.len());
+
+        if name == b"VP8X" && size >= 1 {
+            // A truncated chunk has no flags byte to clear
+            if end < pos + 9 {
+                return None;
+            }
+            let mut chunk = bytes[pos..end].to_vec();
+            // Clear the EXIF and XMP flags
+            chunk[8] &= !0x0c;
+            output.extend_from_slice(&chunk);
+        } else if name != b"EXIF" && name != b"XMP " {
+            output.extend_from_slice(&bytes[pos..end]);
+        }
+        pos = end;
+    }
+
+    let riff_size = (output.len() - 8) as u32;
+    output[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    Some(output)
+}
+
+pub(crate) fn decode(bytes: &[u8]) -> Result<(DynamicImage, Orientation), String> {
+    let mut decoder = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|e| e.to_string())?
+        .into_decoder()
+        .map_err(|e| e.to_string())?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let image = DynamicImage::from_decoder(decoder).map_err(|e| e.to_string())?;
+
+    Ok((image, orientation))
+}
+
+/// Lossless PNG recompression
+fn optimize_png(bytes: &[u8], strip_metadata: bool) -> Result<Vec<u8>, String> {
+    let mut options = oxipng::Options::from_preset(2);
+    options.strip = if strip_metadata {
+        oxipng::StripChunks::Safe
+    } else {
+        oxipng::StripChunks::None
+    };
+
+    oxipng::optimize_from_memory(bytes, &options).map_err(|e| e.to_string())
+}
+
+fn encode(image: &DynamicImage, format: OutputFormat, config: &ImageOptimization) -> Result<Vec<u8>, String> {
+    let quality = config.quality.clamp(1, 100);
+    let mut output = Vec::new();
+
+    match format {
+        OutputFormat::Jpeg => {
+            let encoder = JpegEncoder::new_with_quality(&mut output, quality);
+            // JPEG has no alpha channel
+            DynamicImage::ImageRgb8(image.to_rgb8())
+                .write_with_encoder(encoder)
+                .map_err(|e| e.to_string())?;
+        }
+        OutputFormat::Avif => {
+            let encoder = AvifEncoder::new_with_speed_quality(&mut output, AVIF_SPEED, quality);
+            image.write_with_encoder(encoder).map_err(|e| e.to_string())?;
+        }
+        OutputFormat::Webp => {
+            // The image crate only writes lossless WebP, so use libwebp
+            let image = DynamicImage::ImageRgba8(image.to_rgba8());
+            let encoder = webp::Encoder::from_image(&image)?;
+            output = encoder.encode(quality as f32).to_vec();
+        }
+        OutputFormat::Png | OutputFormat::Keep => {
+            image
+                .write_to(&mut Cursor::new(&mut output), ImageFormat::Png)
+                .map_err(|e| e.to_string())?;
+            output = optimize_png(&output, config.strip_metadata)?;
+        }
+    }
+
+    Ok(output)
+}
+
+/// Resize, recompress or convert an image as configured. Returns the new
+/// bytes and extension; SVG, GIF and other formats pass through unchanged.
+pub(crate) fn optimize_image(
+    bytes: &[u8],
+    extension: &str,
+    config: &ImageOptimization,
+) -> Result<(Vec<u8>, String), String> {
+    let source = match extension {
+        "png" | "bmp" => OutputFormat::Png,
+        "jpg" | "jpeg" => OutputFormat::Jpeg,
+        "webp" => OutputFormat::Webp,
+        _ => return Ok((bytes.to_vec(), extension.to_string())),
+    };
+    let target = match config.format {
+        OutputFormat::Keep => source,
+        format => format,
+    };
+    let target_extension = match target {
+        OutputFormat::Jpeg if extension == "jpeg" => "jpeg",
+        OutputFormat::Jpeg => "jpg",
+        OutputFormat::Webp => "webp",
+        OutputFormat::Avif => "avif",
+        OutputFormat::Png | OutputFormat::Keep => "png",
+    };
+
+    let (mut image, orientation) = decode(bytes)?;
+    let resize = config.max_width > 0 && image.width() > config.max_width;
+    // Stripping EXIF would lose the rotation, so it has to be applied to the pixels
+    let rotate = orientation != Orientation::NoTransforms && config.strip_metadata;
+    let same_format = target == source && extension != "bmp";
+
+    if same_format && !resize && !rotate {
+        let optimized = match target {
+            OutputFormat::Png => optimize_png(bytes, config.strip_metadata)?,
+            OutputFormat::Jpeg if config.strip_metadata => strip_jpeg_metadata(bytes).ok_or("Malformed JPEG")?,
+            OutputFormat::Webp if config.strip_metadata => strip_webp_metadata(bytes).ok_or("Malformed WebP")?,
+            _ => bytes.to_vec(),
+        };
+        return Ok((optimized, target_extension.to_string()));
+    }
+
+    image.apply_orientation(orientation);
+    if resize {
+        image = image.resize(config.max_width, u32::MAX, FilterType::Lanczos3);
+    }
+
+    Ok((encode(&image, target, config)?, target_extension.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::images::sniff_extension;
+    use image::{GenericImageView, RgbImage};
+
+    fn segment(marker: u8, data: &[u8]) -> Vec<u8> {
+        let mut segment = vec![0xff, marker];
+        segment.extend_from_slice(&(data.len() as u16 + 2).to_be_bytes());
+        segment.extend_from_slice(data);
+        segment
+    }
+
+    fn chunk(name: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut chunk = name.to_vec();
+        chunk.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        chunk.extend_from_slice(data);
+        if data.len() % 2 == 1 {
+            chunk.push(0);
+        }
+        chunk
+    }
+
+    fn riff(chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body: Vec<u8> = chunks.concat();
+        let mut bytes = b"RIFF".to_vec();
+        bytes.extend_from_slice(&(body.len() as u32 + 4).to_le_bytes());
+        bytes.extend_from_slice(b"WEBP");
+        bytes.extend_from_slice(&body);
+        bytes
+    }
+
+    /// A JPEG whose EXIF says to rotate it 90° clockwise for display
+    fn rotated_jpeg(width: u32, height: u32) -> Vec<u8> {
+        let mut plain = Vec::new();
+        let image = DynamicImage::ImageRgb8(RgbImage::from_pixel(width, height, image::Rgb([200, 40, 40])));
+        image.write_with_encoder(JpegEncoder::new_with_quality(&mut plain, 90)).unwrap();
+
+        let mut exif = b"Exif\0\0MM\0\x2a\0\0\0\x08\0\x01".to_vec();
+        exif.extend_from_slice(&[0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, 6, 0, 0, 0, 0, 0, 0]);
+
+        let mut bytes = plain[..2].to_vec();
+        bytes.extend_from_slice(&segment(0xe1, &exif));
+        bytes.extend_from_slice(&plain[2..]);
+        bytes
+    }
+
+    fn config() -> ImageOptimization {
+        ImageOptimization {
+            enabled: true,
+            ..ImageOptimization::default()
+        }
+    }
+
+    #[test]
+    fn strips_jpeg_metadata_segments() {
+        let app0 = segment(0xe0, b"JFIF\0\x01\x01");
+        let exif = segment(0xe1, b"Exif\0\0data");
+        let xmp = segment(0xe1, b"http://ns.adobe.com/xap/1.0/\0<x/>");
+        let iptc = segment(0xed, b"Photoshop 3.0\0");
+        let dqt = segment(0xdb, &[0; 5]);
+        let scan = [0xff, 0xda, 0, 2, 1, 2, 3, 0xff, 0xd9];
+
+        let bytes = [&[0xff, 0xd8][..], &app0, &exif, &xmp, &iptc, &dqt, &scan].concat();
+        let stripped = strip_jpeg_metadata(&bytes).unwrap();
+        assert_eq!(stripped, [&[0xff, 0xd8][..], &app0, &dqt, &scan].concat());
+
+        assert!(strip_jpeg_metadata(b"not a jpeg").is_none());
+        assert!(strip_jpeg_metadata(&[0xff, 0xd8, 0xff, 0xe1, 0x40, 0]).is_none());
+    }
+
+    #[test]
+    fn strips_webp_metadata_chunks() {
+        let mut vp8x = vec![0x0c | 0x10, 0, 0, 0];
+        vp8x.extend_from_slice(&[0; 6]);
+        let bytes = riff(&[
+            chunk(b"VP8X", &vp8x),
+            chunk(b"VP8L", &[1, 2, 3]),
+            chunk(b"EXIF", b"exif data"),
+            chunk(b"XMP ", b"<x/>"),
+        ]);
+
+        let stripped = strip_webp_metadata(&bytes).unwrap();
+        let mut cleared = vp8x.clone();
+        cleared[0] = 0x10;
+        assert_eq!(stripped, riff(&[chunk(b"VP8X", &cleared), chunk(b"VP8L", &[1, 2, 3])]));
+        assert_eq!(u32::from_le_bytes(stripped[4..8].try_into().unwrap()) as usize, stripped.len() - 8);
+    }
+
+    #[test]
+    fn rejects_truncated_webp_chunks() {
+        let mut bytes = riff(&[]);
+        bytes.extend_from_slice(b"VP8X\x0a\0\0\0");
+        assert!(strip_webp_metadata(&bytes).is_none());
+        assert!(strip_webp_metadata(b"RIFF\0\0\0\0WEBX").is_none());
+    }
+
+    #[test]
+    fn scales_wide_images_down() {
+        let mut png = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::new(400, 100))
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+
+        let config = ImageOptimization {
+            max_width: 200,
+            ..config()
+        };
+        let (optimized, extension) = optimize_image(&png, "png", &config).unwrap();
+        assert_eq!(extension, "png");
+        assert_eq!(decode(&optimized).unwrap().0.dimensions(), (200, 50));
+
+        let jpeg = ImageOptimization {
+            format: OutputFormat::Jpeg,
+            ..config
+        };
+        let (converted, extension) = optimize_image(&png, "png", &jpeg).unwrap();
+        assert_eq!((extension.as_str(), sniff_extension(&converted)), ("jpg", Some("jpg")));
+    }
+
+    #[test]
+    fn applies_orientation_only_when_stripping_metadata() {
+        let bytes = rotated_jpeg(40, 20);
+
+        let (stripped, _) = optimize_image(&bytes, "jpg", &config()).unwrap();
+        let (image, orientation) = decode(&stripped).unwrap();
+        assert_eq!((image.dimensions(), orientation), ((20, 40), Orientation::NoTransforms));
+
+        let keep = ImageOptimization {
+            strip_metadata: false,
+            ..config()
+        };
+        assert_eq!(optimize_image(&bytes, "jpg", &keep).unwrap().0, bytes);
+    }
+
+    #[test]
+    fn passes_other_formats_through_and_fails_on_bad_data() {
+        let svg = b"<svg xmlns='http://www.w3.org/2000/svg'/>";
+        assert_eq!(optimize_image(svg, "svg", &config()).unwrap(), (svg.to_vec(), "svg".to_string()));
+        assert!(optimize_image(b"\x89PNG\r\n\x1a\ngarbage", "png", &config()).is_err());
+    }
+}
@@ -0,0 +1,224 @@
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::markdown::{markdown_files, parser_options, LineIndex, Slugger};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutlineHeading {
+    pub level: u8,
+    pub text: String,
+    pub anchor: String,
+    /// Byte offset of the heading in the document
+    pub offset: usize,
+    /// 1-based line number
+    pub line: usize,
+    pub children: Vec<OutlineHeading>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileOutline {
+    pub path: String,
+    pub headings: Vec<OutlineHeading>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum OutlineError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("File not found: {0}")]
+    NotFound(String),
+    #[error("Either a path or content is required")]
+    MissingInput,
+    #[error("Outline task failed: {0}")]
+    Task(String),
+}
+
+impl Serialize for OutlineError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// Extract headings in document order, without nesting
+pub(crate) fn extract_headings(content: &str) -> Vec<OutlineHeading> {
+    let lines = LineIndex::new(content);
+    let mut slugger = Slugger::default();
+    let mut headings = Vec::new();
+    let mut current: Option<(OutlineHeading, Option<String>)> = None;
+
+    for (event, range) in Parser::new_ext(content, parser_options()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { level, id, .. }) => {
+                let heading = OutlineHeading {
+                    level: level as u8,
+                    text: String::new(),
+                    anchor: String::new(),
+                    offset: range.start,
+                    line: lines.line(range.start),
+                    children: Vec::new(),
+                };
+                current = Some((heading, id.map(|id| id.to_string())));
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((mut heading, id)) = current.take() {
+                    heading.text = heading.text.trim().to_string();
+                    // An explicit `{#id}` wins over the generated slug
+                    heading.anchor = id.unwrap_or_else(|| slugger.slug(&heading.text));
+                    headings.push(heading);
+                }
+            }
+            Event::Text(text) | Event::Code(text) | Event::InlineMath(text) => {
+                if let Some((heading, _)) = current.as_mut() {
+                    heading.text.push_str(&text);
+                }
+            }
+            Event::SoftBreak | Event::HardBreak => {
+                if let Some((heading, _)) = current.as_mut() {
+                    heading.text.push(' ');
+                }
+            }
+            _ => {}
+        }
+    }
+
+    headings
+}
+
+/// Nest a flat heading list so each heading owns the deeper ones that follow it
+fn build_tree(headings: Vec<OutlineHeading>) -> Vec<OutlineHeading> {
+    let mut roots: Vec<OutlineHeading> = Vec::new();
+    let mut stack: Vec<OutlineHeading> = Vec::new();
+
+    fn attach(node: OutlineHeading, stack: &mut [OutlineHeading], roots: &mut Vec<OutlineHeading>) {
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => roots.push(node),
+        }
+    }
+
+    for heading in headings {
+        while stack.last().is_some_and(|top| top.level >= heading.level) {
+            let node = stack.pop().unwrap();
+            attach(node, &mut stack, &mut roots);
+        }
+        stack.push(heading);
+    }
+
+    while let Some(node) = stack.pop() {
+        attach(node, &mut stack, &mut roots);
+    }
+
+    roots
+}
+
+/// Get the heading tree of a document, from its path or unsaved content
+#[tauri::command]
+pub fn get_outline(
+    path: Option<String>,
+    content: Option<String>,
+) -> Result<Vec<OutlineHeading>, OutlineError> {
+    let content = match (content, path) {
+        (Some(content), _) => content,
+        (None, Some(path)) => {
+            if !Path::new(&path).exists() {
+                return Err(OutlineError::NotFound(path));
+            }
+            fs::read_to_string(&path)?
+        }
+        (None, None) => return Err(OutlineError::MissingInput),
+    };
+
+    Ok(build_tree(extract_headings(&content)))
+}
+
+fn workspace_outline(root: &Path, max_level: u8) -> Vec<FileOutline> {
+    let mut outlines = Vec::new();
+
+    for file in markdown_files(root) {
+        // Unreadable files are left out of the table of contents
+        let Ok(content) = fs::read_to_string(&file) else {
+            continue;
+        };
+
+        let headings: Vec<OutlineHeading> = extract_headings(&content)
+            .into_iter()
+            .filter(|h| h.level <= max_level)
+            .collect();
+
+        outlines.push(FileOutline {
+            path: file.display().to_string(),
+            headings: build_tree(headings),
+        });
+    }
+
+    outlines
+}
+
+/// Get the outline of every Markdown file in a workspace, for a table of contents
+#[tauri::command]
+pub async fn get_workspace_outline(
+    root: String,
+    max_level: Option<u8>,
+) -> Result<Vec<FileOutline>, OutlineError> {
+    let root_path = PathBuf::from(&root);
+
+    if !root_path.exists() {
+        return Err(OutlineError::NotFound(root));
+    }
+
+    let max_level = max_level.unwrap_or(6);
+    tauri::async_runtime::spawn_blocking(move || workspace_outline(&root_path, max_level))
+        .await
+        .map_err(|e| OutlineError::Task(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Level and text of each heading, indented by depth
+    fn shape(headings: &[OutlineHeading], depth: usize, out: &mut Vec<String>) {
+        for heading in headings {
+            out.push(format!("{}{} {}", "  ".repeat(depth), heading.level, heading.text));
+            shape(&heading.children, depth + 1, out);
+        }
+    }
+
+    #[test]
+    fn nests_headings_across_skipped_levels() {
+        let content = "### Early\n# One\n### Deep\n## Two\n#### Deeper\n# Three\n## Four\n";
+        let mut lines = Vec::new();
+        shape(&build_tree(extract_headings(content)), 0, &mut lines);
+
+        assert_eq!(
+            lines,
+            ["3 Early", "1 One", "  3 Deep", "  2 Two", "    4 Deeper", "1 Three", "  2 Four"]
+        );
+    }
+
+    #[test]
+    fn slugs_duplicates_and_keeps_explicit_ids() {
+        let content = "# Intro\n\n## Intro\n\n## `code` *and* text\n\n## Named {#custom}\n\n# Intro\n";
+        let anchors: Vec<String> = extract_headings(content).into_iter().map(|h| h.anchor).collect();
+
+        assert_eq!(anchors, ["intro", "intro-1", "code-and-text", "custom", "intro-2"]);
+    }
+
+    #[test]
+    fn reports_lines_and_offsets_of_atx_and_setext_headings() {
+        let content = "Title\n=====\n\ntext\n\nSub\n---\n\n```\n# not a heading\n```\n\n## Last\n";
+        let headings = extract_headings(content);
+        let found: Vec<(u8, &str, usize, usize)> =
+            headings.iter().map(|h| (h.level, h.text.as_str(), h.line, h.offset)).collect();
+
+        assert_eq!(found, [(1, "Title", 1, 0), (2, "Sub", 6, 19), (2, "Last", 13, content.find("## Last").unwrap())]);
+        assert_eq!(&content[headings[1].offset..headings[1].offset + 3], "Sub");
+    }
+}
@@ -0,0 +1,503 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+use super::file::write_atomic;
+
+/// Folder under app data holding the journal
+const RECOVERY_DIR: &str = "recovery";
+const JOURNAL_FILE: &str = "journal.log";
+/// Journals larger than this are compacted on the next write
+const COMPACT_BYTES: u64 = 8 * 1024 * 1024;
+/// Sessions without writes for this long are no longer offered
+const MAX_SESSION_AGE_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// A tab as written by the editor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryTab {
+    pub id: String,
+    pub file_name: String,
+    pub file_path: Option<String>,
+    pub content: String,
+    pub is_dirty: bool,
+    pub file_type: Option<String>,
+    pub extension: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoverableTab {
+    pub id: String,
+    pub file_name: String,
+    pub file_path: Option<String>,
+    /// Content size in bytes
+    pub size: u64,
+}
+
+/// Summary of an earlier session with unsaved tabs
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoverableSession {
+    pub id: String,
+    /// Milliseconds since the Unix epoch of the first and last write
+    pub started: u64,
+    pub updated: u64,
+    pub tabs: Vec<RecoverableTab>,
+    pub active_tab_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveredSession {
+    pub id: String,
+    pub tabs: Vec<RecoveryTab>,
+    pub active_tab_id: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RecoveryError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Session not found: {0}")]
+    NotFound(String),
+    #[error("{0}")]
+    AppData(String),
+}
+
+impl Serialize for RecoveryError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum Entry {
+    /// Latest state of a tab; a clean tab needs no recovery
+    Tab(RecoveryTab),
+    Close(String),
+    Active(Option<String>),
+    /// The session was restored or declined
+    Discard,
+}
+
+/// One journal line, written as `<crc32 hex> <json>`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Record {
+    session: String,
+    time: u64,
+    entry: Entry,
+}
+
+#[derive(Debug, Default)]
+struct SessionJournal {
+    started: u64,
+    updated: u64,
+    /// Dirty tabs in the order they were first written
+    tabs: Vec<(u64, RecoveryTab)>,
+    active: Option<(u64, Option<String>)>,
+    discarded: bool,
+}
+
+/// The running session's id and its open journal
+pub struct RecoveryState {
+    session: String,
+    journal: Option<File>,
+    size: u64,
+    /// Older sessions are compacted away once per run, before they are listed
+    compacted: bool,
+}
+
+impl RecoveryState {
+    pub fn new() -> Self {
+        Self {
+            session: uuid::Uuid::new_v4().to_string(),
+            journal: None,
+            size: 0,
+            compacted: false,
+        }
+    }
+}
+
+impl Default for RecoveryState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn journal_path(app: &tauri::AppHandle) -> Result<PathBuf, RecoveryError> {
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| RecoveryError::AppData(e.to_string()))?
+        .join(RECOVERY_DIR)
+        .join(JOURNAL_FILE))
+}
+
+fn encode(record: &Record) -> String {
+    let json = serde_json::to_string(record).unwrap_or_default();
+    format!("{:08x} {}\n", crc32fast::hash(json.as_bytes()), json)
+}
+
+/// Records of a journal in order. Lines with a bad checksum, such as one
+/// cut short by a crash, are skipped.
+fn read_journal(path: &Path) -> Vec<Record> {
+    let Ok(content) = fs::read(path) else {
+        return Vec::new();
+    };
+
+    String::from_utf8_lossy(&content)
+        .lines()
+        .filter_map(|line| {
+            let (checksum, json) = line.split_once(' ')?;
+            let checksum = u32::from_str_radix(checksum, 16).ok()?;
+            if crc32fast::hash(json.as_bytes()) != checksum {
+                return None;
+            }
+            serde_json::from_str(json).ok()
+        })
+        .collect()
+}
+
+fn replay(records: Vec<Record>) -> HashMap<String, SessionJournal> {
+    let mut sessions: HashMap<String, SessionJournal> = HashMap::new();
+
+    for record in records {
+        let session = sessions.entry(record.session).or_default();
+        // Compaction doesn't keep records in time order
+        if session.started == 0 || record.time < session.started {
+            session.started = record.time;
+        }
+        session.updated = session.updated.max(record.time);
+
+        match record.entry {
+            Entry::Tab(tab) => match session.tabs.iter().position(|(_, t)| t.id == tab.id) {
+                Some(i) if tab.is_dirty => session.tabs[i] = (record.time, tab),
+                Some(i) => {
+                    session.tabs.remove(i);
+                }
+                None if tab.is_dirty => session.tabs.push((record.time, tab)),
+                None => {}
+            },
+            Entry::Close(id) => session.tabs.retain(|(_, t)| t.id != id),
+            Entry::Active(id) => session.active = Some((record.time, id)),
+            Entry::Discard => session.discarded = true,
+        }
+    }
+
+    sessions
+}
+
+/// Rewrite the journal with only the latest record of each tab in
+/// sessions worth keeping
+fn compact(state: &mut RecoveryState, path: &Path) -> Result<(), RecoveryError> {
+    let now = now_ms();
+    let sessions = replay(read_journal(path));
+    let mut content = String::new();
+
+    for (id, session) in sessions {
+        let current = id == state.session;
+        let expired = now.saturating_sub(session.updated) > MAX_SESSION_AGE_MS;
+        if !current && (session.discarded || session.tabs.is_empty() || expired) {
+            continue;
+        }
+
+        if let Some((time, active)) = session.active {
+            let entry = Entry::Active(active);
+            content.push_str(&encode(&Record { session: id.clone(), time, entry }));
+        }
+        for (time, tab) in session.tabs {
+            content.push_str(&encode(&Record { session: id.clone(), time, entry: Entry::Tab(tab) }));
+        }
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_atomic(path, content.as_bytes())?;
+
+    // The open handle still points at the replaced file
+    state.journal = None;
+    state.size = content.len() as u64;
+    Ok(())
+}
+
+fn append(state: &mut RecoveryState, path: &Path, entry: Entry) -> Result<(), RecoveryError> {
+    let session = state.session.clone();
+    append_to(state, path, session, entry)
+}
+
+fn append_to(state: &mut RecoveryState, path: &Path, session: String, entry: Entry) -> Result<(), RecoveryError> {
+    if state.journal.is_none() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new().create(true).read(true).append(true).open(path)?;
+        state.size = file.metadata()?.len();
+
+        // A crash mid-write leaves a partial line, which would swallow the next record
+        if state.size > 0 {
+            let mut last = [0u8; 1];
+            file.seek(SeekFrom::End(-1))?;
+            file.read_exact(&mut last)?;
+            if last[0] != b'\n' {
+                file.write_all(b"\n")?;
+                state.size += 1;
+            }
+        }
+        state.journal = Some(file);
+    }
+
+    let line = encode(&Record { session, time: now_ms(), entry });
+    if let Some(journal) = state.journal.as_mut() {
+        journal.write_all(line.as_bytes())?;
+        journal.sync_data()?;
+    }
+    state.size += line.len() as u64;
+
+    if state.size > COMPACT_BYTES {
+        compact(state, path)?;
+    }
+
+    Ok(())
+}
+
+/// Record the current content of a tab. Clean tabs are dropped from the
+/// journal, since the file on disk has their content.
+#[tauri::command]
+pub fn journal_tab(
+    app: tauri::AppHandle,
+    tab: RecoveryTab,
+    state: tauri::State<'_, Arc<Mutex<RecoveryState>>>,
+) -> Result<(), RecoveryError> {
+    let path = journal_path(&app)?;
+    append(&mut state.lock().unwrap(), &path, Entry::Tab(tab))
+}
+
+/// Record that a tab was closed
+#[tauri::command]
+pub fn journal_close_tab(
+    app: tauri::AppHandle,
+    tab_id: String,
+    state: tauri::State<'_, Arc<Mutex<RecoveryState>>>,
+) -> Result<(), RecoveryError> {
+    let path = journal_path(&app)?;
+    append(&mut state.lock().unwrap(), &path, Entry::Close(tab_id))
+}
+
+/// Record which tab is active
+#[tauri::command]
+pub fn journal_active_tab(
+    app: tauri::AppHandle,
+    tab_id: Option<String>,
+    state: tauri::State<'_, Arc<Mutex<RecoveryState>>>,
+) -> Result<(), RecoveryError> {
+    let path = journal_path(&app)?;
+    append(&mut state.lock().unwrap(), &path, Entry::Active(tab_id))
+}
+
+/// List earlier sessions that ended with unsaved tabs, newest first
+#[tauri::command]
+pub fn list_recoverable_sessions(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<Mutex<RecoveryState>>>,
+) -> Result<Vec<RecoverableSession>, RecoveryError> {
+    let path = journal_path(&app)?;
+    let mut state = state.lock().unwrap();
+
+    if !state.compacted && path.exists() {
+        compact(&mut state, &path)?;
+        state.compacted = true;
+    }
+
+    let mut sessions: Vec<RecoverableSession> = replay(read_journal(&path))
+        .into_iter()
+        .filter(|(id, session)| *id != state.session && !session.discarded && !session.tabs.is_empty())
+        .map(|(id, session)| RecoverableSession {
+            id,
+            started: session.started,
+            updated: session.updated,
+            tabs: session
+                .tabs
+                .iter()
+                .map(|(_, tab)| RecoverableTab {
+                    id: tab.id.clone(),
+                    file_name: tab.file_name.clone(),
+                    file_path: tab.file_path.clone(),
+                    size: tab.content.len() as u64,
+                })
+                .collect(),
+            active_tab_id: session.active.and_then(|(_, id)| id),
+        })
+        .collect();
+    sessions.sort_by_key(|session| std::cmp::Reverse(session.updated));
+
+    Ok(sessions)
+}
+
+/// Get the unsaved tabs of an earlier session. The session is then
+/// discarded; the editor journals the restored tabs as part of this one.
+#[tauri::command]
+pub fn restore_session(
+    app: tauri::AppHandle,
+    id: String,
+    state: tauri::State<'_, Arc<Mutex<RecoveryState>>>,
+) -> Result<RecoveredSession, RecoveryError> {
+    let path = journal_path(&app)?;
+    let mut state = state.lock().unwrap();
+
+    let session = replay(read_journal(&path))
+        .remove(&id)
+        .filter(|session| id != state.session && !session.discarded)
+        .ok_or_else(|| RecoveryError::NotFound(id.clone()))?;
+
+    append_to(&mut state, &path, id.clone(), Entry::Discard)?;
+
+    Ok(RecoveredSession {
+        id,
+        tabs: session.tabs.into_iter().map(|(_, tab)| tab).collect(),
+        active_tab_id: session.active.and_then(|(_, id)| id),
+    })
+}
+
+/// Decline recovery of an earlier session, or of all of them without `id`
+#[tauri::command]
+pub fn discard_session(
+    app: tauri::AppHandle,
+    id: Option<String>,
+    state: tauri::State<'_, Arc<Mutex<RecoveryState>>>,
+) -> Result<(), RecoveryError> {
+    let path = journal_path(&app)?;
+    let mut state = state.lock().unwrap();
+
+    let ids: Vec<String> = match id {
+        Some(id) => vec![id],
+        None => replay(read_journal(&path)).into_keys().collect(),
+    };
+
+    for id in ids {
+        if id != state.session {
+            append_to(&mut state, &path, id, Entry::Discard)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tab(id: &str, content: &str, is_dirty: bool) -> RecoveryTab {
+        RecoveryTab {
+            id: id.to_string(),
+            file_name: format!("{}.md", id),
+            file_path: None,
+            content: content.to_string(),
+            is_dirty,
+            file_type: None,
+            extension: None,
+        }
+    }
+
+    fn record(session: &str, time: u64, entry: Entry) -> Record {
+        Record {
+            session: session.to_string(),
+            time,
+            entry,
+        }
+    }
+
+    #[test]
+    fn replays_the_latest_state_of_each_tab() {
+        let sessions = replay(vec![
+            record("a", 20, Entry::Tab(tab("one", "first", true))),
+            record("a", 10, Entry::Tab(tab("two", "draft", true))),
+            record("a", 30, Entry::Tab(tab("one", "second", true))),
+            record("a", 40, Entry::Tab(tab("three", "x", true))),
+            record("a", 50, Entry::Tab(tab("two", "saved", false))),
+            record("a", 60, Entry::Active(Some("three".to_string()))),
+            record("a", 70, Entry::Close("three".to_string())),
+            record("b", 5, Entry::Tab(tab("four", "y", true))),
+            record("b", 6, Entry::Discard),
+        ]);
+
+        let a = &sessions["a"];
+        assert_eq!((a.started, a.updated, a.discarded), (10, 70, false));
+        let tabs: Vec<(u64, &str)> = a.tabs.iter().map(|(time, t)| (*time, t.content.as_str())).collect();
+        assert_eq!(tabs, [(30, "second")]);
+        assert_eq!(a.active, Some((60, Some("three".to_string()))));
+
+        assert!(sessions["b"].discarded);
+        assert_eq!(sessions["b"].tabs.len(), 1);
+    }
+
+    #[test]
+    fn skips_damaged_journal_lines() {
+        let dir = std::env::temp_dir().join(format!("ourea-recovery-journal-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join(JOURNAL_FILE);
+
+        let mut state = RecoveryState::new();
+        append(&mut state, &path, Entry::Tab(tab("one", "hello", true))).unwrap();
+        append(&mut state, &path, Entry::Active(Some("one".to_string()))).unwrap();
+
+        // A line with a wrong checksum, and one cut short by a crash
+        let tampered = encode(&record("x", 1, Entry::Discard)).replacen(|c: char| c.is_ascii_hexdigit(), "g", 1);
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(tampered.as_bytes()).unwrap();
+        let cut = encode(&record("x", 2, Entry::Discard));
+        file.write_all(&cut.as_bytes()[..cut.len() / 2]).unwrap();
+        drop(file);
+
+        // A new run starts its writes on a fresh line
+        let mut next = RecoveryState::new();
+        append(&mut next, &path, Entry::Close("two".to_string())).unwrap();
+
+        let records = read_journal(&path);
+        let sessions: Vec<&str> = records.iter().map(|r| r.session.as_str()).collect();
+        assert_eq!(sessions, [state.session.as_str(), state.session.as_str(), next.session.as_str()]);
+        assert!(matches!(&records[0].entry, Entry::Tab(t) if t.content == "hello"));
+        assert!(read_journal(&dir.join("missing.log")).is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn compaction_keeps_only_recoverable_sessions() {
+        let dir = std::env::temp_dir().join(format!("ourea-recovery-compact-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join(JOURNAL_FILE);
+
+        let mut state = RecoveryState::new();
+        append(&mut state, &path, Entry::Tab(tab("one", "old", true))).unwrap();
+        append(&mut state, &path, Entry::Tab(tab("one", "new", true))).unwrap();
+        append_to(&mut state, &path, "declined".to_string(), Entry::Tab(tab("two", "y", true))).unwrap();
+        append_to(&mut state, &path, "declined".to_string(), Entry::Discard).unwrap();
+
+        compact(&mut state, &path).unwrap();
+        let records = read_journal(&path);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].session, state.session);
+        assert!(matches!(&records[0].entry, Entry::Tab(t) if t.content == "new"));
+        assert_eq!(state.size, fs::metadata(&path).unwrap().len());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,343 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+
+use super::file::write_atomic;
+use super::links::{normalize_path, parse_links, LinkIndex, LinkKind, ParsedLink};
+use super::markdown::{is_markdown_file, markdown_files};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditedFile {
+    /// Path of the file after the move
+    pub path: String,
+    pub links_updated: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveResult {
+    pub new_path: String,
+    pub edited: Vec<EditedFile>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MoveError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("File not found: {0}")]
+    NotFound(String),
+    #[error("Target path already exists: {0}")]
+    AlreadyExists(String),
+}
+
+impl Serialize for MoveError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// Relative path from a folder to a file, with `/` separators
+pub(crate) fn relative_path(from_dir: &Path, to: &Path) -> String {
+    let from: Vec<Component> = from_dir.components().collect();
+    let to: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let mut parts: Vec<String> = vec!["..".to_string(); from.len() - common];
+    parts.extend(to[common..].iter().map(|c| c.as_os_str().to_string_lossy().to_string()));
+    parts.join("/")
+}
+
+/// Escape what would end a bare Markdown link destination
+pub(crate) fn encode_destination(path: &str) -> String {
+    path.replace('%', "%25")
+        .replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+}
+
+fn strip_markdown_extension(path: &str) -> &str {
+    path.strip_suffix(".md")
+        .or_else(|| path.strip_suffix(".markdown"))
+        .unwrap_or(path)
+}
+
+/// Where every file affected by a move ends up
+fn moved_files(old: &Path, new: &Path) -> HashMap<PathBuf, PathBuf> {
+    if old.is_file() {
+        return HashMap::from([(old.to_path_buf(), new.to_path_buf())]);
+    }
+
+    // Hidden and ignored files move too, so nothing is filtered here
+    ignore::WalkBuilder::new(old)
+        .standard_filters(false)
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(old).ok()?.to_path_buf();
+            Some((entry.into_path(), new.join(relative)))
+        })
+        .collect()
+}
+
+/// New text for a link's target after a move, if it changes
+fn rewrite_target(
+    link: &ParsedLink,
+    raw: &str,
+    angled: bool,
+    root: &Path,
+    source: (&Path, &Path),
+    target: (&Path, &Path),
+) -> Option<String> {
+    let (old_source, new_source) = source;
+    let (old_target, new_target) = target;
+
+    match link.kind {
+        LinkKind::Markdown => {
+            let root_relative = raw.starts_with('/');
+            let (old_path, new_path) = if root_relative {
+                (relative_path(root, old_target), relative_path(root, new_target))
+            } else {
+                let old_dir = old_source.parent().unwrap_or(root);
+                let new_dir = new_source.parent().unwrap_or(root);
+                (relative_path(old_dir, old_target), relative_path(new_dir, new_target))
+            };
+            if old_path == new_path {
+                return None;
+            }
+
+            // Keep the author's style: extensionless links stay that way
+            let keeps_extension = Path::new(&link.target).extension().is_some();
+            let mut path = if keeps_extension {
+                new_path
+            } else {
+                strip_markdown_extension(&new_path).to_string()
+            };
+            if root_relative {
+                path.insert(0, '/');
+            }
+
+            // Angle-bracketed destinations may hold spaces as they are
+            Some(if angled { path } else { encode_destination(&path) })
+        }
+        LinkKind::Wiki => {
+            if old_target == new_target {
+                return None;
+            }
+
+            let is_note = is_markdown_file(new_target);
+            let by_path = link.target.contains('/') || !is_note;
+            let text = if by_path {
+                let path = relative_path(root, new_target);
+                let keeps_extension = !is_note || Path::new(&link.target).extension().is_some();
+                if keeps_extension {
+                    path
+                } else {
+                    strip_markdown_extension(&path).to_string()
+                }
+            } else {
+                new_target.file_stem()?.to_string_lossy().to_string()
+            };
+
+            (text.to_lowercase() != link.target.to_lowercase()).then_some(text)
+        }
+    }
+}
+
+/// Replace byte ranges of a document, given in any order
+fn apply_edits(content: &str, mut edits: Vec<(Range<usize>, String)>) -> String {
+    edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+
+    let mut output = content.to_string();
+    for (range, text) in edits {
+        output.replace_range(range, &text);
+    }
+    output
+}
+
+/// Rename or move a file or folder, optionally updating every Markdown
+/// link, wiki link and image reference in the workspace that points into
+/// it or out of it. Returns the edited files for review. If a link can't
+/// be updated, the move and the edits made so far are undone.
+#[tauri::command]
+pub fn move_path(root: String, old_path: String, new_path: String, update_links: bool) -> Result<MoveResult, MoveError> {
+    let root = normalize_path(Path::new(&root));
+    let old = normalize_path(Path::new(&old_path));
+    let new = normalize_path(Path::new(&new_path));
+
+    if !old.exists() {
+        return Err(MoveError::NotFound(old_path));
+    }
+    if new.exists() {
+        return Err(MoveError::AlreadyExists(new_path));
+    }
+
+    // New path, original and edited content, and number of links changed
+    let mut rewritten: Vec<(PathBuf, String, String, usize)> = Vec::new();
+
+    if update_links {
+        let moved = moved_files(&old, &new);
+        // Links are resolved against the layout before the move
+        let index = LinkIndex::file_names(&root);
+
+        for source in markdown_files(&root) {
+            let Ok(content) = fs::read_to_string(&source) else {
+                continue;
+            };
+            let new_source = moved.get(&source).cloned().unwrap_or_else(|| source.clone());
+
+            let mut edits = Vec::new();
+            for link in parse_links(&content) {
+                let Some(range) = link.target_range.clone() else {
+                    continue;
+                };
+                let Some(old_target) = index.resolve(&link, Some(&source)) else {
+                    continue;
+                };
+                // Links to a heading of the same document need no change
+                if link.target.is_empty() {
+                    continue;
+                }
+
+                let new_target = moved.get(&old_target).cloned().unwrap_or_else(|| old_target.clone());
+                if new_source == source && new_target == old_target {
+                    continue;
+                }
+
+                let raw = &content[range.clone()];
+                let angled = content[..range.start].ends_with('<');
+                let source_move = (source.as_path(), new_source.as_path());
+                let target_move = (old_target.as_path(), new_target.as_path());
+                if let Some(text) = rewrite_target(&link, raw, angled, &root, source_move, target_move) {
+                    edits.push((range, text));
+                }
+            }
+
+            if !edits.is_empty() {
+                let count = edits.len();
+                let edited = apply_edits(&content, edits);
+                rewritten.push((new_source, content, edited, count));
+            }
+        }
+    }
+
+    if let Some(parent) = new.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(&old, &new)?;
+
+    for (written, (path, _, content, _)) in rewritten.iter().enumerate() {
+        if let Err(e) = write_atomic(path, content.as_bytes()) {
+            // Put everything back, so links never point at the wrong place
+            for (path, original, _, _) in &rewritten[..written] {
+                if let Err(e) = write_atomic(path, original.as_bytes()) {
+                    log::error!("Failed to restore {}: {}", path.display(), e);
+                }
+            }
+            if let Err(e) = fs::rename(&new, &old) {
+                log::error!("Failed to move {} back: {}", new.display(), e);
+            }
+            return Err(e.into());
+        }
+    }
+
+    let edited = rewritten
+        .into_iter()
+        .map(|(path, _, _, links_updated)| EditedFile {
+            path: path.display().to_string(),
+            links_updated,
+        })
+        .collect();
+
+    Ok(MoveResult {
+        new_path: new.display().to_string(),
+        edited,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(content: &str) -> ParsedLink {
+        parse_links(content).remove(0)
+    }
+
+    fn rewrite(content: &str, source: (&str, &str), target: (&str, &str)) -> Option<String> {
+        let link = link(content);
+        let range = link.target_range.clone().unwrap();
+        let raw = &content[range.clone()];
+        let angled = content[..range.start].ends_with('<');
+        let (old_source, new_source) = (Path::new(source.0), Path::new(source.1));
+        let (old_target, new_target) = (Path::new(target.0), Path::new(target.1));
+        rewrite_target(&link, raw, angled, Path::new("/w"), (old_source, new_source), (old_target, new_target))
+    }
+
+    #[test]
+    fn rewrites_relative_markdown_links() {
+        let moved_target = ("/w/b.md", "/w/sub/my notes.md");
+        let unmoved = ("/w/a.md", "/w/a.md");
+        assert_eq!(rewrite("[b](b.md)", unmoved, moved_target).as_deref(), Some("sub/my%20notes.md"));
+        assert_eq!(rewrite("[b](<b.md>)", unmoved, moved_target).as_deref(), Some("sub/my notes.md"));
+        assert_eq!(rewrite("[b](b#top)", unmoved, moved_target).as_deref(), Some("sub/my%20notes"));
+        assert_eq!(rewrite("[b](/b.md)", unmoved, moved_target).as_deref(), Some("/sub/my%20notes.md"));
+
+        // Moving the source changes its relative links out
+        let moved_source = ("/w/a.md", "/w/deep/er/a.md");
+        let unmoved_target = ("/w/img/x.png", "/w/img/x.png");
+        assert_eq!(rewrite("![x](img/x.png)", moved_source, unmoved_target).as_deref(), Some("../../img/x.png"));
+        assert_eq!(rewrite("![x](/img/x.png)", moved_source, unmoved_target), None);
+        // Moving both together keeps the link as it is
+        assert_eq!(rewrite("[b](b.md)", ("/w/a.md", "/w/n/a.md"), ("/w/b.md", "/w/n/b.md")), None);
+    }
+
+    #[test]
+    fn rewrites_wiki_links_by_name_or_path() {
+        let unmoved = ("/w/a.md", "/w/a.md");
+        assert_eq!(rewrite("[[Old]]", unmoved, ("/w/Old.md", "/w/New.md")).as_deref(), Some("New"));
+        // A move between folders keeps a name link as it is
+        assert_eq!(rewrite("[[Old]]", unmoved, ("/w/Old.md", "/w/x/Old.md")), None);
+        assert_eq!(rewrite("[[old|alias]]", unmoved, ("/w/Old.md", "/w/Old.md")), None);
+        assert_eq!(rewrite("[[f/Old]]", unmoved, ("/w/f/Old.md", "/w/g/Old.md")).as_deref(), Some("g/Old"));
+        assert_eq!(rewrite("[[f/Old.md]]", unmoved, ("/w/f/Old.md", "/w/g/Old.md")).as_deref(), Some("g/Old.md"));
+        assert_eq!(
+            rewrite("![[pic.png]]", unmoved, ("/w/pic.png", "/w/assets/pic.png")).as_deref(),
+            Some("assets/pic.png")
+        );
+    }
+
+    #[test]
+    fn moves_a_folder_and_updates_links_both_ways() {
+        let root = std::env::temp_dir().join(format!("ourea-relink-folder-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("old")).unwrap();
+        fs::write(root.join("index.md"), "[n](old/note.md) [[note]] `[x](old/note.md)`").unwrap();
+        fs::write(root.join("old/note.md"), "[home](../index.md#top) [self](#top)").unwrap();
+
+        let path = |p: &str| root.join(p).display().to_string();
+        let result = move_path(path(""), path("old"), path("new/place"), true).unwrap();
+
+        assert_eq!(result.edited.len(), 2);
+        assert_eq!(
+            fs::read_to_string(root.join("index.md")).unwrap(),
+            "[n](new/place/note.md) [[note]] `[x](old/note.md)`"
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("new/place/note.md")).unwrap(),
+            "[home](../../index.md#top) [self](#top)"
+        );
+        assert!(!root.join("old").exists());
+        assert!(matches!(
+            move_path(path(""), path("index.md"), path("new/place/note.md"), true),
+            Err(MoveError::AlreadyExists(_))
+        ));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,459 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+use super::file::write_atomic;
+use super::search::{build_regex, find_matches, searchable_files, SearchError, SearchMatch, SearchQuery};
+
+/// Folder under the app data directory holding undo journals
+const JOURNAL_DIR: &str = "replace-journal";
+
+/// Matches the user kept from a preview, by byte offset
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceSelection {
+    pub path: String,
+    pub starts: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceChange {
+    #[serde(flatten)]
+    pub location: SearchMatch,
+    pub original: String,
+    pub replacement: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileReplacement {
+    pub path: String,
+    pub changes: Vec<ReplaceChange>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceResult {
+    /// Journal id to pass to `undo_replace`, absent for dry runs
+    pub batch_id: Option<String>,
+    pub dry_run: bool,
+    pub files: Vec<FileReplacement>,
+    pub total_replacements: usize,
+}
+
+/// One file of an applied batch, with both versions of its content
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JournalEntry {
+    path: String,
+    original: String,
+    replaced: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReplaceJournal {
+    batch_id: String,
+    root: String,
+    files: Vec<JournalEntry>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReplaceError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("File not found: {0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Search(#[from] SearchError),
+    #[error("File changed since the preview: {0}")]
+    Stale(String),
+    #[error("Files changed since the replacement, undo aborted: {0}")]
+    Conflict(String),
+    #[error("Journal error: {0}")]
+    Journal(String),
+    #[error("Path is outside the workspace: {0}")]
+    OutsideRoot(String),
+    #[error("Replace task failed: {0}")]
+    Task(String),
+}
+
+impl Serialize for ReplaceError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+fn journal_dir(app: &tauri::AppHandle) -> Result<PathBuf, ReplaceError> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| ReplaceError::Journal(e.to_string()))?
+        .join(JOURNAL_DIR);
+
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+fn journal_path(app: &tauri::AppHandle, batch_id: &str) -> Result<PathBuf, ReplaceError> {
+    // Batch ids are generated here, so anything else is a bad request
+    if batch_id.is_empty() || !batch_id.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+        return Err(ReplaceError::Journal(format!("Invalid batch id: {}", batch_id)));
+    }
+
+    Ok(journal_dir(app)?.join(format!("{}.json", batch_id)))
+}
+
+/// Replace the matches of a regex in some text. Only matches starting at a
+/// `selected` offset are replaced when a selection is given. Capture groups
+/// (`$1`, `${name}`) are expanded for regex queries.
+fn plan_replacements(
+    content: &str,
+    regex: &Regex,
+    replacement: &str,
+    expand: bool,
+    selected: Option<&HashSet<usize>>,
+) -> (String, Vec<ReplaceChange>) {
+    let locations = find_matches(content, regex);
+    let captures = regex.captures_iter(content).filter(|c| !c[0].is_empty());
+
+    let mut output = String::with_capacity(content.len());
+    let mut changes = Vec::new();
+    let mut last = 0;
+
+    for (location, caps) in locations.into_iter().zip(captures) {
+        if selected.is_some_and(|starts| !starts.contains(&location.start)) {
+            continue;
+        }
+
+        let found = caps.get(0).unwrap();
+        let mut replaced = String::new();
+        if expand {
+            caps.expand(replacement, &mut replaced);
+        } else {
+            replaced.push_str(replacement);
+        }
+
+        output.push_str(&content[last..found.start()]);
+        output.push_str(&replaced);
+        last = found.end();
+
+        changes.push(ReplaceChange {
+            location,
+            original: found.as_str().to_string(),
+            replacement: replaced,
+        });
+    }
+
+    output.push_str(&content[last..]);
+    (output, changes)
+}
+
+/// Fail unless `path` resolves to a file inside the workspace root, so a
+/// selection can't name files elsewhere (`../`, symlinks)
+fn check_in_root(root: &Path, path: &Path) -> Result<(), ReplaceError> {
+    if !path.exists() {
+        return Err(ReplaceError::NotFound(path.display().to_string()));
+    }
+
+    if fs::canonicalize(path)?.starts_with(fs::canonicalize(root)?) {
+        Ok(())
+    } else {
+        Err(ReplaceError::OutsideRoot(path.display().to_string()))
+    }
+}
+
+/// Put every file back to its given content, as far as possible
+fn restore(files: &[(PathBuf, &str)]) {
+    for (path, content) in files {
+        if let Err(e) = write_atomic(path, content.as_bytes()) {
+            log::error!("Failed to restore {}: {}", path.display(), e);
+        }
+    }
+}
+
+fn replace_matches(
+    app: &tauri::AppHandle,
+    root: String,
+    query: SearchQuery,
+    replacement: String,
+    dry_run: bool,
+    selection: Option<Vec<ReplaceSelection>>,
+) -> Result<ReplaceResult, ReplaceError> {
+    let root_path = Path::new(&root);
+    let regex = build_regex(&query)?;
+    let selected: Option<HashMap<PathBuf, HashSet<usize>>> = selection.map(|items| {
+        items
+            .into_iter()
+            .map(|item| (PathBuf::from(item.path), item.starts.into_iter().collect()))
+            .collect()
+    });
+
+    let files = match &selected {
+        Some(selected) => {
+            let mut files: Vec<PathBuf> = selected.keys().cloned().collect();
+            files.sort();
+            files
+        }
+        None => searchable_files(root_path),
+    };
+
+    let mut planned = Vec::new();
+    for path in files {
+        let starts = selected.as_ref().and_then(|s| s.get(&path));
+        if starts.is_some() {
+            check_in_root(root_path, &path)?;
+        }
+
+        // Unreadable or non-UTF-8 files can't be matched, so they're skipped
+        let Ok(original) = fs::read_to_string(&path) else {
+            continue;
+        };
+
+        let (replaced, changes) = plan_replacements(&original, &regex, &replacement, query.regex, starts);
+        if starts.is_some_and(|starts| changes.len() != starts.len()) {
+            return Err(ReplaceError::Stale(path.display().to_string()));
+        }
+        if !changes.is_empty() {
+            planned.push((path, original, replaced, changes));
+        }
+    }
+
+    let total_replacements = planned.iter().map(|(_, _, _, changes)| changes.len()).sum();
+    let mut batch_id = None;
+
+    if !dry_run && !planned.is_empty() {
+        let files = planned
+            .iter()
+            .map(|(path, original, replaced, _)| JournalEntry {
+                path: path.display().to_string(),
+                original: original.clone(),
+                replaced: replaced.clone(),
+            })
+            .collect();
+        batch_id = Some(apply_batch(&journal_dir(app)?, &root, files)?);
+    }
+
+    Ok(ReplaceResult {
+        batch_id,
+        dry_run,
+        files: planned
+            .into_iter()
+            .map(|(path, _, _, changes)| FileReplacement {
+                path: path.display().to_string(),
+                changes,
+            })
+            .collect(),
+        total_replacements,
+    })
+}
+
+/// Replace matches of a search query across a workspace. A dry run returns
+/// every change without touching the disk; otherwise the selected changes
+/// (all of them when no selection is given) are written and journaled so
+/// the batch can be undone.
+#[tauri::command]
+pub async fn replace_in_workspace(
+    app: tauri::AppHandle,
+    root: String,
+    query: SearchQuery,
+    replacement: String,
+    dry_run: bool,
+    selection: Option<Vec<ReplaceSelection>>,
+) -> Result<ReplaceResult, ReplaceError> {
+    if !Path::new(&root).exists() {
+        return Err(ReplaceError::NotFound(root));
+    }
+
+    // Scanning and rewriting a whole workspace would block the window
+    tauri::async_runtime::spawn_blocking(move || {
+        replace_matches(&app, root, query, replacement, dry_run, selection)
+    })
+    .await
+    .map_err(|e| ReplaceError::Task(e.to_string()))?
+}
+
+/// Journal a batch, then write its files. Returns the batch id.
+fn apply_batch(dir: &Path, root: &str, files: Vec<JournalEntry>) -> Result<String, ReplaceError> {
+    let id = uuid::Uuid::new_v4().to_string();
+
+    // Journal first, so a batch interrupted halfway can still be undone
+    let journal = ReplaceJournal {
+        batch_id: id.clone(),
+        root: root.to_string(),
+        files,
+    };
+    let journal_file = dir.join(format!("{}.json", id));
+    let json = serde_json::to_string(&journal).map_err(|e| ReplaceError::Journal(e.to_string()))?;
+    fs::write(&journal_file, json)?;
+
+    let mut written: Vec<(PathBuf, &str)> = Vec::new();
+    for entry in &journal.files {
+        let path = PathBuf::from(&entry.path);
+        if let Err(e) = write_atomic(&path, entry.replaced.as_bytes()) {
+            // All or nothing: put back what was already written
+            restore(&written);
+            let _ = fs::remove_file(&journal_file);
+            return Err(e.into());
+        }
+        written.push((path, &entry.original));
+    }
+
+    Ok(id)
+}
+
+/// Restore the files of a journaled batch and delete the journal
+fn undo_batch(journal_file: &Path) -> Result<Vec<String>, ReplaceError> {
+    let content = fs::read_to_string(journal_file)?;
+    let journal: ReplaceJournal =
+        serde_json::from_str(&content).map_err(|e| ReplaceError::Journal(e.to_string()))?;
+
+    let conflicts: Vec<&str> = journal
+        .files
+        .iter()
+        .filter(|entry| fs::read_to_string(&entry.path).ok().as_deref() != Some(entry.replaced.as_str()))
+        .map(|entry| entry.path.as_str())
+        .collect();
+    if !conflicts.is_empty() {
+        return Err(ReplaceError::Conflict(conflicts.join(", ")));
+    }
+
+    let mut restored: Vec<(PathBuf, &str)> = Vec::new();
+    for entry in &journal.files {
+        let path = PathBuf::from(&entry.path);
+        if let Err(e) = write_atomic(&path, entry.original.as_bytes()) {
+            // Re-apply the batch on the files already restored
+            restore(&restored);
+            return Err(e.into());
+        }
+        restored.push((path, &entry.replaced));
+    }
+
+    fs::remove_file(journal_file)?;
+    Ok(journal.files.into_iter().map(|entry| entry.path).collect())
+}
+
+/// Undo a replacement batch. Nothing is restored if any of its files was
+/// edited afterwards. Returns the restored paths.
+#[tauri::command]
+pub fn undo_replace(app: tauri::AppHandle, batch_id: String) -> Result<Vec<String>, ReplaceError> {
+    let journal_file = journal_path(&app, &batch_id)?;
+
+    if !journal_file.exists() {
+        return Err(ReplaceError::Journal(format!("No replacement batch {}", batch_id)));
+    }
+
+    undo_batch(&journal_file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ourea-replace-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn query(text: &str, regex: bool) -> SearchQuery {
+        SearchQuery {
+            query: text.to_string(),
+            case_sensitive: true,
+            whole_word: false,
+            regex,
+        }
+    }
+
+    #[test]
+    fn expands_captures_for_regex_queries() {
+        let regex = build_regex(&query(r"(\w+)@(\w+)", true)).unwrap();
+        let (output, changes) = plan_replacements("a@b, c@d", &regex, "$2 at ${1}", true, None);
+
+        assert_eq!(output, "b at a, d at c");
+        assert_eq!(changes.len(), 2);
+        assert_eq!((changes[1].original.as_str(), changes[1].replacement.as_str()), ("c@d", "d at c"));
+        assert_eq!(changes[1].location.start, 5);
+    }
+
+    #[test]
+    fn replaces_literally_and_only_selected_matches() {
+        let regex = build_regex(&query("x", false)).unwrap();
+        let (output, _) = plan_replacements("x x x", &regex, "$1", false, None);
+        assert_eq!(output, "$1 $1 $1");
+
+        let selected = HashSet::from([2]);
+        let (output, changes) = plan_replacements("x x x", &regex, "y", false, Some(&selected));
+        assert_eq!(output, "x y x");
+        assert_eq!(changes.len(), 1);
+    }
+
+    #[test]
+    fn rejects_paths_outside_the_root() {
+        let dir = temp_dir("root");
+        let root = dir.join("workspace");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("note.md"), "x").unwrap();
+        fs::write(dir.join("secret.md"), "x").unwrap();
+
+        assert!(check_in_root(&root, &root.join("note.md")).is_ok());
+        assert!(matches!(
+            check_in_root(&root, &root.join("../secret.md")),
+            Err(ReplaceError::OutsideRoot(_))
+        ));
+        assert!(matches!(
+            check_in_root(&root, &root.join("missing.md")),
+            Err(ReplaceError::NotFound(_))
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn journals_and_undoes_batches() {
+        let dir = temp_dir("journal");
+        let a = dir.join("a.md");
+        let b = dir.join("b.md");
+        fs::write(&a, "old a").unwrap();
+        fs::write(&b, "old b").unwrap();
+
+        let entry = |path: &Path, original: &str, replaced: &str| JournalEntry {
+            path: path.display().to_string(),
+            original: original.to_string(),
+            replaced: replaced.to_string(),
+        };
+        let batch = |dir: &Path| {
+            apply_batch(dir, "root", vec![entry(&a, "old a", "new a"), entry(&b, "old b", "new b")]).unwrap()
+        };
+
+        // Two batches in quick succession get their own journals
+        let first = batch(&dir);
+        fs::write(&a, "old a").unwrap();
+        fs::write(&b, "old b").unwrap();
+        let second = batch(&dir);
+        assert_ne!(first, second);
+        assert_eq!(fs::read_to_string(&a).unwrap(), "new a");
+
+        // An edit after the batch blocks the undo entirely
+        let journal = dir.join(format!("{}.json", second));
+        fs::write(&b, "edited").unwrap();
+        assert!(matches!(undo_batch(&journal), Err(ReplaceError::Conflict(path)) if path == b.display().to_string()));
+        assert_eq!(fs::read_to_string(&a).unwrap(), "new a");
+
+        fs::write(&b, "new b").unwrap();
+        assert_eq!(undo_batch(&journal).unwrap().len(), 2);
+        assert_eq!(fs::read_to_string(&a).unwrap(), "old a");
+        assert_eq!(fs::read_to_string(&b).unwrap(), "old b");
+        assert!(!journal.exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
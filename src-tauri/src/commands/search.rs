@@ -0,0 +1,375 @@
+use ignore::WalkState;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter};
+
+use super::file::is_text_file_extension;
+use super::markdown::{workspace_walker, LineIndex};
+
+const DEFAULT_MAX_RESULTS: usize = 10_000;
+/// Longest line preview sent to the frontend, in characters
+const PREVIEW_CHARS: usize = 200;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchQuery {
+    pub query: String,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    #[serde(default)]
+    pub whole_word: bool,
+    #[serde(default)]
+    pub regex: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMatch {
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column, in characters
+    pub column: usize,
+    /// Byte range of the match in the file
+    pub start: usize,
+    pub end: usize,
+    /// The matched line, shortened around the match when long
+    pub preview: String,
+    /// Character range of the match within `preview`
+    pub preview_start: usize,
+    pub preview_end: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchFileResult {
+    pub search_id: String,
+    pub path: String,
+    pub matches: Vec<SearchMatch>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchFinished {
+    pub search_id: String,
+    pub files_searched: usize,
+    pub total_matches: usize,
+    pub cancelled: bool,
+    /// Whether the result limit stopped the search early
+    pub truncated: bool,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SearchError {
+    #[error("Invalid search pattern: {0}")]
+    Pattern(String),
+    #[error("Path not found: {0}")]
+    NotFound(String),
+}
+
+impl Serialize for SearchError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// Cancellation flags of running searches, by search id
+pub struct SearchState {
+    searches: HashMap<String, Arc<AtomicBool>>,
+}
+
+impl SearchState {
+    pub fn new() -> Self {
+        Self {
+            searches: HashMap::new(),
+        }
+    }
+}
+
+impl Default for SearchState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Compile a search query into a regex
+pub(crate) fn build_regex(query: &SearchQuery) -> Result<Regex, SearchError> {
+    if query.query.is_empty() {
+        return Err(SearchError::Pattern("empty query".to_string()));
+    }
+
+    let mut pattern = if query.regex {
+        query.query.clone()
+    } else {
+        regex::escape(&query.query)
+    };
+    if query.whole_word {
+        pattern = format!(r"\b(?:{})\b", pattern);
+    }
+
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!query.case_sensitive)
+        .multi_line(true)
+        .build()
+        .map_err(|e| SearchError::Pattern(e.to_string()))
+}
+
+fn is_searchable(path: &Path) -> bool {
+    is_text_file_extension(path.extension().and_then(|e| e.to_str()))
+}
+
+/// Collect every searchable file in a workspace
+pub(crate) fn searchable_files(root: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = workspace_walker(root)
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| entry.into_path())
+        .filter(|path| is_searchable(path))
+        .collect();
+
+    files.sort();
+    files
+}
+
+/// Build a preview of the line holding a match, keeping the match visible
+fn preview_line(line: &str, match_start: usize, match_end: usize) -> (String, usize, usize) {
+    let match_start = match_start.min(line.len());
+    let match_end = match_end.clamp(match_start, line.len());
+    let before: Vec<char> = line[..match_start].chars().collect();
+    let matched = line[match_start..match_end].chars().count();
+    let after = line[match_end..].chars();
+
+    // Keep a little leading context and spend the rest on the trailing side
+    let lead = before.len().min(PREVIEW_CHARS / 4);
+    let skipped = before.len() - lead;
+    let mut preview: String = before[skipped..].iter().collect();
+    let prefix = if skipped > 0 { "…" } else { "" };
+    preview.insert_str(0, prefix);
+
+    let offset = prefix.chars().count();
+    preview.push_str(&line[match_start..match_end]);
+    let remaining = PREVIEW_CHARS.saturating_sub(lead + matched);
+    preview.extend(after.take(remaining));
+
+    (preview, offset + lead, offset + lead + matched)
+}
+
+/// Find every match of a regex in some text
+pub(crate) fn find_matches(content: &str, regex: &Regex) -> Vec<SearchMatch> {
+    let lines = LineIndex::new(content);
+    let mut matches = Vec::new();
+
+    for found in regex.find_iter(content) {
+        if found.as_str().is_empty() {
+            continue;
+        }
+
+        let line = lines.line(found.start());
+        let line_start = lines.line_start(line);
+        let line_end = content[line_start..]
+            .find('\n')
+            .map_or(content.len(), |i| line_start + i);
+        let line_text = content[line_start..line_end].trim_end_matches('\r');
+
+        // A match can begin on the `\r` of a CRLF ending, past the trimmed line
+        let start_in_line = (found.start() - line_start).min(line_text.len());
+        let end_in_line = (found.end() - line_start).min(line_text.len());
+        let (preview, preview_start, preview_end) = preview_line(line_text, start_in_line, end_in_line);
+
+        matches.push(SearchMatch {
+            line,
+            column: content[line_start..found.start()].chars().count() + 1,
+            start: found.start(),
+            end: found.end(),
+            preview,
+            preview_start,
+            preview_end,
+        });
+    }
+
+    matches
+}
+
+/// Search every text file in a workspace. Results stream back as
+/// `search-result` events, one per file with matches, followed by a
+/// `search-finished` event.
+#[tauri::command]
+pub fn search_workspace(
+    app: AppHandle,
+    root: String,
+    query: SearchQuery,
+    search_id: String,
+    max_results: Option<usize>,
+    state: tauri::State<'_, Arc<Mutex<SearchState>>>,
+) -> Result<(), SearchError> {
+    let root_path = PathBuf::from(&root);
+
+    if !root_path.exists() {
+        return Err(SearchError::NotFound(root));
+    }
+
+    let regex = build_regex(&query)?;
+    let cancelled = Arc::new(AtomicBool::new(false));
+    state
+        .lock()
+        .unwrap()
+        .searches
+        .insert(search_id.clone(), cancelled.clone());
+
+    let searches = state.inner().clone();
+    let max_results = max_results.unwrap_or(DEFAULT_MAX_RESULTS);
+
+    // Walk on a background thread so the command returns immediately
+    std::thread::spawn(move || {
+        let files_searched = AtomicUsize::new(0);
+        let total_matches = AtomicUsize::new(0);
+        // Set only when files were left unsearched because of the limit
+        let truncated = AtomicBool::new(false);
+
+        workspace_walker(&root_path).build_parallel().run(|| {
+            let app = app.clone();
+            let regex = regex.clone();
+            let search_id = search_id.clone();
+            let cancelled = cancelled.clone();
+            let files_searched = &files_searched;
+            let total_matches = &total_matches;
+            let truncated = &truncated;
+
+            Box::new(move |entry| {
+                if cancelled.load(Ordering::Relaxed) {
+                    return WalkState::Quit;
+                }
+                if total_matches.load(Ordering::Relaxed) >= max_results {
+                    truncated.store(true, Ordering::Relaxed);
+                    return WalkState::Quit;
+                }
+
+                let Ok(entry) = entry else {
+                    return WalkState::Continue;
+                };
+                let path = entry.path();
+                if !entry.file_type().is_some_and(|t| t.is_file()) || !is_searchable(path) {
+                    return WalkState::Continue;
+                }
+
+                // Files that aren't valid UTF-8 are skipped
+                let Ok(content) = fs::read_to_string(path) else {
+                    return WalkState::Continue;
+                };
+                files_searched.fetch_add(1, Ordering::Relaxed);
+
+                let matches = find_matches(&content, &regex);
+                if !matches.is_empty() {
+                    total_matches.fetch_add(matches.len(), Ordering::Relaxed);
+                    let _ = app.emit(
+                        "search-result",
+                        SearchFileResult {
+                            search_id: search_id.clone(),
+                            path: path.display().to_string(),
+                            matches,
+                        },
+                    );
+                }
+
+                WalkState::Continue
+            })
+        });
+
+        let _ = app.emit(
+            "search-finished",
+            SearchFinished {
+                search_id: search_id.clone(),
+                files_searched: files_searched.load(Ordering::Relaxed),
+                total_matches: total_matches.load(Ordering::Relaxed),
+                cancelled: cancelled.load(Ordering::Relaxed),
+                truncated: truncated.load(Ordering::Relaxed),
+            },
+        );
+
+        searches.lock().unwrap().searches.remove(&search_id);
+    });
+
+    Ok(())
+}
+
+/// Cancel a running workspace search
+#[tauri::command]
+pub fn cancel_search(search_id: String, state: tauri::State<'_, Arc<Mutex<SearchState>>>) {
+    if let Some(cancelled) = state.lock().unwrap().searches.get(&search_id) {
+        cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(text: &str) -> SearchQuery {
+        SearchQuery {
+            query: text.to_string(),
+            case_sensitive: false,
+            whole_word: false,
+            regex: false,
+        }
+    }
+
+    #[test]
+    fn matches_on_crlf_line_endings() {
+        let content = "one\r\ntwo\r\n";
+
+        let newlines = find_matches(content, &build_regex(&query("\n")).unwrap());
+        assert_eq!(newlines.len(), 2);
+        assert_eq!((newlines[0].line, newlines[0].preview.as_str()), (1, "one"));
+        assert_eq!((newlines[0].preview_start, newlines[0].preview_end), (3, 3));
+
+        let regex = build_regex(&SearchQuery { regex: true, ..query(r"\s+") }).unwrap();
+        let spaces = find_matches(content, &regex);
+        assert_eq!(spaces.len(), 2);
+        assert_eq!((spaces[1].line, spaces[1].start, spaces[1].end), (2, 8, 10));
+        assert_eq!(spaces[1].preview, "two");
+    }
+
+    #[test]
+    fn reports_lines_and_character_columns() {
+        let content = "first\nnaïve Café café\n";
+        let matches = find_matches(content, &build_regex(&query("CAFÉ")).unwrap());
+
+        assert_eq!(matches.len(), 2);
+        assert_eq!((matches[0].line, matches[0].column), (2, 7));
+        assert_eq!(&content[matches[1].start..matches[1].end], "café");
+        assert_eq!(matches[1].preview, "naïve Café café");
+        assert_eq!((matches[1].preview_start, matches[1].preview_end), (11, 15));
+
+        let sensitive = SearchQuery { case_sensitive: true, ..query("café") };
+        assert_eq!(find_matches(content, &build_regex(&sensitive).unwrap()).len(), 1);
+    }
+
+    #[test]
+    fn honours_whole_word_and_escaping() {
+        let content = "cat catalog (cat)";
+        let whole_word = SearchQuery { whole_word: true, ..query("cat") };
+        assert_eq!(find_matches(content, &build_regex(&whole_word).unwrap()).len(), 2);
+        assert_eq!(find_matches(content, &build_regex(&query("(cat)")).unwrap()).len(), 1);
+
+        assert!(build_regex(&query("")).is_err());
+        assert!(build_regex(&SearchQuery { regex: true, ..query("(") }).is_err());
+    }
+
+    #[test]
+    fn shortens_long_previews_around_the_match() {
+        let line = format!("{}needle{}", "a".repeat(300), "b".repeat(300));
+        let (preview, start, end) = preview_line(&line, 300, 306);
+
+        assert!(preview.starts_with('…'));
+        assert_eq!(preview.chars().count(), 1 + PREVIEW_CHARS);
+        assert_eq!(preview.chars().skip(start).take(end - start).collect::<String>(), "needle");
+    }
+}
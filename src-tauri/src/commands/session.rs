@@ -0,0 +1,623 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+use super::file::write_atomic;
+use super::menu::refresh_open_recent;
+
+const SESSION_FILE: &str = "session.json";
+/// Unpinned recent files and folders kept; pinned ones don't count
+const RECENT_LIMIT: usize = 30;
+/// Closed tabs remembered for reopening
+const CLOSED_LIMIT: usize = 20;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecentKind {
+    File,
+    Folder,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentEntry {
+    pub path: String,
+    pub name: String,
+    /// Milliseconds since the Unix epoch
+    pub last_opened: u64,
+    pub pinned: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentLists {
+    pub files: Vec<RecentEntry>,
+    pub folders: Vec<RecentEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CursorPosition {
+    pub line: u32,
+    pub column: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TabSession {
+    pub path: String,
+    pub cursor: Option<CursorPosition>,
+    pub scroll_top: Option<f64>,
+}
+
+/// Open tabs and file tree state of a workspace
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WorkspaceSession {
+    pub tabs: Vec<TabSession>,
+    pub active_path: Option<String>,
+    pub expanded_paths: Vec<String>,
+    /// Milliseconds since the Unix epoch of the last save
+    pub updated: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct SessionData {
+    recent: RecentLists,
+    /// Sessions by workspace folder; the empty key holds tabs opened
+    /// without a folder
+    workspaces: BTreeMap<String, WorkspaceSession>,
+    last_workspace: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SessionError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid session file: {0}")]
+    Parse(String),
+    #[error("{0}")]
+    AppData(String),
+}
+
+impl Serialize for SessionError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// Recent files and folders and per-workspace sessions, loaded from app
+/// data on first use and written back on every change
+pub struct SessionStore {
+    data: Option<SessionData>,
+    /// Files of closed tabs, most recent last; not kept across restarts
+    closed: Vec<String>,
+}
+
+impl SessionStore {
+    pub fn new() -> Self {
+        Self {
+            data: None,
+            closed: Vec::new(),
+        }
+    }
+}
+
+impl Default for SessionStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn session_path(app: &tauri::AppHandle) -> Result<PathBuf, SessionError> {
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| SessionError::AppData(e.to_string()))?
+        .join(SESSION_FILE))
+}
+
+fn load<'a>(store: &'a mut SessionStore, path: &Path) -> Result<&'a mut SessionData, SessionError> {
+    if store.data.is_none() {
+        let data = match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                // A broken file shouldn't keep the app from starting
+                log::warn!("Ignoring invalid session file {}: {}", path.display(), e);
+                SessionData::default()
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => SessionData::default(),
+            Err(e) => return Err(e.into()),
+        };
+        store.data = Some(data);
+    }
+
+    Ok(store.data.as_mut().unwrap())
+}
+
+fn save(data: &SessionData, path: &Path) -> Result<(), SessionError> {
+    let json = serde_json::to_string_pretty(data).map_err(|e| SessionError::Parse(e.to_string()))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_atomic(path, json.as_bytes())?;
+    Ok(())
+}
+
+fn recent_list(recent: &mut RecentLists, kind: RecentKind) -> &mut Vec<RecentEntry> {
+    match kind {
+        RecentKind::File => &mut recent.files,
+        RecentKind::Folder => &mut recent.folders,
+    }
+}
+
+/// Pinned entries first, then most recently opened, dropping the oldest
+/// unpinned entries over the limit
+fn sort_recent(entries: &mut Vec<RecentEntry>) {
+    entries.sort_by(|a, b| b.pinned.cmp(&a.pinned).then(b.last_opened.cmp(&a.last_opened)));
+
+    let mut unpinned = 0;
+    entries.retain(|entry| {
+        if !entry.pinned {
+            unpinned += 1;
+        }
+        entry.pinned || unpinned <= RECENT_LIMIT
+    });
+}
+
+/// Drop entries whose file or folder is gone. Returns whether any were.
+fn prune_recent(recent: &mut RecentLists) -> bool {
+    let before = recent.files.len() + recent.folders.len();
+    recent.files.retain(|entry| Path::new(&entry.path).is_file());
+    recent.folders.retain(|entry| Path::new(&entry.path).is_dir());
+    before != recent.files.len() + recent.folders.len()
+}
+
+fn push_recent(recent: &mut RecentLists, kind: RecentKind, path: &str) {
+    let entries = recent_list(recent, kind);
+    let pinned = entries.iter().any(|entry| entry.path == path && entry.pinned);
+    entries.retain(|entry| entry.path != path);
+
+    let name = Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string());
+    entries.push(RecentEntry {
+        path: path.to_string(),
+        name,
+        last_opened: now_ms(),
+        pinned,
+    });
+    sort_recent(entries);
+}
+
+/// Rebuild the Open Recent menu after the recent lists or closed tabs
+/// change. The store is unlocked first, as menu changes wait on the main
+/// thread, which may be handling a menu event that needs the store.
+fn update_menu(app: &tauri::AppHandle, store: MutexGuard<'_, SessionStore>) {
+    let recent = store.data.as_ref().map(|data| data.recent.clone());
+    let has_closed = !store.closed.is_empty();
+    drop(store);
+
+    if let Some(recent) = recent {
+        refresh_open_recent(app, &recent, has_closed);
+    }
+}
+
+/// Fill the Open Recent menu from the saved lists when the app starts
+pub(crate) fn init_recent_menu(app: &tauri::AppHandle) -> Result<(), SessionError> {
+    let path = session_path(app)?;
+    let state = app.state::<Arc<Mutex<SessionStore>>>();
+    let mut store = state.lock().unwrap();
+    let data = load(&mut store, &path)?;
+
+    if prune_recent(&mut data.recent) {
+        save(data, &path)?;
+    }
+    update_menu(app, store);
+
+    Ok(())
+}
+
+/// Take the most recently closed tab's file for reopening
+pub(crate) fn take_closed_tab(app: &tauri::AppHandle) -> Option<String> {
+    let state = app.state::<Arc<Mutex<SessionStore>>>();
+    let mut store = state.lock().unwrap();
+    let path = store.closed.pop();
+
+    update_menu(app, store);
+    path
+}
+
+/// Recently opened files, most recent first, for ranking quick open
+pub(crate) fn recent_files(app: &tauri::AppHandle) -> Vec<String> {
+    let path = match session_path(app) {
+        Ok(path) => path,
+        Err(e) => {
+            log::warn!("Failed to read recent files: {}", e);
+            return Vec::new();
+        }
+    };
+    let state = app.state::<Arc<Mutex<SessionStore>>>();
+    let mut store = state.lock().unwrap();
+
+    let mut files = match load(&mut store, &path) {
+        Ok(data) => data.recent.files.clone(),
+        Err(e) => {
+            log::warn!("Failed to read recent files: {}", e);
+            return Vec::new();
+        }
+    };
+    files.sort_by_key(|entry| std::cmp::Reverse(entry.last_opened));
+    files.into_iter().map(|entry| entry.path).collect()
+}
+
+/// Clear the unpinned entries of both recent lists
+pub(crate) fn clear_recent_lists(app: &tauri::AppHandle) -> Result<RecentLists, SessionError> {
+    let path = session_path(app)?;
+    let state = app.state::<Arc<Mutex<SessionStore>>>();
+    let mut store = state.lock().unwrap();
+    let data = load(&mut store, &path)?;
+
+    data.recent.files.retain(|entry| entry.pinned);
+    data.recent.folders.retain(|entry| entry.pinned);
+    save(data, &path)?;
+
+    let recent = data.recent.clone();
+    update_menu(app, store);
+    Ok(recent)
+}
+
+/// Recent files and folders, without any that no longer exist
+#[tauri::command]
+pub fn get_recent(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<Mutex<SessionStore>>>,
+) -> Result<RecentLists, SessionError> {
+    let path = session_path(&app)?;
+    let mut store = state.lock().unwrap();
+    let data = load(&mut store, &path)?;
+
+    if !prune_recent(&mut data.recent) {
+        return Ok(data.recent.clone());
+    }
+    save(data, &path)?;
+
+    let recent = data.recent.clone();
+    update_menu(&app, store);
+    Ok(recent)
+}
+
+/// Move a file or folder to the top of its recent list
+#[tauri::command]
+pub fn add_recent(
+    app: tauri::AppHandle,
+    kind: RecentKind,
+    path: String,
+    state: tauri::State<'_, Arc<Mutex<SessionStore>>>,
+) -> Result<RecentLists, SessionError> {
+    let session_path = session_path(&app)?;
+    let mut store = state.lock().unwrap();
+    let data = load(&mut store, &session_path)?;
+
+    push_recent(&mut data.recent, kind, &path);
+    save(data, &session_path)?;
+
+    let recent = data.recent.clone();
+    update_menu(&app, store);
+    Ok(recent)
+}
+
+/// Pin or unpin a recent file or folder. Pinned entries stay at the top
+/// and are kept by `clear_recent`.
+#[tauri::command]
+pub fn pin_recent(
+    app: tauri::AppHandle,
+    kind: RecentKind,
+    path: String,
+    pinned: bool,
+    state: tauri::State<'_, Arc<Mutex<SessionStore>>>,
+) -> Result<RecentLists, SessionError> {
+    let session_path = session_path(&app)?;
+    let mut store = state.lock().unwrap();
+    let data = load(&mut store, &session_path)?;
+
+    let entries = recent_list(&mut data.recent, kind);
+    if let Some(entry) = entries.iter_mut().find(|entry| entry.path == path) {
+        entry.pinned = pinned;
+    }
+    sort_recent(entries);
+    save(data, &session_path)?;
+
+    let recent = data.recent.clone();
+    update_menu(&app, store);
+    Ok(recent)
+}
+
+#[tauri::command]
+pub fn remove_recent(
+    app: tauri::AppHandle,
+    kind: RecentKind,
+    path: String,
+    state: tauri::State<'_, Arc<Mutex<SessionStore>>>,
+) -> Result<RecentLists, SessionError> {
+    let session_path = session_path(&app)?;
+    let mut store = state.lock().unwrap();
+    let data = load(&mut store, &session_path)?;
+
+    recent_list(&mut data.recent, kind).retain(|entry| entry.path != path);
+    save(data, &session_path)?;
+
+    let recent = data.recent.clone();
+    update_menu(&app, store);
+    Ok(recent)
+}
+
+/// Remove every unpinned entry from a recent list
+#[tauri::command]
+pub fn clear_recent(
+    app: tauri::AppHandle,
+    kind: RecentKind,
+    state: tauri::State<'_, Arc<Mutex<SessionStore>>>,
+) -> Result<RecentLists, SessionError> {
+    let session_path = session_path(&app)?;
+    let mut store = state.lock().unwrap();
+    let data = load(&mut store, &session_path)?;
+
+    recent_list(&mut data.recent, kind).retain(|entry| entry.pinned);
+    save(data, &session_path)?;
+
+    let recent = data.recent.clone();
+    update_menu(&app, store);
+    Ok(recent)
+}
+
+fn push_closed(closed: &mut Vec<String>, path: String) {
+    closed.retain(|p| p != &path);
+    closed.push(path);
+    if closed.len() > CLOSED_LIMIT {
+        closed.remove(0);
+    }
+}
+
+/// Remember a closed tab's file for Reopen Closed Tab
+#[tauri::command]
+pub fn record_closed_tab(app: tauri::AppHandle, path: String, state: tauri::State<'_, Arc<Mutex<SessionStore>>>) {
+    let mut store = state.lock().unwrap();
+    push_closed(&mut store.closed, path);
+    update_menu(&app, store);
+}
+
+/// Remember the open tabs and expanded folders of a workspace, or of the
+/// editor without a folder when `workspace` is omitted
+#[tauri::command]
+pub fn save_workspace_session(
+    app: tauri::AppHandle,
+    workspace: Option<String>,
+    session: WorkspaceSession,
+    state: tauri::State<'_, Arc<Mutex<SessionStore>>>,
+) -> Result<(), SessionError> {
+    let path = session_path(&app)?;
+    let mut store = state.lock().unwrap();
+    let data = load(&mut store, &path)?;
+
+    data.workspaces.insert(
+        workspace.clone().unwrap_or_default(),
+        WorkspaceSession {
+            updated: now_ms(),
+            ..session
+        },
+    );
+    data.last_workspace = workspace;
+    save(data, &path)
+}
+
+/// The saved session of a workspace, leaving out tabs and folders that no
+/// longer exist
+#[tauri::command]
+pub fn load_workspace_session(
+    app: tauri::AppHandle,
+    workspace: Option<String>,
+    state: tauri::State<'_, Arc<Mutex<SessionStore>>>,
+) -> Result<Option<WorkspaceSession>, SessionError> {
+    let path = session_path(&app)?;
+    let mut store = state.lock().unwrap();
+    let data = load(&mut store, &path)?;
+
+    Ok(data.workspaces.get(&workspace.unwrap_or_default()).map(existing_session))
+}
+
+/// A session without the tabs and folders that no longer exist
+fn existing_session(session: &WorkspaceSession) -> WorkspaceSession {
+    let mut session = session.clone();
+    session.tabs.retain(|tab| Path::new(&tab.path).is_file());
+    session.expanded_paths.retain(|dir| Path::new(dir).is_dir());
+    if !session.tabs.iter().any(|tab| Some(&tab.path) == session.active_path.as_ref()) {
+        session.active_path = session.tabs.first().map(|tab| tab.path.clone());
+    }
+    session
+}
+
+/// The workspace open when the last session was saved, if it still exists
+#[tauri::command]
+pub fn get_last_workspace(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<Mutex<SessionStore>>>,
+) -> Result<Option<String>, SessionError> {
+    let path = session_path(&app)?;
+    let mut store = state.lock().unwrap();
+    let data = load(&mut store, &path)?;
+
+    Ok(data.last_workspace.clone().filter(|workspace| Path::new(workspace).is_dir()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Folder(PathBuf);
+
+    impl Folder {
+        fn new(name: &str) -> Self {
+            let root = std::env::temp_dir().join(format!("ourea-session-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&root);
+            fs::create_dir_all(&root).unwrap();
+            Self(root)
+        }
+
+        fn file(&self, name: &str) -> String {
+            let path = self.0.join(name);
+            fs::write(&path, "").unwrap();
+            path.display().to_string()
+        }
+    }
+
+    impl Drop for Folder {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn entry(path: &str, last_opened: u64, pinned: bool) -> RecentEntry {
+        RecentEntry {
+            path: path.to_string(),
+            name: path.to_string(),
+            last_opened,
+            pinned,
+        }
+    }
+
+    fn paths(entries: &[RecentEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.path.as_str()).collect()
+    }
+
+    #[test]
+    fn sorts_pinned_first_then_newest_and_caps_unpinned() {
+        let mut entries = vec![
+            entry("old", 1, false),
+            entry("pinned-old", 2, true),
+            entry("new", 9, false),
+            entry("pinned-new", 5, true),
+        ];
+        sort_recent(&mut entries);
+        assert_eq!(paths(&entries), ["pinned-new", "pinned-old", "new", "old"]);
+
+        let mut many: Vec<RecentEntry> =
+            (0..RECENT_LIMIT as u64 + 5).map(|i| entry(&i.to_string(), i, false)).collect();
+        many.push(entry("pinned", 0, true));
+        sort_recent(&mut many);
+        assert_eq!(many.len(), RECENT_LIMIT + 1);
+        assert_eq!(many[0].path, "pinned");
+        assert_eq!(many.last().unwrap().path, "5");
+    }
+
+    #[test]
+    fn re_adding_moves_an_entry_to_the_top() {
+        let mut recent = RecentLists {
+            files: vec![entry("/a.md", 10, true), entry("/b.md", 20, false), entry("/c.md", 30, false)],
+            folders: Vec::new(),
+        };
+
+        push_recent(&mut recent, RecentKind::File, "/b.md");
+        assert_eq!(paths(&recent.files), ["/a.md", "/b.md", "/c.md"]);
+        assert!(recent.files[1].last_opened > 30);
+        assert_eq!(recent.files[1].name, "b.md");
+
+        push_recent(&mut recent, RecentKind::File, "/a.md");
+        assert_eq!(recent.files.iter().filter(|e| e.path == "/a.md").count(), 1);
+        assert!(recent.files[0].pinned && recent.files[0].last_opened > 10);
+
+        push_recent(&mut recent, RecentKind::Folder, "/notes");
+        assert_eq!(paths(&recent.folders), ["/notes"]);
+    }
+
+    #[test]
+    fn prunes_paths_that_no_longer_exist() {
+        let folder = Folder::new("prune");
+        let kept = folder.file("kept.md");
+        let gone = folder.0.join("gone.md").display().to_string();
+        let dir = folder.0.display().to_string();
+
+        let mut recent = RecentLists {
+            files: vec![entry(&kept, 1, false), entry(&gone, 2, true), entry(&dir, 3, false)],
+            folders: vec![entry(&dir, 1, true), entry(&kept, 2, false)],
+        };
+        assert!(prune_recent(&mut recent));
+        assert_eq!(paths(&recent.files), [kept.as_str()]);
+        assert_eq!(paths(&recent.folders), [dir.as_str()]);
+        assert!(!prune_recent(&mut recent));
+    }
+
+    #[test]
+    fn remembers_closed_tabs_most_recent_last() {
+        let mut closed = Vec::new();
+        for i in 0..CLOSED_LIMIT + 2 {
+            push_closed(&mut closed, format!("/{}.md", i));
+        }
+        push_closed(&mut closed, "/5.md".to_string());
+
+        assert_eq!(closed.len(), CLOSED_LIMIT);
+        assert_eq!(closed.first().map(String::as_str), Some("/2.md"));
+        assert_eq!(closed.pop().as_deref(), Some("/5.md"));
+        assert_eq!(closed.iter().filter(|p| *p == "/5.md").count(), 0);
+    }
+
+    #[test]
+    fn saves_and_loads_workspace_sessions() {
+        let folder = Folder::new("roundtrip");
+        let open = folder.file("open.md");
+        let gone = folder.0.join("gone.md").display().to_string();
+        let path = folder.0.join("data").join(SESSION_FILE);
+
+        let mut data = SessionData::default();
+        let session = WorkspaceSession {
+            tabs: vec![
+                TabSession {
+                    path: gone.clone(),
+                    cursor: Some(CursorPosition { line: 3, column: 4 }),
+                    scroll_top: None,
+                },
+                TabSession {
+                    path: open.clone(),
+                    cursor: None,
+                    scroll_top: Some(120.5),
+                },
+            ],
+            active_path: Some(gone),
+            expanded_paths: vec![folder.0.display().to_string(), folder.0.join("missing").display().to_string()],
+            updated: 7,
+        };
+        data.workspaces.insert(folder.0.display().to_string(), session);
+        data.last_workspace = Some(folder.0.display().to_string());
+        save(&data, &path).unwrap();
+
+        let mut store = SessionStore::new();
+        let loaded = load(&mut store, &path).unwrap();
+        assert_eq!(loaded.last_workspace, data.last_workspace);
+
+        let session = existing_session(&loaded.workspaces[&folder.0.display().to_string()]);
+        assert_eq!(session.tabs.len(), 1);
+        assert_eq!((session.tabs[0].path.as_str(), session.tabs[0].scroll_top), (open.as_str(), Some(120.5)));
+        assert_eq!(session.active_path, Some(open));
+        assert_eq!(session.expanded_paths, [folder.0.display().to_string()]);
+        assert_eq!(session.updated, 7);
+
+        // A broken file starts over instead of failing
+        fs::write(&path, "{not json").unwrap();
+        assert!(load(&mut SessionStore::new(), &path).unwrap().workspaces.is_empty());
+    }
+}
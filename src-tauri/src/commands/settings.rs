@@ -1,10 +1,15 @@
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tauri::Manager;
 
+use super::lint::LintConfig;
+use super::optimize::ImageOptimization;
+use super::versions::VersionHistory;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
 pub struct AppSettings {
     // Theme settings
     pub theme_mode: String,
@@ -33,6 +38,18 @@ pub struct AppSettings {
     pub image_storage_location: String,
     pub image_naming_rule: String,
     pub image_assets_folder: String,
+    pub image_optimization: ImageOptimization,
+
+    // Lint settings
+    pub lint: LintConfig,
+
+    // Network settings
+    /// Proxy for downloads such as `http://host:port`; empty uses the environment
+    pub http_proxy: String,
+    pub download_timeout_secs: u64,
+
+    // Version history settings
+    pub version_history: VersionHistory,
 }
 
 impl Default for AppSettings {
@@ -58,6 +75,11 @@ impl Default for AppSettings {
             image_storage_location: "relative".to_string(),
             image_naming_rule: "timestamp".to_string(),
             image_assets_folder: "assets".to_string(),
+            image_optimization: ImageOptimization::default(),
+            lint: LintConfig::default(),
+            http_proxy: String::new(),
+            download_timeout_secs: 30,
+            version_history: VersionHistory::default(),
         }
     }
 }
@@ -78,10 +100,62 @@ fn get_settings_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
     Ok(app_data_dir.join("settings.json"))
 }
 
-/// Load settings from file
-#[tauri::command]
-pub async fn load_settings(app: tauri::AppHandle) -> Result<AppSettings, String> {
-    let settings_path = get_settings_path(&app)?;
+/// Find a per-workspace config file in the nearest `.ourea` folder at or above `start`
+pub(crate) fn find_workspace_config(start: &Path, name: &str) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(".ourea").join(name))
+        .find(|path| path.is_file())
+}
+
+/// `max_width` to `maxWidth`; camelCase keys are left as they are
+fn camel_case(key: &str) -> String {
+    let mut parts = key.split('_');
+    let mut camel = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            camel.extend(first.to_uppercase());
+            camel.push_str(chars.as_str());
+        }
+    }
+    camel
+}
+
+/// Apply a workspace `.ourea/<name>` TOML file on top of a settings section.
+/// Keys may be camelCase like the app settings or snake_case; keys the file
+/// leaves out keep their app setting, and unknown keys are an error.
+pub(crate) fn with_workspace_overrides<T: Clone + Serialize + DeserializeOwned>(
+    base: &T,
+    start: Option<&Path>,
+    name: &str,
+) -> Result<T, String> {
+    let Some(config_path) = start.and_then(|start| find_workspace_config(start, name)) else {
+        return Ok(base.clone());
+    };
+
+    let content = fs::read_to_string(&config_path)
+        .map_err(|e| format!("Failed to read {}: {}", config_path.display(), e))?;
+    let overrides: serde_json::Value =
+        toml::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", config_path.display(), e))?;
+    let mut merged = serde_json::to_value(base).map_err(|e| e.to_string())?;
+
+    if let (serde_json::Value::Object(merged), serde_json::Value::Object(overrides)) = (&mut merged, overrides) {
+        for (key, value) in overrides {
+            let field = camel_case(&key);
+            if !merged.contains_key(&field) {
+                return Err(format!("Unknown key `{}` in {}", key, config_path.display()));
+            }
+            merged.insert(field, value);
+        }
+    }
+
+    serde_json::from_value(merged).map_err(|e| format!("Invalid {}: {}", config_path.display(), e))
+}
+
+/// Read settings from disk for use by other backend commands
+pub(crate) fn read_settings(app: &tauri::AppHandle) -> Result<AppSettings, String> {
+    let settings_path = get_settings_path(app)?;
 
     if !settings_path.exists() {
         // Return default settings if file doesn't exist
@@ -97,6 +171,12 @@ pub async fn load_settings(app: tauri::AppHandle) -> Result<AppSettings, String>
     Ok(settings)
 }
 
+/// Load settings from file
+#[tauri::command]
+pub async fn load_settings(app: tauri::AppHandle) -> Result<AppSettings, String> {
+    read_settings(&app)
+}
+
 /// Save settings to file
 #[tauri::command]
 pub async fn save_settings(app: tauri::AppHandle, settings: AppSettings) -> Result<(), String> {
@@ -117,3 +197,35 @@ pub async fn get_settings_file_path(app: tauri::AppHandle) -> Result<String, Str
     let path = get_settings_path(&app)?;
     Ok(path.to_string_lossy().to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::optimize::{OutputFormat, CONFIG_FILE};
+
+    #[test]
+    fn applies_camel_and_snake_case_overrides() {
+        let root = std::env::temp_dir().join(format!("ourea-settings-overrides-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join(".ourea")).unwrap();
+        fs::create_dir_all(root.join("notes")).unwrap();
+        let config = root.join(".ourea").join(CONFIG_FILE);
+        let base = ImageOptimization::default();
+
+        assert_eq!(with_workspace_overrides(&base, Some(&root), CONFIG_FILE).unwrap().max_width, 1920);
+
+        fs::write(&config, "max_width = 1280\nstrip_metadata = false\nformat = \"webp\"\nenabled = true\n").unwrap();
+        let merged = with_workspace_overrides(&base, Some(&root.join("notes")), CONFIG_FILE).unwrap();
+        assert_eq!((merged.max_width, merged.strip_metadata, merged.format), (1280, false, OutputFormat::Webp));
+        assert_eq!((merged.enabled, merged.quality), (true, 85));
+
+        fs::write(&config, "maxWidth = 800\n").unwrap();
+        assert_eq!(with_workspace_overrides(&base, Some(&root), CONFIG_FILE).unwrap().max_width, 800);
+
+        fs::write(&config, "max_widht = 800\n").unwrap();
+        let error = with_workspace_overrides(&base, Some(&root), CONFIG_FILE).unwrap_err();
+        assert!(error.starts_with("Unknown key `max_widht`"), "{}", error);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
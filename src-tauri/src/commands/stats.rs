@@ -0,0 +1,304 @@
+use pulldown_cmark::{Event, LinkType, Parser, Tag, TagEnd};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::markdown::{markdown_files, parser_options};
+
+/// Average silent reading speed for space-separated languages
+const LATIN_WORDS_PER_MINUTE: f64 = 200.0;
+/// Average silent reading speed for Chinese and Japanese text
+const CJK_CHARACTERS_PER_MINUTE: f64 = 300.0;
+
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentStats {
+    /// Latin words plus CJK characters
+    pub words: usize,
+    pub latin_words: usize,
+    pub cjk_characters: usize,
+    /// Characters excluding whitespace
+    pub characters: usize,
+    pub characters_with_spaces: usize,
+    pub sentences: usize,
+    pub paragraphs: usize,
+    pub reading_time_seconds: u64,
+}
+
+impl DocumentStats {
+    fn add(&mut self, other: &DocumentStats) {
+        self.words += other.words;
+        self.latin_words += other.latin_words;
+        self.cjk_characters += other.cjk_characters;
+        self.characters += other.characters;
+        self.characters_with_spaces += other.characters_with_spaces;
+        self.sentences += other.sentences;
+        self.paragraphs += other.paragraphs;
+        self.update_reading_time();
+    }
+
+    fn update_reading_time(&mut self) {
+        let minutes = self.latin_words as f64 / LATIN_WORDS_PER_MINUTE
+            + self.cjk_characters as f64 / CJK_CHARACTERS_PER_MINUTE;
+        self.reading_time_seconds = (minutes * 60.0).ceil() as u64;
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileStats {
+    pub path: String,
+    pub stats: DocumentStats,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderStats {
+    pub files: Vec<FileStats>,
+    pub total: DocumentStats,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum StatsError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("File not found: {0}")]
+    NotFound(String),
+    #[error("Either a path or content is required")]
+    MissingInput,
+    #[error("Stats task failed: {0}")]
+    Task(String),
+}
+
+impl Serialize for StatsError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// Han ideographs and Japanese kana, which are read one character at a time.
+/// Hangul separates words with spaces, so it is counted like Latin text.
+pub(crate) fn is_cjk(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x3040..=0x30FF     // Hiragana, Katakana
+            | 0x31F0..=0x31FF   // Katakana phonetic extensions
+            | 0x3400..=0x4DBF   // CJK Extension A
+            | 0x4E00..=0x9FFF   // CJK Unified Ideographs
+            | 0xF900..=0xFAFF   // CJK Compatibility Ideographs
+            | 0xFF66..=0xFF9F   // Halfwidth Katakana
+            | 0x20000..=0x2FA1F // CJK Extensions B-F, Compatibility Supplement
+    )
+}
+
+fn is_url(token: &str) -> bool {
+    token.starts_with("http://") || token.starts_with("https://") || token.starts_with("www.")
+}
+
+/// Drop bare URLs from text while keeping the surrounding whitespace
+fn strip_urls(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+
+    for piece in text.split_inclusive(char::is_whitespace) {
+        let token = piece.trim_end();
+        if is_url(token) {
+            out.push_str(&piece[token.len()..]);
+        } else {
+            out.push_str(piece);
+        }
+    }
+
+    out
+}
+
+/// Collect the prose of a Markdown document, dropping syntax, code blocks,
+/// math, raw HTML, front matter and link targets. Blocks are separated by
+/// blank lines so sentence detection doesn't run across them.
+fn prose_text(content: &str) -> (String, usize) {
+    let mut text = String::new();
+    let mut paragraphs = 0;
+    let mut skip_depth: i32 = 0;
+    // Whether each open link is an autolink, whose text is just its URL
+    let mut links: Vec<bool> = Vec::new();
+
+    for event in Parser::new_ext(content, parser_options()) {
+        match event {
+            Event::Start(Tag::CodeBlock(_))
+            | Event::Start(Tag::MetadataBlock(_))
+            | Event::Start(Tag::Image { .. }) => skip_depth += 1,
+            Event::End(TagEnd::CodeBlock)
+            | Event::End(TagEnd::MetadataBlock(_))
+            | Event::End(TagEnd::Image) => skip_depth -= 1,
+            Event::Start(Tag::Link { link_type, .. }) => {
+                let is_autolink = matches!(link_type, LinkType::Autolink | LinkType::Email);
+                if is_autolink {
+                    skip_depth += 1;
+                }
+                links.push(is_autolink);
+            }
+            Event::End(TagEnd::Link) => skip_depth -= i32::from(links.pop() == Some(true)),
+            Event::Start(Tag::Paragraph) if skip_depth == 0 => paragraphs += 1,
+            Event::End(TagEnd::Paragraph)
+            | Event::End(TagEnd::Heading(_))
+            | Event::End(TagEnd::Item)
+            | Event::End(TagEnd::TableCell) => text.push_str("\n\n"),
+            Event::Text(t) | Event::Code(t) if skip_depth == 0 => text.push_str(&t),
+            Event::SoftBreak | Event::HardBreak => text.push(' '),
+            _ => {}
+        }
+    }
+
+    (strip_urls(&text), paragraphs)
+}
+
+/// Count words, characters, sentences and paragraphs in Markdown content
+pub(crate) fn compute_stats(content: &str) -> DocumentStats {
+    let (text, paragraphs) = prose_text(content);
+    let mut stats = DocumentStats {
+        paragraphs,
+        ..Default::default()
+    };
+
+    for word in text.unicode_words() {
+        let cjk = word.chars().filter(|c| is_cjk(*c)).count();
+        stats.cjk_characters += cjk;
+        // Mixed tokens such as "Rust语言" count the Latin part as one word
+        if word.chars().any(|c| c.is_alphanumeric() && !is_cjk(c)) {
+            stats.latin_words += 1;
+        }
+    }
+
+    let prose = text.trim();
+    stats.characters = prose.chars().filter(|c| !c.is_whitespace()).count();
+    stats.characters_with_spaces = prose
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|block| !block.is_empty())
+        .map(|block| block.chars().count())
+        .sum();
+    stats.sentences = prose
+        .unicode_sentences()
+        .filter(|s| s.chars().any(char::is_alphanumeric))
+        .count();
+    stats.words = stats.latin_words + stats.cjk_characters;
+    stats.update_reading_time();
+
+    stats
+}
+
+/// Get statistics for a document or selection, from its path or content
+#[tauri::command]
+pub fn document_stats(path: Option<String>, content: Option<String>) -> Result<DocumentStats, StatsError> {
+    let content = match (content, path) {
+        (Some(content), _) => content,
+        (None, Some(path)) => {
+            if !Path::new(&path).exists() {
+                return Err(StatsError::NotFound(path));
+            }
+            fs::read_to_string(&path)?
+        }
+        (None, None) => return Err(StatsError::MissingInput),
+    };
+
+    Ok(compute_stats(&content))
+}
+
+fn collect_folder_stats(root: &Path) -> FolderStats {
+    let mut files = Vec::new();
+    let mut total = DocumentStats::default();
+
+    for file in markdown_files(root) {
+        let Ok(content) = fs::read_to_string(&file) else {
+            continue;
+        };
+
+        let stats = compute_stats(&content);
+        total.add(&stats);
+        files.push(FileStats {
+            path: file.display().to_string(),
+            stats,
+        });
+    }
+
+    FolderStats { files, total }
+}
+
+/// Get per-file and aggregated statistics for every Markdown file in a folder
+#[tauri::command]
+pub async fn folder_stats(path: String) -> Result<FolderStats, StatsError> {
+    let root = PathBuf::from(&path);
+
+    if !root.exists() {
+        return Err(StatsError::NotFound(path));
+    }
+
+    tauri::async_runtime::spawn_blocking(move || collect_folder_stats(&root))
+        .await
+        .map_err(|e| StatsError::Task(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_each_cjk_character_as_a_word() {
+        let stats = compute_stats("我喜欢写笔记。ひらがなとカタカナ。\n");
+
+        assert_eq!(stats.cjk_characters, 15);
+        assert_eq!((stats.latin_words, stats.words), (0, 15));
+        assert_eq!(stats.characters, 17);
+        assert_eq!(stats.sentences, 2);
+    }
+
+    #[test]
+    fn counts_mixed_cjk_and_latin_text() {
+        let stats = compute_stats("我用 Rust 写代码, and it works.\n");
+
+        assert_eq!(stats.cjk_characters, 5);
+        assert_eq!(stats.latin_words, 4);
+        assert_eq!(stats.words, 9);
+
+        // Hangul is spaced like Latin text
+        let korean = compute_stats("안녕하세요 세계\n");
+        assert_eq!((korean.cjk_characters, korean.latin_words), (0, 2));
+    }
+
+    #[test]
+    fn skips_code_urls_and_link_targets() {
+        let content = "---\ntitle: Skipped words here\n---\n\n\
+                       See [the docs](https://example.com/a/b) or https://example.com and <https://auto.link>.\n\n\
+                       ```rust\nfn main() { let words = 3; }\n```\n\n\
+                       ![alt text](image.png) `code` ends\n";
+        let stats = compute_stats(content);
+
+        // See, the, docs, or, and / code, ends
+        assert_eq!(stats.latin_words, 7);
+        assert_eq!(stats.paragraphs, 2);
+        assert_eq!(strip_urls("a https://x.y/z\tb www.c.d\n"), "a \tb \n");
+    }
+
+    #[test]
+    fn counts_sentences_paragraphs_and_reading_time() {
+        let content = "# Heading\n\nOne sentence. Two sentences! Three?\n\n- an item\n- another item\n\nLast paragraph\n";
+        let stats = compute_stats(content);
+
+        assert_eq!(stats.paragraphs, 2);
+        assert_eq!(stats.sentences, 7);
+        assert_eq!(stats.latin_words, 12);
+
+        let long = compute_stats(&"word ".repeat(400));
+        assert_eq!(long.reading_time_seconds, 120);
+        let cjk = compute_stats(&"字".repeat(150));
+        assert_eq!(cjk.reading_time_seconds, 30);
+
+        let mut total = DocumentStats::default();
+        total.add(&long);
+        total.add(&cjk);
+        assert_eq!((total.words, total.reading_time_seconds), (550, 150));
+    }
+}
@@ -0,0 +1,533 @@
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use super::file::write_atomic;
+use super::frontmatter::{parse_front_matter, update_front_matter, FrontMatterError};
+use super::markdown::{is_markdown_file, markdown_files, parser_options};
+use super::watcher::{watch_workspace, WatcherError, WorkspaceSubscription, WorkspaceWatchers};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagNode {
+    /// Last segment, `alpha` in `project/alpha`
+    pub name: String,
+    /// Full tag without `#`
+    pub tag: String,
+    /// Files tagged with this tag itself
+    pub count: usize,
+    /// Files tagged with this tag or any nested tag
+    pub total: usize,
+    pub children: Vec<TagNode>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaggedFile {
+    pub path: String,
+    /// The matching tags as written in the file
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagRenameResult {
+    pub edited: Vec<String>,
+    pub replacements: usize,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TagError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Path not found: {0}")]
+    NotFound(String),
+    #[error("Invalid tag: {0}")]
+    Invalid(String),
+    #[error("{0}")]
+    FrontMatter(#[from] FrontMatterError),
+    #[error("{0}")]
+    Watch(#[from] WatcherError),
+    #[error("Tag task failed: {0}")]
+    Task(String),
+}
+
+impl Serialize for TagError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// Tags of every Markdown file in a workspace, as written
+type TagIndex = Arc<Mutex<HashMap<PathBuf, Vec<String>>>>;
+
+/// Tag index of the open workspace, kept current by the workspace watcher
+pub struct TagState {
+    root: Option<PathBuf>,
+    files: TagIndex,
+    subscription: Option<WorkspaceSubscription>,
+}
+
+impl TagState {
+    pub fn new() -> Self {
+        Self {
+            root: None,
+            files: Arc::new(Mutex::new(HashMap::new())),
+            subscription: None,
+        }
+    }
+}
+
+impl Default for TagState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A tag in the body, with the byte range of its name after the `#`
+struct BodyTag {
+    tag: String,
+    range: Range<usize>,
+}
+
+/// Tags of one document
+#[derive(Default)]
+struct DocumentTags {
+    body: Vec<BodyTag>,
+    front_matter: Vec<String>,
+}
+
+impl DocumentTags {
+    fn all(&self) -> impl Iterator<Item = &str> {
+        self.body.iter().map(|t| t.tag.as_str()).chain(self.front_matter.iter().map(String::as_str))
+    }
+}
+
+fn tag_regex() -> &'static Regex {
+    static TAG: OnceLock<Regex> = OnceLock::new();
+    // The prefix keeps `a#b`, `&#38;` and URL fragments from counting
+    TAG.get_or_init(|| Regex::new(r"(?:^|[^\w/&#])#([\p{L}\p{N}_\-/]+)").unwrap())
+}
+
+/// Clean up a tag and check it is one: `123` is an issue number, not a tag
+fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim().trim_start_matches('#').trim_matches('/');
+    let valid = !tag.is_empty()
+        && !tag.contains("//")
+        && tag.chars().any(|c| !c.is_numeric())
+        && tag.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '/'));
+    valid.then(|| tag.to_string())
+}
+
+/// Find `#tags` in prose, leaving out code, headings, links and front matter
+fn body_tags(content: &str) -> Vec<BodyTag> {
+    let mut tags = Vec::new();
+    let mut skip_depth = 0;
+
+    for (event, range) in Parser::new_ext(content, parser_options()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { .. })
+            | Event::Start(Tag::CodeBlock(_))
+            | Event::Start(Tag::MetadataBlock(_))
+            | Event::Start(Tag::Link { .. })
+            | Event::Start(Tag::Image { .. }) => skip_depth += 1,
+            Event::End(TagEnd::Heading(_))
+            | Event::End(TagEnd::CodeBlock)
+            | Event::End(TagEnd::MetadataBlock(_))
+            | Event::End(TagEnd::Link)
+            | Event::End(TagEnd::Image) => skip_depth -= 1,
+            Event::Text(text) if skip_depth == 0 => {
+                // Escaped or entity text doesn't map back to the source
+                if content.get(range.clone()) != Some(&*text) {
+                    continue;
+                }
+                for captures in tag_regex().captures_iter(&text) {
+                    let name = captures.get(1).unwrap();
+                    let raw = name.as_str().trim_end_matches('/');
+                    if let Some(tag) = normalize_tag(raw) {
+                        let start = range.start + name.start();
+                        tags.push(BodyTag {
+                            tag,
+                            range: start..start + raw.len(),
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    tags
+}
+
+/// Tags listed under `tags` in front matter, as a list or a comma or space
+/// separated string
+fn front_matter_tags(data: &Map<String, Value>) -> Vec<String> {
+    match data.get("tags") {
+        Some(Value::Array(items)) => items.iter().filter_map(Value::as_str).filter_map(normalize_tag).collect(),
+        Some(Value::String(text)) => text
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter_map(normalize_tag)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn document_tags(content: &str) -> DocumentTags {
+    let front_matter = parse_front_matter(content)
+        .ok()
+        .flatten()
+        .map(|fm| front_matter_tags(&fm.data))
+        .unwrap_or_default();
+
+    DocumentTags {
+        body: body_tags(content),
+        front_matter,
+    }
+}
+
+/// Whether `tag` is `parent` or nested under it, ignoring case
+fn is_within(tag: &str, parent: &str) -> bool {
+    let tag = tag.to_lowercase();
+    let parent = parent.to_lowercase();
+    tag == parent || tag.starts_with(&format!("{}/", parent))
+}
+
+fn index_file(files: &Mutex<HashMap<PathBuf, Vec<String>>>, path: &Path) {
+    if let Ok(content) = fs::read_to_string(path) {
+        let tags = document_tags(&content).all().map(str::to_string).collect();
+        files.lock().unwrap().insert(path.to_path_buf(), tags);
+    }
+}
+
+/// Bring the index up to date after a change on disk
+fn update_path(files: &Mutex<HashMap<PathBuf, Vec<String>>>, path: &Path) {
+    if path.is_dir() {
+        // A folder moved in brings its notes along
+        for file in markdown_files(path) {
+            index_file(files, &file);
+        }
+    } else if !path.exists() {
+        files.lock().unwrap().retain(|indexed, _| !indexed.starts_with(path));
+    } else if is_markdown_file(path) {
+        index_file(files, path);
+    }
+}
+
+/// The tag index of a workspace, built on first use
+fn workspace_index(
+    root: &str,
+    state: &Mutex<TagState>,
+    watchers: &Arc<Mutex<WorkspaceWatchers>>,
+) -> Result<TagIndex, TagError> {
+    let root_path = Path::new(root);
+
+    if !root_path.exists() {
+        return Err(TagError::NotFound(root.to_string()));
+    }
+
+    let mut state = state.lock().unwrap();
+    if state.root.as_deref() == Some(root_path) {
+        return Ok(state.files.clone());
+    }
+
+    // Watch before scanning, so no change in between is missed
+    let files: TagIndex = Arc::new(Mutex::new(HashMap::new()));
+    let watched = files.clone();
+    let subscription = watch_workspace(watchers, root_path, move |path| update_path(&watched, path))?;
+    for path in markdown_files(root_path) {
+        index_file(&files, &path);
+    }
+
+    state.root = Some(root_path.to_path_buf());
+    state.files = files.clone();
+    state.subscription = Some(subscription);
+    Ok(files)
+}
+
+/// Files and their tags, in path order so results don't shift around
+fn sorted_files(files: &TagIndex) -> Vec<(PathBuf, Vec<String>)> {
+    let mut files: Vec<(PathBuf, Vec<String>)> =
+        files.lock().unwrap().iter().map(|(path, tags)| (path.clone(), tags.clone())).collect();
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    files
+}
+
+/// Parsing and reading every file blocks, so commands run off the async runtime
+async fn run_blocking<T: Send + 'static>(
+    task: impl FnOnce() -> Result<T, TagError> + Send + 'static,
+) -> Result<T, TagError> {
+    tauri::async_runtime::spawn_blocking(task)
+        .await
+        .map_err(|e| TagError::Task(e.to_string()))?
+}
+
+#[derive(Default)]
+struct TreeBuilder {
+    name: String,
+    count: usize,
+    files: HashSet<usize>,
+    children: BTreeMap<String, TreeBuilder>,
+}
+
+impl TreeBuilder {
+    fn build(self, prefix: &str) -> TagNode {
+        let tag = if prefix.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}/{}", prefix, self.name)
+        };
+        let children: Vec<TagNode> = self.children.into_values().map(|child| child.build(&tag)).collect();
+
+        TagNode {
+            name: self.name,
+            total: self.files.len(),
+            count: self.count,
+            children,
+            tag,
+        }
+    }
+}
+
+fn tag_tree(files: &TagIndex) -> Vec<TagNode> {
+    let mut roots: BTreeMap<String, TreeBuilder> = BTreeMap::new();
+
+    for (file, (_, tags)) in sorted_files(files).iter().enumerate() {
+        let mut seen = HashSet::new();
+
+        for tag in tags {
+            if !seen.insert(tag.to_lowercase()) {
+                continue;
+            }
+
+            // Tags differing only in case are merged under the first spelling
+            let mut level = &mut roots;
+            let segments: Vec<&str> = tag.split('/').collect();
+            for (depth, segment) in segments.iter().enumerate() {
+                let node = level.entry(segment.to_lowercase()).or_insert_with(|| TreeBuilder {
+                    name: segment.to_string(),
+                    ..Default::default()
+                });
+                node.files.insert(file);
+                if depth == segments.len() - 1 {
+                    node.count += 1;
+                }
+                level = &mut node.children;
+            }
+        }
+    }
+
+    roots.into_values().map(|node| node.build("")).collect()
+}
+
+fn files_for_tag(files: &TagIndex, tag: &str, include_nested: bool) -> Result<Vec<TaggedFile>, TagError> {
+    let wanted = normalize_tag(tag).ok_or_else(|| TagError::Invalid(tag.to_string()))?;
+    let mut tagged = Vec::new();
+
+    for (path, tags) in sorted_files(files) {
+        let mut tags: Vec<String> = tags
+            .into_iter()
+            .filter(|t| {
+                if include_nested {
+                    is_within(t, &wanted)
+                } else {
+                    t.eq_ignore_ascii_case(&wanted)
+                }
+            })
+            .collect();
+        tags.sort();
+        tags.dedup();
+
+        if !tags.is_empty() {
+            tagged.push(TaggedFile {
+                path: path.display().to_string(),
+                tags,
+            });
+        }
+    }
+
+    Ok(tagged)
+}
+
+fn rename_in_files(files: &TagIndex, old_tag: &str, new_tag: &str) -> Result<TagRenameResult, TagError> {
+    let old = normalize_tag(old_tag).ok_or_else(|| TagError::Invalid(old_tag.to_string()))?;
+    let new = normalize_tag(new_tag).ok_or_else(|| TagError::Invalid(new_tag.to_string()))?;
+    // Keep whatever was nested under the old tag
+    let renamed = |tag: &str| format!("{}{}", new, tag.chars().skip(old.chars().count()).collect::<String>());
+
+    let mut edited = Vec::new();
+    let mut replacements = 0;
+
+    let tagged = sorted_files(files)
+        .into_iter()
+        .filter(|(_, tags)| tags.iter().any(|t| is_within(t, &old)));
+
+    for (path, _) in tagged {
+        // The index only knows which files to open; ranges come from the file itself
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let tags = document_tags(&content);
+        let mut changed = false;
+
+        let mut body = content.clone();
+        let mut matches: Vec<&BodyTag> = tags.body.iter().filter(|t| is_within(&t.tag, &old)).collect();
+        matches.sort_by_key(|t| std::cmp::Reverse(t.range.start));
+        for found in &matches {
+            body.replace_range(found.range.clone(), &renamed(&found.tag));
+        }
+        if !matches.is_empty() {
+            write_atomic(&path, body.as_bytes())?;
+            replacements += matches.len();
+            changed = true;
+        }
+
+        if tags.front_matter.iter().any(|t| is_within(t, &old)) {
+            let data = parse_front_matter(&body)?.map(|fm| fm.data).unwrap_or_default();
+            let (value, count) = match data.get("tags") {
+                Some(Value::Array(items)) => {
+                    let mut count = 0;
+                    let items = items
+                        .iter()
+                        .map(|item| match item.as_str().and_then(normalize_tag) {
+                            Some(tag) if is_within(&tag, &old) => {
+                                count += 1;
+                                Value::String(renamed(&tag))
+                            }
+                            _ => item.clone(),
+                        })
+                        .collect();
+                    (Value::Array(items), count)
+                }
+                // A string of tags becomes a list
+                _ => {
+                    let list = front_matter_tags(&data);
+                    let count = list.iter().filter(|t| is_within(t, &old)).count();
+                    let items = list
+                        .into_iter()
+                        .map(|t| Value::String(if is_within(&t, &old) { renamed(&t) } else { t }))
+                        .collect();
+                    (Value::Array(items), count)
+                }
+            };
+
+            let mut updates = Map::new();
+            updates.insert("tags".to_string(), value);
+            update_front_matter(&path.display().to_string(), updates)?;
+            replacements += count;
+            changed = true;
+        }
+
+        if changed {
+            // Don't wait for the watcher, so the next query sees the new tags
+            index_file(files, &path);
+            edited.push(path.display().to_string());
+        }
+    }
+
+    Ok(TagRenameResult { edited, replacements })
+}
+
+/// Get every tag in a workspace as a tree of nested tags, with file counts
+#[tauri::command]
+pub async fn get_tags(
+    root: String,
+    state: tauri::State<'_, Arc<Mutex<TagState>>>,
+    watchers: tauri::State<'_, Arc<Mutex<WorkspaceWatchers>>>,
+) -> Result<Vec<TagNode>, TagError> {
+    let (state, watchers) = (state.inner().clone(), watchers.inner().clone());
+    run_blocking(move || Ok(tag_tree(&workspace_index(&root, &state, &watchers)?))).await
+}
+
+/// Get the files tagged with a tag, or with any tag nested under it
+#[tauri::command]
+pub async fn get_files_for_tag(
+    root: String,
+    tag: String,
+    include_nested: Option<bool>,
+    state: tauri::State<'_, Arc<Mutex<TagState>>>,
+    watchers: tauri::State<'_, Arc<Mutex<WorkspaceWatchers>>>,
+) -> Result<Vec<TaggedFile>, TagError> {
+    let (state, watchers) = (state.inner().clone(), watchers.inner().clone());
+    run_blocking(move || {
+        let files = workspace_index(&root, &state, &watchers)?;
+        files_for_tag(&files, &tag, include_nested.unwrap_or(true))
+    })
+    .await
+}
+
+/// Rename a tag and the tags nested under it in every file, both inline
+/// and in front matter
+#[tauri::command]
+pub async fn rename_tag(
+    root: String,
+    old_tag: String,
+    new_tag: String,
+    state: tauri::State<'_, Arc<Mutex<TagState>>>,
+    watchers: tauri::State<'_, Arc<Mutex<WorkspaceWatchers>>>,
+) -> Result<TagRenameResult, TagError> {
+    let (state, watchers) = (state.inner().clone(), watchers.inner().clone());
+    run_blocking(move || {
+        let files = workspace_index(&root, &state, &watchers)?;
+        rename_in_files(&files, &old_tag, &new_tag)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_body_and_front_matter_tags() {
+        let content = "---\ntags: [Project/Alpha, '123']\n---\n# #heading\n\nA #todo and a#b, `#code` [#link](x) #2024 #a/b/\n";
+        let tags: Vec<String> = document_tags(content).all().map(str::to_string).collect();
+        assert_eq!(tags, ["todo", "a/b", "Project/Alpha"]);
+    }
+
+    #[test]
+    fn builds_the_tree_and_renames_through_the_index() {
+        let root = std::env::temp_dir().join(format!("ourea-tags-index-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("a.md"), "#project/alpha and #Project notes").unwrap();
+        fs::write(root.join("b.md"), "---\ntags: project/beta, misc\n---\nbody #misc").unwrap();
+
+        let files: TagIndex = Arc::new(Mutex::new(HashMap::new()));
+        for path in markdown_files(&root) {
+            update_path(&files, &path);
+        }
+
+        let tree = tag_tree(&files);
+        assert_eq!(tree.iter().map(|n| n.tag.as_str()).collect::<Vec<_>>(), ["misc", "project"]);
+        assert_eq!((tree[1].count, tree[1].total), (1, 2));
+        let children: Vec<&str> = tree[1].children.iter().map(|n| n.tag.as_str()).collect();
+        assert_eq!(children, ["project/alpha", "project/beta"]);
+
+        assert_eq!(files_for_tag(&files, "#project", true).unwrap().len(), 2);
+        assert_eq!(files_for_tag(&files, "project", false).unwrap().len(), 1);
+        assert!(files_for_tag(&files, "123", true).is_err());
+
+        let result = rename_in_files(&files, "project", "work").unwrap();
+        assert_eq!((result.edited.len(), result.replacements), (2, 3));
+        assert_eq!(fs::read_to_string(root.join("a.md")).unwrap(), "#work/alpha and #work notes");
+        assert!(fs::read_to_string(root.join("b.md")).unwrap().contains("- work/beta"));
+        assert!(files_for_tag(&files, "project", true).unwrap().is_empty());
+        assert_eq!(files_for_tag(&files, "work", true).unwrap().len(), 2);
+
+        fs::remove_file(root.join("b.md")).unwrap();
+        update_path(&files, &root.join("b.md"));
+        assert!(files_for_tag(&files, "misc", true).unwrap().is_empty());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
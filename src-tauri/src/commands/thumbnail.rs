@@ -0,0 +1,297 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::ImageReader;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tauri::Manager;
+
+use super::images::{content_hash, sniff_extension};
+use super::optimize::decode;
+use super::watcher::{watch_all_workspaces, WorkspaceSubscription, WorkspaceWatchers};
+
+/// Folder under the app cache dir holding thumbnails, named by content hash
+const THUMBNAIL_DIR: &str = "thumbnails";
+/// Thumbnails fit in a square of this many pixels
+const THUMBNAIL_SIZE: u32 = 256;
+const THUMBNAIL_QUALITY: f32 = 75.0;
+/// The oldest thumbnails are deleted once the cache grows past this size
+const MAX_CACHE_BYTES: u64 = 100 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageInfo {
+    pub path: String,
+    /// Missing for images that can't be decoded, such as SVG
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// File format, such as `png` or `svg`
+    pub format: String,
+    /// File size in bytes
+    pub size: u64,
+    /// Small WebP preview as a data URL
+    pub thumbnail: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ThumbnailError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("File not found: {0}")]
+    NotFound(String),
+    #[error("{0}")]
+    AppData(String),
+    #[error("Thumbnail task failed: {0}")]
+    Task(String),
+}
+
+impl Serialize for ThumbnailError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+struct CachedImage {
+    hash: String,
+    size: u64,
+    modified: Option<SystemTime>,
+    info: ImageInfo,
+}
+
+type ImageCache = Arc<Mutex<HashMap<PathBuf, CachedImage>>>;
+
+/// Image info by path, dropped when a workspace watcher sees the file change
+pub struct ThumbnailState {
+    images: ImageCache,
+    subscription: Option<WorkspaceSubscription>,
+    /// Whether the thumbnail folder was pruned this session
+    pruned: bool,
+}
+
+impl ThumbnailState {
+    pub fn new() -> Self {
+        Self {
+            images: Arc::new(Mutex::new(HashMap::new())),
+            subscription: None,
+            pruned: false,
+        }
+    }
+}
+
+impl Default for ThumbnailState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Forget a changed file, and delete its thumbnail unless another file
+/// has the same content
+fn invalidate(images: &ImageCache, cache_dir: &Path, path: &Path) {
+    if path.is_dir() {
+        return;
+    }
+
+    let mut images = images.lock().unwrap();
+    let removed: Vec<PathBuf> = images.keys().filter(|p| p.starts_with(path)).cloned().collect();
+
+    for file in removed {
+        let Some(entry) = images.remove(&file) else {
+            continue;
+        };
+        if !images.values().any(|other| other.hash == entry.hash) {
+            let _ = fs::remove_file(cache_dir.join(format!("{}.webp", entry.hash)));
+        }
+    }
+}
+
+/// Delete the oldest thumbnails until the folder fits in `max_bytes`
+fn prune_cache(cache_dir: &Path, max_bytes: u64) {
+    let Ok(entries) = fs::read_dir(cache_dir) else {
+        return;
+    };
+    let mut thumbnails: Vec<(SystemTime, u64, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok().filter(|m| m.is_file())?;
+            Some((metadata.modified().ok()?, metadata.len(), entry.path()))
+        })
+        .collect();
+    thumbnails.sort_by_key(|(modified, _, _)| std::cmp::Reverse(*modified));
+
+    let mut total = 0;
+    for (_, size, path) in thumbnails {
+        total += size;
+        if total > max_bytes {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+fn make_thumbnail(bytes: &[u8]) -> Option<Vec<u8>> {
+    let (mut image, orientation) = decode(bytes).ok()?;
+    image.apply_orientation(orientation);
+
+    let thumbnail = image::DynamicImage::ImageRgba8(image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).to_rgba8());
+    let encoder = webp::Encoder::from_image(&thumbnail).ok()?;
+    Some(encoder.encode(THUMBNAIL_QUALITY).to_vec())
+}
+
+/// Read an image's dimensions and format, creating its thumbnail in
+/// `cache_dir` unless one exists for the same content
+fn read_image_info(path: &Path, cache_dir: &Path) -> Result<(String, ImageInfo), ThumbnailError> {
+    let bytes = fs::read(path)?;
+    let hash = content_hash(&bytes);
+
+    let format = sniff_extension(&bytes)
+        .map(str::to_string)
+        .or_else(|| path.extension().map(|e| e.to_string_lossy().to_lowercase()))
+        .unwrap_or_default();
+    let dimensions = ImageReader::new(Cursor::new(&bytes))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.into_dimensions().ok());
+
+    let thumbnail_path = cache_dir.join(format!("{}.webp", hash));
+    let thumbnail = match fs::read(&thumbnail_path) {
+        Ok(thumbnail) => {
+            // Pruning goes by modification time, so keep used thumbnails recent
+            let _ = fs::File::options()
+                .write(true)
+                .open(&thumbnail_path)
+                .and_then(|file| file.set_modified(SystemTime::now()));
+            Some(thumbnail)
+        }
+        Err(_) => make_thumbnail(&bytes).inspect(|thumbnail| {
+            if fs::create_dir_all(cache_dir).is_ok() {
+                let _ = fs::write(&thumbnail_path, thumbnail);
+            }
+        }),
+    };
+
+    let info = ImageInfo {
+        path: path.display().to_string(),
+        width: dimensions.map(|(width, _)| width),
+        height: dimensions.map(|(_, height)| height),
+        format,
+        size: bytes.len() as u64,
+        thumbnail: thumbnail.map(|data| format!("data:image/webp;base64,{}", STANDARD.encode(data))),
+    };
+
+    Ok((hash, info))
+}
+
+fn image_info(
+    image_path: PathBuf,
+    cache_dir: PathBuf,
+    state: &Mutex<ThumbnailState>,
+    watchers: &Arc<Mutex<WorkspaceWatchers>>,
+) -> Result<ImageInfo, ThumbnailError> {
+    if !image_path.is_file() {
+        return Err(ThumbnailError::NotFound(image_path.display().to_string()));
+    }
+
+    let metadata = fs::metadata(&image_path)?;
+    let modified = metadata.modified().ok();
+
+    let images = {
+        let mut state = state.lock().unwrap();
+
+        // Edits inside open workspaces drop the cached info right away
+        if state.subscription.is_none() {
+            let images = state.images.clone();
+            let watched_cache = cache_dir.clone();
+            state.subscription = Some(watch_all_workspaces(watchers, move |path| {
+                invalidate(&images, &watched_cache, path)
+            }));
+        }
+        if !state.pruned {
+            prune_cache(&cache_dir, MAX_CACHE_BYTES);
+            state.pruned = true;
+        }
+
+        state.images.clone()
+    };
+
+    // Size and modification time catch changes outside workspaces too
+    if let Some(cached) = images.lock().unwrap().get(&image_path) {
+        if cached.size == metadata.len() && cached.modified == modified {
+            return Ok(cached.info.clone());
+        }
+    }
+
+    let (hash, info) = read_image_info(&image_path, &cache_dir)?;
+    images.lock().unwrap().insert(
+        image_path,
+        CachedImage {
+            hash,
+            size: metadata.len(),
+            modified,
+            info: info.clone(),
+        },
+    );
+
+    Ok(info)
+}
+
+/// Get an image's dimensions, format, size and a small thumbnail, cached
+/// by content so previews don't load the full image into the webview
+#[tauri::command]
+pub async fn get_image_info(
+    app: tauri::AppHandle,
+    path: String,
+    state: tauri::State<'_, Arc<Mutex<ThumbnailState>>>,
+    watchers: tauri::State<'_, Arc<Mutex<WorkspaceWatchers>>>,
+) -> Result<ImageInfo, ThumbnailError> {
+    let cache_dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| ThumbnailError::AppData(e.to_string()))?
+        .join(THUMBNAIL_DIR);
+    let (state, watchers) = (state.inner().clone(), watchers.inner().clone());
+
+    // Decoding a large image takes a while, so keep it off the main thread
+    tauri::async_runtime::spawn_blocking(move || image_info(PathBuf::from(path), cache_dir, &state, &watchers))
+        .await
+        .map_err(|e| ThumbnailError::Task(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn prunes_the_oldest_thumbnails() {
+        let dir = std::env::temp_dir().join(format!("ourea-thumbnail-prune-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let now = SystemTime::now();
+        for (name, age) in [("new", 0), ("old", 20), ("middle", 10)] {
+            let path = dir.join(format!("{}.webp", name));
+            fs::write(&path, [0; 100]).unwrap();
+            let file = fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(now - Duration::from_secs(age)).unwrap();
+        }
+
+        prune_cache(&dir, 250);
+        let mut left: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        left.sort();
+        assert_eq!(left, ["middle.webp", "new.webp"]);
+
+        prune_cache(&dir, 1_000);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
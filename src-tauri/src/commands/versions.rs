@@ -0,0 +1,588 @@
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+use super::file::write_atomic;
+use super::images::content_hash;
+use super::settings::read_settings;
+
+/// Folder under app data holding one folder of snapshots per document
+const VERSIONS_DIR: &str = "versions";
+const INDEX_FILE: &str = "index.json";
+/// Unchanged lines shown around each change in a diff
+const DIFF_CONTEXT: usize = 3;
+/// Hex digits of the path hash naming a document's folder
+const KEY_LEN: usize = 16;
+
+const HOUR_MS: u64 = 60 * 60 * 1000;
+const DAY_MS: u64 = 24 * HOUR_MS;
+
+/// How snapshots are taken and thinned out as they age
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VersionHistory {
+    pub enabled: bool,
+    /// Every snapshot is kept for this many hours
+    pub keep_all_hours: u64,
+    /// Then one per hour for this many days
+    pub hourly_days: u64,
+    /// Then one per day for this many days; 0 keeps daily snapshots forever
+    pub daily_days: u64,
+    /// Minimum time between snapshots of unsaved edits
+    pub draft_interval_minutes: u64,
+}
+
+impl Default for VersionHistory {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            keep_all_hours: 24,
+            hourly_days: 7,
+            daily_days: 90,
+            draft_interval_minutes: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionKind {
+    /// The document as saved
+    Save,
+    /// Unsaved edits, taken periodically while the document is dirty
+    Draft,
+    /// The file as it was before a restore replaced it
+    Restore,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionEntry {
+    pub id: String,
+    pub path: String,
+    /// Milliseconds since the Unix epoch
+    pub created: u64,
+    /// Uncompressed size in bytes
+    pub size: u64,
+    pub hash: String,
+    pub kind: VersionKind,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VersionIndex {
+    path: String,
+    /// Oldest first
+    versions: Vec<VersionEntry>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffKind {
+    Equal,
+    Insert,
+    Delete,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    pub kind: DiffKind,
+    pub content: String,
+    /// 1-based line in the older text, absent for inserted lines
+    pub old_line: Option<usize>,
+    /// 1-based line in the newer text, absent for deleted lines
+    pub new_line: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffHunk {
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionDiff {
+    pub hunks: Vec<DiffHunk>,
+    pub additions: usize,
+    pub deletions: usize,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum VersionError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("File not found: {0}")]
+    NotFound(String),
+    #[error("Version not found: {0}")]
+    VersionNotFound(String),
+    #[error("Invalid version index: {0}")]
+    Index(String),
+    #[error("{0}")]
+    AppData(String),
+}
+
+impl Serialize for VersionError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// Version indexes by document key, loaded from disk on first use
+pub struct VersionState {
+    indexes: HashMap<String, VersionIndex>,
+}
+
+impl VersionState {
+    pub fn new() -> Self {
+        Self {
+            indexes: HashMap::new(),
+        }
+    }
+}
+
+impl Default for VersionState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Folder name for a document's snapshots
+fn document_key(path: &Path) -> String {
+    content_hash(path.to_string_lossy().as_bytes())[..KEY_LEN].to_string()
+}
+
+/// Whether a key has the form `document_key` produces, so it can't name
+/// a folder outside the versions root
+fn is_document_key(key: &str) -> bool {
+    key.len() == KEY_LEN && key.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// Split a version id `<key>-<created>` into its parts
+fn parse_id(id: &str) -> Result<(&str, u64), VersionError> {
+    id.split_once('-')
+        .filter(|(key, _)| is_document_key(key))
+        .and_then(|(key, created)| Some((key, created.parse().ok()?)))
+        .ok_or_else(|| VersionError::VersionNotFound(id.to_string()))
+}
+
+fn versions_root(app: &tauri::AppHandle) -> Result<PathBuf, VersionError> {
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| VersionError::AppData(e.to_string()))?
+        .join(VERSIONS_DIR))
+}
+
+fn snapshot_file(dir: &Path, created: u64) -> PathBuf {
+    dir.join(format!("{}.md.gz", created))
+}
+
+fn load_index<'a>(
+    state: &'a mut VersionState,
+    root: &Path,
+    key: &str,
+) -> Result<&'a mut VersionIndex, VersionError> {
+    if !is_document_key(key) {
+        return Err(VersionError::Index(format!("invalid document key {}", key)));
+    }
+
+    if !state.indexes.contains_key(key) {
+        let index = match fs::read_to_string(root.join(key).join(INDEX_FILE)) {
+            Ok(content) => serde_json::from_str(&content).map_err(|e| VersionError::Index(e.to_string()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => VersionIndex::default(),
+            Err(e) => return Err(e.into()),
+        };
+        state.indexes.insert(key.to_string(), index);
+    }
+
+    Ok(state.indexes.get_mut(key).unwrap())
+}
+
+fn save_index(dir: &Path, index: &VersionIndex) -> Result<(), VersionError> {
+    let json = serde_json::to_string_pretty(index).map_err(|e| VersionError::Index(e.to_string()))?;
+    write_atomic(&dir.join(INDEX_FILE), json.as_bytes())?;
+    Ok(())
+}
+
+fn read_snapshot(dir: &Path, created: u64) -> Result<String, VersionError> {
+    let compressed = fs::read(snapshot_file(dir, created))?;
+    let mut content = String::new();
+    GzDecoder::new(compressed.as_slice()).read_to_string(&mut content)?;
+    Ok(content)
+}
+
+/// Snapshots to drop: all are kept for a while, then the newest of each
+/// hour, then the newest of each day
+fn expired(versions: &[VersionEntry], policy: &VersionHistory, now: u64) -> HashSet<u64> {
+    let keep_all = policy.keep_all_hours * HOUR_MS;
+    let hourly = policy.hourly_days * DAY_MS;
+    let daily = policy.daily_days * DAY_MS;
+
+    let mut hours = HashSet::new();
+    let mut days = HashSet::new();
+    let mut expired = HashSet::new();
+
+    for version in versions.iter().rev() {
+        let age = now.saturating_sub(version.created);
+        let keep = if age < keep_all {
+            true
+        } else if age < hourly {
+            hours.insert(version.created / HOUR_MS)
+        } else if policy.daily_days == 0 || age < daily {
+            days.insert(version.created / DAY_MS)
+        } else {
+            false
+        };
+
+        if !keep {
+            expired.insert(version.created);
+        }
+    }
+
+    expired
+}
+
+/// Store a compressed snapshot of a document and apply the retention
+/// policy. Returns `None` when nothing was stored because the content is
+/// unchanged or a draft was taken too recently.
+pub(crate) fn record_version(
+    state: &mut VersionState,
+    root: &Path,
+    path: &Path,
+    content: &str,
+    kind: VersionKind,
+    policy: &VersionHistory,
+) -> Result<Option<VersionEntry>, VersionError> {
+    let key = document_key(path);
+    let dir = root.join(&key);
+    let index = load_index(state, root, &key)?;
+    let hash = content_hash(content.as_bytes());
+    let now = now_ms();
+
+    if let Some(latest) = index.versions.last() {
+        let too_soon = kind == VersionKind::Draft
+            && now.saturating_sub(latest.created) < policy.draft_interval_minutes * 60 * 1000;
+        if latest.hash == hash || too_soon {
+            return Ok(None);
+        }
+    }
+
+    // Ids must be unique even for snapshots taken within the same millisecond
+    let created = index.versions.last().map_or(now, |latest| now.max(latest.created + 1));
+    fs::create_dir_all(&dir)?;
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(content.as_bytes())?;
+    fs::write(snapshot_file(&dir, created), encoder.finish()?)?;
+
+    let entry = VersionEntry {
+        id: format!("{}-{}", key, created),
+        path: path.display().to_string(),
+        created,
+        size: content.len() as u64,
+        hash,
+        kind,
+    };
+    index.path = entry.path.clone();
+    index.versions.push(entry.clone());
+
+    let expired = expired(&index.versions, policy, now);
+    if !expired.is_empty() {
+        index.versions.retain(|version| !expired.contains(&version.created));
+        for created in &expired {
+            let _ = fs::remove_file(snapshot_file(&dir, *created));
+        }
+    }
+
+    save_index(&dir, index)?;
+    Ok(Some(entry))
+}
+
+/// Look up a version and read its content
+fn find_version(
+    state: &mut VersionState,
+    root: &Path,
+    id: &str,
+) -> Result<(VersionEntry, String), VersionError> {
+    let (key, created) = parse_id(id)?;
+    let index = load_index(state, root, key)?;
+    let entry = index
+        .versions
+        .iter()
+        .find(|version| version.created == created)
+        .cloned()
+        .ok_or_else(|| VersionError::VersionNotFound(id.to_string()))?;
+    let content = read_snapshot(&root.join(key), created)?;
+
+    Ok((entry, content))
+}
+
+fn diff(old: &str, new: &str) -> VersionDiff {
+    let text_diff = TextDiff::from_lines(old, new);
+    let mut hunks = Vec::new();
+    let mut additions = 0;
+    let mut deletions = 0;
+
+    for group in text_diff.grouped_ops(DIFF_CONTEXT) {
+        let (Some(first), Some(last)) = (group.first(), group.last()) else {
+            continue;
+        };
+        let old_range = first.old_range().start..last.old_range().end;
+        let new_range = first.new_range().start..last.new_range().end;
+        let mut lines = Vec::new();
+
+        for op in &group {
+            for change in text_diff.iter_changes(op) {
+                let kind = match change.tag() {
+                    ChangeTag::Equal => DiffKind::Equal,
+                    ChangeTag::Insert => {
+                        additions += 1;
+                        DiffKind::Insert
+                    }
+                    ChangeTag::Delete => {
+                        deletions += 1;
+                        DiffKind::Delete
+                    }
+                };
+                lines.push(DiffLine {
+                    kind,
+                    content: change.value().trim_end_matches(['\r', '\n']).to_string(),
+                    old_line: change.old_index().map(|i| i + 1),
+                    new_line: change.new_index().map(|i| i + 1),
+                });
+            }
+        }
+
+        hunks.push(DiffHunk {
+            old_start: old_range.start + 1,
+            old_lines: old_range.len(),
+            new_start: new_range.start + 1,
+            new_lines: new_range.len(),
+            lines,
+        });
+    }
+
+    VersionDiff {
+        hunks,
+        additions,
+        deletions,
+    }
+}
+
+/// Take a snapshot of a document. The editor calls this after each save
+/// with `save`, and periodically with `draft` while there are unsaved
+/// edits. Without `content` the file on disk is read.
+#[tauri::command]
+pub fn snapshot_version(
+    app: tauri::AppHandle,
+    path: String,
+    content: Option<String>,
+    kind: Option<VersionKind>,
+    state: tauri::State<'_, Arc<Mutex<VersionState>>>,
+) -> Result<Option<VersionEntry>, VersionError> {
+    let policy = read_settings(&app).unwrap_or_default().version_history;
+    if !policy.enabled {
+        return Ok(None);
+    }
+
+    let doc_path = Path::new(&path);
+    let content = match content {
+        Some(content) => content,
+        None if doc_path.is_file() => fs::read_to_string(doc_path)?,
+        None => return Err(VersionError::NotFound(path)),
+    };
+
+    let root = versions_root(&app)?;
+    let mut state = state.lock().unwrap();
+    record_version(&mut state, &root, doc_path, &content, kind.unwrap_or(VersionKind::Save), &policy)
+}
+
+/// List the snapshots of a document, newest first
+#[tauri::command]
+pub fn list_versions(
+    app: tauri::AppHandle,
+    path: String,
+    state: tauri::State<'_, Arc<Mutex<VersionState>>>,
+) -> Result<Vec<VersionEntry>, VersionError> {
+    let root = versions_root(&app)?;
+    let key = document_key(Path::new(&path));
+    let mut state = state.lock().unwrap();
+    let index = load_index(&mut state, &root, &key)?;
+
+    Ok(index.versions.iter().rev().cloned().collect())
+}
+
+/// Get the content of a snapshot
+#[tauri::command]
+pub fn get_version(
+    app: tauri::AppHandle,
+    id: String,
+    state: tauri::State<'_, Arc<Mutex<VersionState>>>,
+) -> Result<String, VersionError> {
+    let root = versions_root(&app)?;
+    let mut state = state.lock().unwrap();
+    let (_, content) = find_version(&mut state, &root, &id)?;
+
+    Ok(content)
+}
+
+/// Line diff from snapshot `a` to snapshot `b`, or to the file on disk
+/// when `b` is omitted
+#[tauri::command]
+pub fn diff_versions(
+    app: tauri::AppHandle,
+    a: String,
+    b: Option<String>,
+    state: tauri::State<'_, Arc<Mutex<VersionState>>>,
+) -> Result<VersionDiff, VersionError> {
+    let root = versions_root(&app)?;
+    let mut state = state.lock().unwrap();
+    let (entry, old) = find_version(&mut state, &root, &a)?;
+    let new = match b {
+        Some(b) => find_version(&mut state, &root, &b)?.1,
+        None => fs::read_to_string(&entry.path)?,
+    };
+
+    Ok(diff(&old, &new))
+}
+
+/// Write a snapshot back to its document. The current file is kept as a
+/// `restore` snapshot first, so a restore can itself be undone. Returns
+/// the restored content.
+#[tauri::command]
+pub fn restore_version(
+    app: tauri::AppHandle,
+    id: String,
+    state: tauri::State<'_, Arc<Mutex<VersionState>>>,
+) -> Result<String, VersionError> {
+    let policy = read_settings(&app).unwrap_or_default().version_history;
+    let root = versions_root(&app)?;
+    let mut state = state.lock().unwrap();
+    let (entry, content) = find_version(&mut state, &root, &id)?;
+    let doc_path = Path::new(&entry.path);
+
+    if let Ok(current) = fs::read_to_string(doc_path) {
+        record_version(&mut state, &root, doc_path, &current, VersionKind::Restore, &policy)?;
+    }
+    if let Some(parent) = doc_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_atomic(doc_path, content.as_bytes())?;
+
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(created: u64) -> VersionEntry {
+        VersionEntry {
+            id: format!("0123456789abcdef-{}", created),
+            path: "note.md".to_string(),
+            created,
+            size: 0,
+            hash: String::new(),
+            kind: VersionKind::Save,
+        }
+    }
+
+    #[test]
+    fn thins_out_versions_as_they_age() {
+        let policy = VersionHistory {
+            keep_all_hours: 2,
+            hourly_days: 1,
+            daily_days: 3,
+            ..VersionHistory::default()
+        };
+        let now = 10 * DAY_MS;
+        let ages = [
+            // Recent ones are all kept
+            HOUR_MS / 2,
+            HOUR_MS,
+            // Within the hourly window only the newest of each hour stays
+            5 * HOUR_MS + 10,
+            5 * HOUR_MS + 20,
+            7 * HOUR_MS,
+            // Then the newest of each day
+            2 * DAY_MS + 10,
+            2 * DAY_MS + 20,
+            // And nothing past the daily window
+            4 * DAY_MS,
+        ];
+        let mut versions: Vec<VersionEntry> = ages.iter().map(|age| version(now - age)).collect();
+        versions.sort_by_key(|v| v.created);
+
+        let expired = expired(&versions, &policy, now);
+        let mut expired: Vec<u64> = expired.into_iter().map(|created| now - created).collect();
+        expired.sort();
+        assert_eq!(expired, [5 * HOUR_MS + 20, 2 * DAY_MS + 20, 4 * DAY_MS]);
+
+        let forever = VersionHistory {
+            daily_days: 0,
+            ..policy
+        };
+        let expired = super::expired(&versions, &forever, now);
+        assert_eq!(expired.len(), 2);
+        assert!(!expired.contains(&(now - 4 * DAY_MS)));
+    }
+
+    #[test]
+    fn rejects_ids_outside_the_versions_folder() {
+        assert_eq!(parse_id("0123456789abcdef-42").unwrap(), ("0123456789abcdef", 42));
+        for id in ["../x-1", "0123456789ABCDEF-1", "0123456789abcde-1", "0123456789abcdef0-1", "0123456789abcdef-x"] {
+            assert!(parse_id(id).is_err(), "{}", id);
+        }
+
+        let mut state = VersionState::new();
+        assert!(load_index(&mut state, Path::new("versions"), "..").is_err());
+        assert!(state.indexes.is_empty());
+    }
+
+    #[test]
+    fn records_and_reads_back_snapshots() {
+        let root = std::env::temp_dir().join(format!("ourea-versions-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let mut state = VersionState::new();
+        let policy = VersionHistory::default();
+        let doc = Path::new("/notes/a.md");
+
+        let first = record_version(&mut state, &root, doc, "one\n", VersionKind::Save, &policy).unwrap().unwrap();
+        assert!(record_version(&mut state, &root, doc, "one\n", VersionKind::Save, &policy).unwrap().is_none());
+        let second = record_version(&mut state, &root, doc, "two\n", VersionKind::Save, &policy).unwrap().unwrap();
+        assert!(record_version(&mut state, &root, doc, "three\n", VersionKind::Draft, &policy).unwrap().is_none());
+
+        // A fresh state loads the index written to disk
+        let mut reloaded = VersionState::new();
+        assert_eq!(find_version(&mut reloaded, &root, &first.id).unwrap().1, "one\n");
+        assert_eq!(find_version(&mut reloaded, &root, &second.id).unwrap().1, "two\n");
+        assert!(second.created > first.created);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
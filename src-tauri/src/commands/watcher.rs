@@ -1,10 +1,13 @@
-use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
+use notify::event::{AccessKind, AccessMode};
+use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Weak};
 use tauri::{AppHandle, Emitter};
 
+use super::markdown::WorkspaceIgnore;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileChangeEvent {
     pub path: String,
@@ -46,6 +49,165 @@ impl Default for WatcherState {
     }
 }
 
+type Listener = Arc<dyn Fn(&Path) + Send + Sync>;
+type Listeners = Arc<Mutex<Vec<(u64, Listener)>>>;
+
+struct WorkspaceWatch {
+    listeners: Listeners,
+    // Kept alive for as long as anything listens
+    _watcher: RecommendedWatcher,
+}
+
+/// One recursive watcher per workspace, shared by the caches that follow
+/// its files. Changes are passed on one path at a time, leaving out hidden
+/// and ignored ones, the same as `workspace_walker`.
+#[derive(Default)]
+pub struct WorkspaceWatchers {
+    next_id: u64,
+    workspaces: HashMap<PathBuf, WorkspaceWatch>,
+    /// Listeners for changes in every watched workspace
+    everywhere: Listeners,
+}
+
+impl WorkspaceWatchers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Stops listening when dropped, and the watcher with the last listener
+pub(crate) struct WorkspaceSubscription {
+    watchers: Weak<Mutex<WorkspaceWatchers>>,
+    root: Option<PathBuf>,
+    id: u64,
+}
+
+impl Drop for WorkspaceSubscription {
+    fn drop(&mut self) {
+        let Some(watchers) = self.watchers.upgrade() else {
+            return;
+        };
+        let mut watchers = watchers.lock().unwrap();
+
+        match &self.root {
+            Some(root) => {
+                let unused = watchers.workspaces.get(root).is_some_and(|watch| {
+                    let mut listeners = watch.listeners.lock().unwrap();
+                    listeners.retain(|(id, _)| *id != self.id);
+                    listeners.is_empty()
+                });
+                if unused {
+                    watchers.workspaces.remove(root);
+                }
+            }
+            None => watchers.everywhere.lock().unwrap().retain(|(id, _)| *id != self.id),
+        }
+    }
+}
+
+/// Whether an event can mean a file was created, changed or removed.
+/// Reading a file to index it is an access event too.
+fn is_change(kind: &EventKind) -> bool {
+    match kind {
+        EventKind::Access(AccessKind::Close(AccessMode::Write)) => true,
+        EventKind::Access(_) => false,
+        _ => true,
+    }
+}
+
+fn start_workspace_watch(root: &Path, everywhere: Listeners) -> Result<WorkspaceWatch, WatcherError> {
+    let listeners: Listeners = Arc::new(Mutex::new(Vec::new()));
+    let notified = listeners.clone();
+    let ignore = Mutex::new(WorkspaceIgnore::new(root));
+
+    let mut watcher = RecommendedWatcher::new(
+        move |result: Result<Event, notify::Error>| {
+            let Ok(event) = result else {
+                return;
+            };
+            if !is_change(&event.kind) {
+                return;
+            }
+
+            // Call listeners outside the lock, so they can take their time
+            let listeners: Vec<Listener> = notified
+                .lock()
+                .unwrap()
+                .iter()
+                .chain(everywhere.lock().unwrap().iter())
+                .map(|(_, listener)| listener.clone())
+                .collect();
+
+            for path in event.paths {
+                let ignored = {
+                    let mut ignore = ignore.lock().unwrap();
+                    ignore.reload(&path);
+                    ignore.is_ignored(&path)
+                };
+                if !ignored {
+                    for listener in &listeners {
+                        listener(&path);
+                    }
+                }
+            }
+        },
+        Config::default(),
+    )
+    .map_err(|e| WatcherError::Watch(e.to_string()))?;
+
+    watcher
+        .watch(root, RecursiveMode::Recursive)
+        .map_err(|e| WatcherError::Watch(e.to_string()))?;
+
+    Ok(WorkspaceWatch {
+        listeners,
+        _watcher: watcher,
+    })
+}
+
+/// Call `listener` with every changed path in a workspace, starting its
+/// watcher unless it is already watched
+pub(crate) fn watch_workspace(
+    watchers: &Arc<Mutex<WorkspaceWatchers>>,
+    root: &Path,
+    listener: impl Fn(&Path) + Send + Sync + 'static,
+) -> Result<WorkspaceSubscription, WatcherError> {
+    let mut state = watchers.lock().unwrap();
+    let id = state.next_id;
+    state.next_id += 1;
+
+    if !state.workspaces.contains_key(root) {
+        let watch = start_workspace_watch(root, state.everywhere.clone())?;
+        state.workspaces.insert(root.to_path_buf(), watch);
+    }
+    if let Some(watch) = state.workspaces.get(root) {
+        watch.listeners.lock().unwrap().push((id, Arc::new(listener)));
+    }
+
+    Ok(WorkspaceSubscription {
+        watchers: Arc::downgrade(watchers),
+        root: Some(root.to_path_buf()),
+        id,
+    })
+}
+
+/// Call `listener` with changed paths in every watched workspace
+pub(crate) fn watch_all_workspaces(
+    watchers: &Arc<Mutex<WorkspaceWatchers>>,
+    listener: impl Fn(&Path) + Send + Sync + 'static,
+) -> WorkspaceSubscription {
+    let mut state = watchers.lock().unwrap();
+    let id = state.next_id;
+    state.next_id += 1;
+    state.everywhere.lock().unwrap().push((id, Arc::new(listener)));
+
+    WorkspaceSubscription {
+        watchers: Arc::downgrade(watchers),
+        root: None,
+        id,
+    }
+}
+
 fn event_kind_to_string(kind: &notify::EventKind) -> String {
     match kind {
         notify::EventKind::Create(_) => "create".to_string(),
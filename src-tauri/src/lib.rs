@@ -1,6 +1,15 @@
 mod commands;
 
-use commands::watcher::WatcherState;
+use commands::finder::FinderState;
+use commands::index::IndexState;
+use commands::links::LinkState;
+use commands::recovery::RecoveryState;
+use commands::search::SearchState;
+use commands::session::SessionStore;
+use commands::tags::TagState;
+use commands::thumbnail::ThumbnailState;
+use commands::versions::VersionState;
+use commands::watcher::{WatcherState, WorkspaceWatchers};
 use std::sync::{Arc, Mutex};
 use tauri::menu::{Menu, MenuItem, PredefinedMenuItem, Submenu};
 use tauri::{Emitter, Manager};
@@ -15,6 +24,16 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
         .manage(Arc::new(Mutex::new(WatcherState::new())))
+        .manage(Arc::new(Mutex::new(WorkspaceWatchers::new())))
+        .manage(Arc::new(Mutex::new(SearchState::new())))
+        .manage(Arc::new(Mutex::new(IndexState::new())))
+        .manage(Arc::new(Mutex::new(FinderState::new())))
+        .manage(Arc::new(Mutex::new(LinkState::new())))
+        .manage(Arc::new(Mutex::new(TagState::new())))
+        .manage(Arc::new(Mutex::new(ThumbnailState::new())))
+        .manage(Arc::new(Mutex::new(VersionState::new())))
+        .manage(Arc::new(Mutex::new(RecoveryState::new())))
+        .manage(Arc::new(Mutex::new(SessionStore::new())))
         .setup(|app| {
             // Initialize logging in debug mode
             if cfg!(debug_assertions) {
@@ -47,12 +66,15 @@ pub fn run() {
             let save_file = MenuItem::with_id(handle, "save_file", "Save", true, Some("CmdOrCtrl+S"))?;
             let save_as = MenuItem::with_id(handle, "save_as", "Save As...", true, Some("CmdOrCtrl+Shift+S"))?;
             let close_tab = MenuItem::with_id(handle, "close_tab", "Close Tab", true, Some("CmdOrCtrl+W"))?;
+            let open_recent = commands::menu::build_open_recent(handle)?;
+            let import_document = MenuItem::with_id(handle, "import_document", "Import...", true, None::<&str>)?;
 
-            let file_menu = Submenu::with_items(
+            let file_menu = Submenu::with_id_and_items(
                 handle,
+                commands::menu::FILE_MENU_ID,
                 "File",
                 true,
-                &[&new_file, &open_file, &PredefinedMenuItem::separator(handle)?, &save_file, &save_as, &PredefinedMenuItem::separator(handle)?, &close_tab],
+                &[&new_file, &open_file, &open_recent, &import_document, &PredefinedMenuItem::separator(handle)?, &save_file, &save_as, &PredefinedMenuItem::separator(handle)?, &close_tab],
             )?;
 
             // Edit menu
@@ -113,6 +135,9 @@ pub fn run() {
             )?;
 
             app.set_menu(menu)?;
+            if let Err(e) = commands::session::init_recent_menu(handle) {
+                log::warn!("Failed to load recent items: {}", e);
+            }
 
             // Get main window and configure it
             if let Some(window) = app.get_webview_window("main") {
@@ -124,7 +149,11 @@ pub fn run() {
             Ok(())
         })
         .on_menu_event(|app, event| {
-            // Handle menu events by emitting them to the frontend
+            if commands::menu::handle_menu_event(app, event.id().0.as_str()) {
+                return;
+            }
+
+            // Handle other menu events by emitting them to the frontend
             if let Some(window) = app.get_webview_window("main") {
                 let _ = window.emit("menu-event", event.id().0.as_str());
             }
@@ -150,6 +179,64 @@ pub fn run() {
             commands::settings::load_settings,
             commands::settings::save_settings,
             commands::settings::get_settings_file_path,
+            commands::export::export_latex,
+            commands::export::export_slides,
+            commands::import::import_document,
+            commands::outline::get_outline,
+            commands::outline::get_workspace_outline,
+            commands::stats::document_stats,
+            commands::stats::folder_stats,
+            commands::frontmatter::read_front_matter,
+            commands::frontmatter::read_front_matter_batch,
+            commands::frontmatter::update_front_matter,
+            commands::frontmatter::validate_front_matter,
+            commands::search::search_workspace,
+            commands::search::cancel_search,
+            commands::replace::replace_in_workspace,
+            commands::replace::undo_replace,
+            commands::index::open_index,
+            commands::index::query_index,
+            commands::index::close_index,
+            commands::finder::set_finder_root,
+            commands::finder::fuzzy_find_files,
+            commands::links::open_link_index,
+            commands::links::get_backlinks,
+            commands::links::get_outgoing_links,
+            commands::links::resolve_link,
+            commands::links::get_unlinked_mentions,
+            commands::linkcheck::check_links,
+            commands::lint::lint_markdown,
+            commands::relink::move_path,
+            commands::tags::get_tags,
+            commands::tags::get_files_for_tag,
+            commands::tags::rename_tag,
+            commands::images::import_image,
+            commands::images::import_image_data,
+            commands::assets::audit_assets,
+            commands::assets::trash_orphaned_assets,
+            commands::localize::localize_remote_images,
+            commands::thumbnail::get_image_info,
+            commands::clipboard::paste_clipboard_images,
+            commands::versions::snapshot_version,
+            commands::versions::list_versions,
+            commands::versions::get_version,
+            commands::versions::diff_versions,
+            commands::versions::restore_version,
+            commands::recovery::journal_tab,
+            commands::recovery::journal_close_tab,
+            commands::recovery::journal_active_tab,
+            commands::recovery::list_recoverable_sessions,
+            commands::recovery::restore_session,
+            commands::recovery::discard_session,
+            commands::session::get_recent,
+            commands::session::add_recent,
+            commands::session::pin_recent,
+            commands::session::remove_recent,
+            commands::session::clear_recent,
+            commands::session::save_workspace_session,
+            commands::session::load_workspace_session,
+            commands::session::get_last_workspace,
+            commands::session::record_closed_tab,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -1,10 +1,17 @@
 import { ref } from "vue";
 import { open, save, message } from "@tauri-apps/plugin-dialog";
 import { readTextFile, writeTextFile } from "@tauri-apps/plugin-fs";
+import { invoke } from "@tauri-apps/api/core";
 import { useTabsStore } from "@/stores/tabs";
 import { useFileStore } from "@/stores/file";
 import { useCloseConfirm } from "./useCloseConfirm";
 
+interface ImportedDocument {
+  title: string;
+  content: string;
+  images: string[];
+}
+
 export function useFile() {
   const tabsStore = useTabsStore();
   const fileStore = useFileStore();
@@ -16,6 +23,13 @@ export function useFile() {
     return filePath.split("/").pop() || filePath.split("\\").pop() || filePath;
   }
 
+  // Keep a version history snapshot of what was just saved
+  function snapshotVersion(path: string, content: string) {
+    invoke("snapshot_version", { path, content, kind: "save" }).catch((error) => {
+      console.error("Failed to snapshot version:", error);
+    });
+  }
+
   // Create a new file tab
   function newFile() {
     tabsStore.createTab({
@@ -82,6 +96,42 @@ export function useFile() {
     }
   }
 
+  // Convert an HTML, Word, OpenDocument or RTF file into a new unsaved tab
+  async function importDocument() {
+    try {
+      const selected = await open({
+        multiple: false,
+        directory: false,
+        filters: [
+          {
+            name: "Documents",
+            extensions: ["html", "htm", "xhtml", "docx", "odt", "rtf"],
+          },
+        ],
+      });
+
+      if (!selected) return;
+      isLoading.value = true;
+
+      const imported = await invoke<ImportedDocument>("import_document", { path: selected as string });
+      tabsStore.createTab({
+        fileName: `${imported.title}.md`,
+        content: imported.content,
+        isNew: true,
+        isDirty: true,
+      });
+
+      isLoading.value = false;
+    } catch (error) {
+      isLoading.value = false;
+      console.error("Failed to import document:", error);
+      await message(`Failed to import document: ${error}`, {
+        title: "Error",
+        kind: "error",
+      });
+    }
+  }
+
   // Save current tab
   async function saveFile(): Promise<boolean> {
     const activeTab = tabsStore.activeTab;
@@ -95,6 +145,7 @@ export function useFile() {
       isLoading.value = true;
 
       await writeTextFile(activeTab.filePath, activeTab.content);
+      snapshotVersion(activeTab.filePath, activeTab.content);
       tabsStore.markActiveTabAsSaved();
 
       isLoading.value = false;
@@ -131,6 +182,7 @@ export function useFile() {
       isLoading.value = true;
 
       await writeTextFile(path, activeTab.content);
+      snapshotVersion(path, activeTab.content);
 
       const fileName = getFileName(path);
       tabsStore.markActiveTabAsSaved(path, fileName);
@@ -175,6 +227,11 @@ export function useFile() {
       // If result is "dont-save", proceed to close without saving
     }
 
+    if (tab.filePath) {
+      invoke("record_closed_tab", { path: tab.filePath }).catch((error) => {
+        console.error("Failed to record closed tab:", error);
+      });
+    }
     tabsStore.closeTab(tabId);
     return true;
   }
@@ -189,6 +246,7 @@ export function useFile() {
     isLoading,
     newFile,
     openFile,
+    importDocument,
     saveFile,
     saveFileAs,
     closeTab,
@@ -1,15 +1,24 @@
 import { onMounted, onUnmounted } from "vue";
 import { listen, UnlistenFn } from "@tauri-apps/api/event";
 import { useSettingsStore } from "@/stores/settings";
+import { useFileStore } from "@/stores/file";
+import { useWorkspaceStore } from "@/stores/workspace";
 import { useFile } from "./useFile";
 import { useGlobalSearch } from "./useSearch";
 
+interface OpenPath {
+  path: string;
+  kind: "file" | "folder";
+}
+
 export function useMenuEvents() {
   const settingsStore = useSettingsStore();
-  const { newFile, openFile, saveFile, saveFileAs, closeActiveTab } = useFile();
+  const fileStore = useFileStore();
+  const workspaceStore = useWorkspaceStore();
+  const { newFile, openFile, importDocument, saveFile, saveFileAs, closeActiveTab } = useFile();
   const { openSearch, openSearchReplace } = useGlobalSearch();
 
-  let unlistenMenu: UnlistenFn | null = null;
+  let unlisteners: UnlistenFn[] = [];
 
   async function handleMenuEvent(menuId: string) {
     switch (menuId) {
@@ -22,6 +31,9 @@ export function useMenuEvents() {
       case "open_file":
         await openFile();
         break;
+      case "import_document":
+        await importDocument();
+        break;
       case "save_file":
         await saveFile();
         break;
@@ -51,17 +63,32 @@ export function useMenuEvents() {
     }
   }
 
+  // Open Recent and Reopen Closed Tab items
+  async function handleOpenPath({ path, kind }: OpenPath) {
+    if (kind === "folder") {
+      await workspaceStore.loadWorkspace(path);
+    } else {
+      await openFile(path);
+    }
+  }
+
   onMounted(async () => {
-    unlistenMenu = await listen<string>("menu-event", (event) => {
-      handleMenuEvent(event.payload);
-    });
+    unlisteners = await Promise.all([
+      listen<string>("menu-event", (event) => {
+        handleMenuEvent(event.payload);
+      }),
+      listen<OpenPath>("open-path", (event) => {
+        handleOpenPath(event.payload);
+      }),
+      listen("recent-changed", () => {
+        fileStore.loadRecent();
+      }),
+    ]);
   });
 
   onUnmounted(() => {
-    if (unlistenMenu) {
-      unlistenMenu();
-      unlistenMenu = null;
-    }
+    unlisteners.forEach((unlisten) => unlisten());
+    unlisteners = [];
   });
 
   return {
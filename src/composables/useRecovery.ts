@@ -1,104 +1,110 @@
-import { ref, onUnmounted } from "vue";
+import { ref, watch, onUnmounted, type WatchStopHandle } from "vue";
 import { invoke } from "@tauri-apps/api/core";
-import { appDataDir } from "@tauri-apps/api/path";
 import { useTabsStore } from "@/stores/tabs";
 
-const RECOVERY_INTERVAL = 30000; // 30 seconds
-const RECOVERY_FILE_NAME = "recovery.json";
+const JOURNAL_DELAY = 1000; // 1 second after the last edit
+const DRAFT_INTERVAL = 30000; // 30 seconds
+
+interface RecoveryTab {
+  id: string;
+  fileName: string;
+  filePath: string | null;
+  content: string;
+  isDirty: boolean;
+  fileType?: "markdown" | "text";
+  extension?: string | null;
+}
 
-interface RecoveryData {
-  timestamp: number;
+interface RecoverableSession {
+  id: string;
+  started: number;
+  updated: number;
   tabs: {
     id: string;
     fileName: string;
     filePath: string | null;
-    content: string;
-    isDirty: boolean;
-    fileType?: "markdown" | "text";
-    extension?: string | null;
+    size: number;
   }[];
   activeTabId: string | null;
 }
 
+interface RecoveredSession {
+  id: string;
+  tabs: RecoveryTab[];
+  activeTabId: string | null;
+}
+
 export function useRecovery() {
   const tabsStore = useTabsStore();
   const hasRecoveryData = ref(false);
-  const recoveryData = ref<RecoveryData | null>(null);
-  let recoveryInterval: number | null = null;
-  let recoveryFilePath: string | null = null;
-
-  async function getRecoveryFilePath(): Promise<string> {
-    if (recoveryFilePath) return recoveryFilePath;
-
-    const dataDir = await appDataDir();
-    recoveryFilePath = `${dataDir}${RECOVERY_FILE_NAME}`;
-    return recoveryFilePath;
-  }
-
+  const recoveryData = ref<RecoverableSession[]>([]);
+  let journalTimer: number | null = null;
+  let draftInterval: number | null = null;
+  let stopWatching: WatchStopHandle[] = [];
+  // Revision last journaled for each tab, to only write tabs that changed
+  const journaled = new Map<string, number>();
+
+  // Write the tabs that changed since the last write, and the tabs closed since
   async function saveRecoveryData(): Promise<void> {
-    const tabs = tabsStore.tabs;
-
-    // Only save if there are dirty tabs
-    const hasDirtyTabs = tabs.some((tab) => tab.isDirty);
-    if (!hasDirtyTabs) return;
-
-    const data: RecoveryData = {
-      timestamp: Date.now(),
-      tabs: tabs.map((tab) => ({
-        id: tab.id,
-        fileName: tab.fileName,
-        filePath: tab.filePath,
-        content: tab.content,
-        isDirty: tab.isDirty,
-        fileType: tab.fileType,
-        extension: tab.extension,
-      })),
-      activeTabId: tabsStore.activeTabId,
-    };
+    if (journalTimer) {
+      window.clearTimeout(journalTimer);
+      journalTimer = null;
+    }
+
+    const openIds = new Set(tabsStore.tabs.map((tab) => tab.id));
 
     try {
-      const filePath = await getRecoveryFilePath();
-      await invoke("write_file", {
-        path: filePath,
-        content: JSON.stringify(data, null, 2),
-      });
+      for (const tab of tabsStore.tabs) {
+        if (journaled.get(tab.id) === tab.revision) continue;
+        // A clean tab that was never journaled has nothing to recover
+        if (!tab.isDirty && !journaled.has(tab.id)) continue;
+
+        const recoveryTab: RecoveryTab = {
+          id: tab.id,
+          fileName: tab.fileName,
+          filePath: tab.filePath,
+          content: tab.isDirty ? tab.content : "",
+          isDirty: tab.isDirty,
+          fileType: tab.fileType,
+          extension: tab.extension,
+        };
+        await invoke("journal_tab", { tab: recoveryTab });
+        journaled.set(tab.id, tab.revision);
+      }
+
+      for (const id of Array.from(journaled.keys())) {
+        if (!openIds.has(id)) {
+          await invoke("journal_close_tab", { tabId: id });
+          journaled.delete(id);
+        }
+      }
     } catch (error) {
       console.error("Failed to save recovery data:", error);
     }
   }
 
-  async function checkRecoveryData(): Promise<boolean> {
-    try {
-      const filePath = await getRecoveryFilePath();
-      const exists = await invoke<boolean>("file_exists", { path: filePath });
-
-      if (!exists) {
-        hasRecoveryData.value = false;
-        return false;
-      }
-
-      const content = await invoke<string>("read_file", { path: filePath });
-      const data = JSON.parse(content) as RecoveryData;
-
-      // Check if data is not too old (24 hours)
-      const maxAge = 24 * 60 * 60 * 1000;
-      if (Date.now() - data.timestamp > maxAge) {
-        await clearRecoveryData();
-        hasRecoveryData.value = false;
-        return false;
-      }
+  function scheduleJournal(): void {
+    if (journalTimer) window.clearTimeout(journalTimer);
+    journalTimer = window.setTimeout(saveRecoveryData, JOURNAL_DELAY);
+  }
 
-      // Check if there are dirty tabs to recover
-      const hasDirtyTabs = data.tabs.some((tab) => tab.isDirty);
-      if (!hasDirtyTabs) {
-        await clearRecoveryData();
-        hasRecoveryData.value = false;
-        return false;
+  // Unsaved edits of saved documents also go into their version history;
+  // the backend skips drafts taken too recently
+  function snapshotDrafts(): void {
+    for (const tab of tabsStore.tabs) {
+      if (tab.isDirty && tab.filePath) {
+        invoke("snapshot_version", { path: tab.filePath, content: tab.content, kind: "draft" }).catch((error) => {
+          console.error("Failed to snapshot draft:", error);
+        });
       }
+    }
+  }
 
-      recoveryData.value = data;
-      hasRecoveryData.value = true;
-      return true;
+  async function checkRecoveryData(): Promise<boolean> {
+    try {
+      recoveryData.value = await invoke<RecoverableSession[]>("list_recoverable_sessions");
+      hasRecoveryData.value = recoveryData.value.length > 0;
+      return hasRecoveryData.value;
     } catch (error) {
       console.error("Failed to check recovery data:", error);
       hasRecoveryData.value = false;
@@ -107,57 +113,85 @@ export function useRecovery() {
   }
 
   async function recoverTabs(): Promise<void> {
-    if (!recoveryData.value) return;
-
-    const data = recoveryData.value;
-
-    for (const tabData of data.tabs) {
-      // Only recover dirty tabs (unsaved changes)
-      if (tabData.isDirty) {
-        tabsStore.createTab({
-          fileName: tabData.fileName,
-          filePath: tabData.filePath,
-          content: tabData.content,
-          isDirty: true,
-          isNew: !tabData.filePath,
-          fileType: tabData.fileType,
-        });
+    for (const session of recoveryData.value) {
+      try {
+        const recovered = await invoke<RecoveredSession>("restore_session", { id: session.id });
+        let activeId: string | null = null;
+
+        for (const tabData of recovered.tabs) {
+          // The file may already be open again from the restored session
+          const existing = tabData.filePath ? tabsStore.findTabByPath(tabData.filePath) : undefined;
+          if (existing) {
+            tabsStore.updateTabContent(existing.id, tabData.content);
+            if (tabData.id === recovered.activeTabId) activeId = existing.id;
+            continue;
+          }
+
+          const tab = tabsStore.createTab({
+            fileName: tabData.fileName,
+            filePath: tabData.filePath,
+            content: tabData.content,
+            isDirty: true,
+            isNew: !tabData.filePath,
+            fileType: tabData.fileType,
+          });
+          if (tabData.id === recovered.activeTabId) activeId = tab.id;
+        }
+
+        if (activeId) tabsStore.setActiveTab(activeId);
+      } catch (error) {
+        console.error("Failed to recover session:", error);
       }
     }
 
-    await clearRecoveryData();
-    recoveryData.value = null;
+    recoveryData.value = [];
     hasRecoveryData.value = false;
   }
 
   async function clearRecoveryData(): Promise<void> {
     try {
-      const filePath = await getRecoveryFilePath();
-      const exists = await invoke<boolean>("file_exists", { path: filePath });
-      if (exists) {
-        await invoke("delete_path", { path: filePath });
-      }
+      await invoke("discard_session", { id: null });
     } catch (error) {
       console.error("Failed to clear recovery data:", error);
     }
-    recoveryData.value = null;
+    recoveryData.value = [];
     hasRecoveryData.value = false;
   }
 
   function startAutoRecovery(): void {
-    if (recoveryInterval) return;
-
-    recoveryInterval = window.setInterval(saveRecoveryData, RECOVERY_INTERVAL);
+    if (draftInterval) return;
+
+    stopWatching = [
+      // Revisions are cheap to compare, unlike the content of every tab
+      watch(() => tabsStore.tabs.map((tab) => `${tab.id}:${tab.revision}`).join(), scheduleJournal),
+      watch(
+        () => tabsStore.activeTabId,
+        (tabId) => {
+          invoke("journal_active_tab", { tabId }).catch((error) => {
+            console.error("Failed to save recovery data:", error);
+          });
+        },
+        { immediate: true }
+      ),
+    ];
+    saveRecoveryData();
+    draftInterval = window.setInterval(snapshotDrafts, DRAFT_INTERVAL);
 
     // Also save on visibility change (user leaving the page)
     document.addEventListener("visibilitychange", handleVisibilityChange);
   }
 
   function stopAutoRecovery(): void {
-    if (recoveryInterval) {
-      window.clearInterval(recoveryInterval);
-      recoveryInterval = null;
+    if (draftInterval) {
+      window.clearInterval(draftInterval);
+      draftInterval = null;
+    }
+    if (journalTimer) {
+      window.clearTimeout(journalTimer);
+      journalTimer = null;
     }
+    stopWatching.forEach((stop) => stop());
+    stopWatching = [];
     document.removeEventListener("visibilitychange", handleVisibilityChange);
   }
 
@@ -0,0 +1,176 @@
+import { watch, onUnmounted, type WatchStopHandle } from "vue";
+import { invoke } from "@tauri-apps/api/core";
+import { useTabsStore } from "@/stores/tabs";
+import { useWorkspaceStore } from "@/stores/workspace";
+import { useEditorStore, type CursorPosition } from "@/stores/editor";
+import { useFileStore } from "@/stores/file";
+import { useFile } from "./useFile";
+
+const SAVE_DELAY = 1000; // 1 second after the last change
+const EDITOR_SCROLLER = ".editor-wrapper";
+
+interface TabSession {
+  path: string;
+  cursor: CursorPosition | null;
+  scrollTop: number | null;
+}
+
+interface WorkspaceSession {
+  tabs: TabSession[];
+  activePath: string | null;
+  expandedPaths: string[];
+}
+
+export function useSession() {
+  const tabsStore = useTabsStore();
+  const workspaceStore = useWorkspaceStore();
+  const editorStore = useEditorStore();
+  const fileStore = useFileStore();
+  const { openFile } = useFile();
+
+  // Last known cursor and scroll offset of each open file
+  const cursors = new Map<string, CursorPosition>();
+  const scrollOffsets = new Map<string, number>();
+  let saveTimer: number | null = null;
+  let stopWatching: WatchStopHandle[] = [];
+
+  function currentSession(): WorkspaceSession {
+    return {
+      tabs: tabsStore.tabs
+        .filter((tab) => tab.filePath)
+        .map((tab) => ({
+          path: tab.filePath!,
+          cursor: cursors.get(tab.filePath!) ?? null,
+          scrollTop: scrollOffsets.get(tab.filePath!) ?? null,
+        })),
+      activePath: tabsStore.activeTab?.filePath ?? null,
+      expandedPaths: Array.from(workspaceStore.expandedPaths),
+    };
+  }
+
+  async function saveSession(): Promise<void> {
+    if (saveTimer) {
+      window.clearTimeout(saveTimer);
+      saveTimer = null;
+    }
+
+    try {
+      await invoke("save_workspace_session", {
+        workspace: workspaceStore.rootPath,
+        session: currentSession(),
+      });
+    } catch (error) {
+      console.error("Failed to save session:", error);
+    }
+  }
+
+  function scheduleSave(): void {
+    if (saveTimer) window.clearTimeout(saveTimer);
+    saveTimer = window.setTimeout(saveSession, SAVE_DELAY);
+  }
+
+  function handleScroll(event: Event): void {
+    const target = event.target as HTMLElement;
+    const path = tabsStore.activeTab?.filePath;
+    if (!path || !(target instanceof HTMLElement) || !target.matches(EDITOR_SCROLLER)) return;
+
+    scrollOffsets.set(path, target.scrollTop);
+    scheduleSave();
+  }
+
+  // Reopen the last workspace and its tabs
+  async function restoreSession(): Promise<void> {
+    await fileStore.loadRecent();
+
+    try {
+      const workspace = await invoke<string | null>("get_last_workspace");
+      if (workspace) {
+        await workspaceStore.loadWorkspace(workspace);
+      }
+
+      const session = await invoke<WorkspaceSession | null>("load_workspace_session", { workspace });
+      if (!session) return;
+
+      for (const tab of session.tabs) {
+        if (tab.cursor) cursors.set(tab.path, tab.cursor);
+        if (tab.scrollTop !== null) scrollOffsets.set(tab.path, tab.scrollTop);
+        if (!tabsStore.findTabByPath(tab.path)) {
+          await openFile(tab.path);
+        }
+      }
+
+      const active = session.activePath ? tabsStore.findTabByPath(session.activePath) : undefined;
+      if (active) tabsStore.setActiveTab(active.id);
+
+      if (workspace && session.expandedPaths.length > 0) {
+        workspaceStore.expandedPaths = new Set(session.expandedPaths);
+      }
+    } catch (error) {
+      console.error("Failed to restore session:", error);
+    }
+  }
+
+  function startSessionTracking(): void {
+    if (stopWatching.length > 0) return;
+
+    stopWatching = [
+      watch(
+        () => [
+          tabsStore.tabs.map((tab) => tab.filePath),
+          tabsStore.activeTabId,
+          workspaceStore.rootPath,
+          workspaceStore.expandedPaths,
+        ],
+        scheduleSave
+      ),
+      watch(
+        () => editorStore.cursorPosition,
+        (position) => {
+          const path = tabsStore.activeTab?.filePath;
+          if (!path) return;
+          cursors.set(path, { ...position });
+          scheduleSave();
+        }
+      ),
+      // Put the editor back where it was when switching to a file
+      watch(
+        () => tabsStore.activeTab?.filePath,
+        (path) => {
+          const scrollTop = path ? scrollOffsets.get(path) : undefined;
+          if (scrollTop === undefined) return;
+          window.setTimeout(() => {
+            const scroller = document.querySelector<HTMLElement>(EDITOR_SCROLLER);
+            if (scroller) scroller.scrollTop = scrollTop;
+          }, 200);
+        },
+        { immediate: true }
+      ),
+      watch(
+        () => workspaceStore.rootPath,
+        (path) => {
+          if (path) fileStore.addToRecentFolders(path);
+        }
+      ),
+    ];
+    document.addEventListener("scroll", handleScroll, { capture: true, passive: true });
+    window.addEventListener("beforeunload", saveSession);
+  }
+
+  function stopSessionTracking(): void {
+    stopWatching.forEach((stop) => stop());
+    stopWatching = [];
+    document.removeEventListener("scroll", handleScroll, { capture: true });
+    window.removeEventListener("beforeunload", saveSession);
+  }
+
+  onUnmounted(() => {
+    stopSessionTracking();
+  });
+
+  return {
+    restoreSession,
+    saveSession,
+    startSessionTracking,
+    stopSessionTracking,
+  };
+}
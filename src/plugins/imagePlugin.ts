@@ -2,11 +2,22 @@ import { $prose } from "@milkdown/kit/utils";
 import { Plugin, PluginKey } from "@milkdown/kit/prose/state";
 import { invoke } from "@tauri-apps/api/core";
 import { save } from "@tauri-apps/plugin-dialog";
-import { join, dirname } from "@tauri-apps/api/path";
 import { useTabsStore } from "@/stores/tabs";
 
 const imagePluginKey = new PluginKey("imagePlugin");
 
+interface ImportedImage {
+  markdownPath: string;
+  path: string;
+  reused: boolean;
+}
+
+interface ClipboardImport {
+  images: (ImportedImage & { alt: string })[];
+  markdown: string;
+  skipped: string[];
+}
+
 export interface ImageUploadResult {
   success: boolean;
   path?: string;
@@ -24,19 +35,18 @@ async function saveImageFromBase64(
     const ext = mimeType.split("/")[1] || "png";
     const filename = `image_${Date.now()}.${ext}`;
 
-    // If we have a current file path, save relative to it
+    // If we have a current file path, let the backend name, dedupe and store it
     if (currentFilePath) {
-      const dir = await dirname(currentFilePath);
-      const assetsDir = await join(dir, "assets");
-      const imagePath = await join(assetsDir, filename);
-
-      // Create assets directory and save image
-      await invoke("save_image", {
-        base64_data: base64Data,
-        path: imagePath,
+      // Sent as the raw request body, with the paths percent-encoded in headers
+      const bytes = Uint8Array.from(atob(base64Data), (c) => c.charCodeAt(0));
+      const image = await invoke<ImportedImage>("import_image_data", bytes, {
+        headers: {
+          "doc-path": encodeURIComponent(currentFilePath),
+          "file-name": encodeURIComponent(filename),
+        },
       });
 
-      return { success: true, path: `./assets/${filename}` };
+      return { success: true, path: image.markdownPath };
     } else {
       // Ask user where to save
       const savePath = await save({
@@ -66,20 +76,13 @@ async function copyImageToLocal(
   currentFilePath: string | null
 ): Promise<ImageUploadResult> {
   try {
-    const ext = sourcePath.split(".").pop() || "png";
-    const filename = `image_${Date.now()}.${ext}`;
-
     if (currentFilePath) {
-      const dir = await dirname(currentFilePath);
-      const assetsDir = await join(dir, "assets");
-      const imagePath = await join(assetsDir, filename);
-
-      await invoke("copy_image", {
-        source: sourcePath,
-        destination: imagePath,
+      const image = await invoke<ImportedImage>("import_image", {
+        docPath: currentFilePath,
+        sourcePath,
       });
 
-      return { success: true, path: `./assets/${filename}` };
+      return { success: true, path: image.markdownPath };
     } else {
       // Copy to a default location or ask user
       return { success: false, error: "Please save the file first" };
@@ -279,24 +282,47 @@ export const imageUploadPlugin = $prose(() => {
 
     props: {
       handlePaste(view, event) {
-        const items = event.clipboardData?.items;
-        if (!items) return false;
+        const data = event.clipboardData;
+        if (!data) return false;
 
-        for (const item of Array.from(items)) {
-          if (item.type.startsWith("image/")) {
-            event.preventDefault();
+        // Copied files, bitmaps and images in copied HTML are read from the
+        // system clipboard by the backend, which sees formats the webview can't
+        const imageItem = Array.from(data.items).find((item) => item.type.startsWith("image/"));
+        const onlyImagesInHtml = /<img\b/i.test(data.getData("text/html")) && !data.getData("text/plain").trim();
+        if (!imageItem && !data.types.includes("Files") && !onlyImagesInHtml) return false;
 
-            const file = item.getAsFile();
-            if (!file) continue;
+        event.preventDefault();
+        // The webview's copy is only readable during the event, so keep it as a fallback
+        const fallback = imageItem?.getAsFile() ?? null;
+        const tabsStore = useTabsStore();
+        const currentPath = tabsStore.activeTab?.filePath || null;
+
+        invoke<ClipboardImport>("paste_clipboard_images", { docPath: currentPath })
+          .then((result) => {
+            const { state, dispatch } = view;
+            const imageType = state.schema.nodes.image;
+            if (!imageType) return;
+
+            let tr = state.tr;
+            let pos = state.selection.from;
+            for (const image of result.images) {
+              const imgNode = imageType.create({ src: image.markdownPath, alt: image.alt || "image" });
+              tr = tr.insert(pos, imgNode);
+              pos += imgNode.nodeSize;
+            }
+            dispatch(tr);
+          })
+          .catch((error) => {
+            if (!fallback) {
+              console.error("Failed to paste images from the clipboard:", error);
+              return;
+            }
 
             // Read file as base64
             const reader = new FileReader();
             reader.onload = async () => {
               const base64 = (reader.result as string).split(",")[1];
-              const tabsStore = useTabsStore();
-              const currentPath = tabsStore.activeTab?.filePath || null;
-
-              const result = await saveImageFromBase64(base64, item.type, currentPath);
+              const result = await saveImageFromBase64(base64, fallback.type, currentPath);
 
               if (result.success && result.path) {
                 // Insert image markdown
@@ -314,13 +340,10 @@ export const imageUploadPlugin = $prose(() => {
                 }
               }
             };
-            reader.readAsDataURL(file);
+            reader.readAsDataURL(fallback);
+          });
 
-            return true;
-          }
-        }
-
-        return false;
+        return true;
       },
 
       handleDrop(view, event) {
@@ -1,16 +1,24 @@
 import { defineStore } from "pinia";
 import { ref, computed } from "vue";
+import { invoke } from "@tauri-apps/api/core";
 
 export interface RecentFile {
   path: string;
   name: string;
   lastOpened: number;
+  pinned: boolean;
+}
+
+interface RecentLists {
+  files: RecentFile[];
+  folders: RecentFile[];
 }
 
 export const useFileStore = defineStore("file", () => {
   // State
   const currentFilePath = ref<string | null>(null);
   const recentFiles = ref<RecentFile[]>([]);
+  const recentFolders = ref<RecentFile[]>([]);
   const isNewFile = ref(true);
   const isDirty = ref(false);
 
@@ -31,35 +39,43 @@ export const useFileStore = defineStore("file", () => {
     }
   }
 
-  function addToRecentFiles(path: string) {
-    const name = path.split(/[/\\]/).pop() || path;
-    const existingIndex = recentFiles.value.findIndex((f) => f.path === path);
-
-    if (existingIndex !== -1) {
-      recentFiles.value.splice(existingIndex, 1);
+  // Recent files and folders are kept by the backend across restarts
+  async function updateRecent(command: string, args: Record<string, unknown> = {}) {
+    try {
+      const recent = await invoke<RecentLists>(command, args);
+      recentFiles.value = recent.files;
+      recentFolders.value = recent.folders;
+    } catch (error) {
+      console.error(`Failed to update recent files (${command}):`, error);
     }
+  }
 
-    recentFiles.value.unshift({
-      path,
-      name,
-      lastOpened: Date.now(),
-    });
+  function loadRecent() {
+    return updateRecent("get_recent");
+  }
 
-    // Keep only last 10 files
-    if (recentFiles.value.length > 10) {
-      recentFiles.value = recentFiles.value.slice(0, 10);
-    }
+  function addToRecentFiles(path: string) {
+    return updateRecent("add_recent", { kind: "file", path });
+  }
+
+  function addToRecentFolders(path: string) {
+    return updateRecent("add_recent", { kind: "folder", path });
+  }
+
+  function pinRecent(kind: "file" | "folder", path: string, pinned: boolean) {
+    return updateRecent("pin_recent", { kind, path, pinned });
   }
 
   function removeRecentFile(path: string) {
-    const index = recentFiles.value.findIndex((f) => f.path === path);
-    if (index !== -1) {
-      recentFiles.value.splice(index, 1);
-    }
+    return updateRecent("remove_recent", { kind: "file", path });
+  }
+
+  function removeRecentFolder(path: string) {
+    return updateRecent("remove_recent", { kind: "folder", path });
   }
 
   function clearRecentFiles() {
-    recentFiles.value = [];
+    return updateRecent("clear_recent", { kind: "file" });
   }
 
   function setDirty(dirty: boolean) {
@@ -76,14 +92,19 @@ export const useFileStore = defineStore("file", () => {
     // State
     currentFilePath,
     recentFiles,
+    recentFolders,
     isNewFile,
     isDirty,
     // Getters
     currentFileName,
     // Actions
     setCurrentFile,
+    loadRecent,
     addToRecentFiles,
+    addToRecentFolders,
+    pinRecent,
     removeRecentFile,
+    removeRecentFolder,
     clearRecentFiles,
     setDirty,
     reset,
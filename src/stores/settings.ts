@@ -61,12 +61,14 @@ export const useSettingsStore = defineStore("settings", () => {
 
   // Image settings
   const imageStorageLocation = ref<"relative" | "assets" | "absolute">("relative");
-  const imageNamingRule = ref<"original" | "timestamp" | "uuid">("timestamp");
+  const imageNamingRule = ref<"original" | "timestamp" | "uuid" | "hash">("timestamp");
   const imageAssetsFolder = ref("assets");
 
   // Loading state
   const isLoading = ref(true);
   let saveTimeout: ReturnType<typeof setTimeout> | null = null;
+  // Backend-only settings (lint rules, image optimization, network) are kept as loaded
+  let backendSettings: Record<string, unknown> = {};
 
   // Actions
   function setThemeMode(newMode: ThemeMode) {
@@ -166,6 +168,7 @@ export const useSettingsStore = defineStore("settings", () => {
     saveTimeout = setTimeout(async () => {
       try {
         const settings: AppSettings = {
+          ...backendSettings,
           themeMode: themeMode.value,
           selectedThemeId: selectedThemeId.value,
           fontSize: fontSize.value,
@@ -199,6 +202,7 @@ export const useSettingsStore = defineStore("settings", () => {
     try {
       isLoading.value = true;
       const settings = await invoke<AppSettings>("load_settings");
+      backendSettings = { ...settings };
 
       // Apply loaded settings
       themeMode.value = settings.themeMode as ThemeMode;
@@ -219,7 +223,7 @@ export const useSettingsStore = defineStore("settings", () => {
       paragraphFocus.value = settings.paragraphFocus;
       paragraphFocusOpacity.value = settings.paragraphFocusOpacity;
       imageStorageLocation.value = settings.imageStorageLocation as "relative" | "assets" | "absolute";
-      imageNamingRule.value = settings.imageNamingRule as "original" | "timestamp" | "uuid";
+      imageNamingRule.value = settings.imageNamingRule as "original" | "timestamp" | "uuid" | "hash";
       imageAssetsFolder.value = settings.imageAssetsFolder;
     } catch (error) {
       console.error("Failed to load settings:", error);
@@ -11,6 +11,7 @@ export interface Tab {
   isNew: boolean;
   fileType: "markdown" | "text"; // markdown = render as markdown, text = render as code block
   extension: string | null; // file extension for syntax highlighting
+  revision: number; // Bumped on every change to content, dirty state or path
 }
 
 export const useTabsStore = defineStore("tabs", () => {
@@ -60,6 +61,7 @@ export const useTabsStore = defineStore("tabs", () => {
       isNew: options.isNew !== undefined ? options.isNew : true,
       fileType,
       extension,
+      revision: 0,
     };
 
     tabs.value.push(newTab);
@@ -99,6 +101,7 @@ export const useTabsStore = defineStore("tabs", () => {
       tab.content = content;
       // Only mark dirty if content differs from original
       tab.isDirty = content !== tab.originalContent;
+      tab.revision++;
     }
   }
 
@@ -116,6 +119,7 @@ export const useTabsStore = defineStore("tabs", () => {
       tab.originalContent = tab.content; // Update original content on save
       if (filePath) tab.filePath = filePath;
       if (fileName) tab.fileName = fileName;
+      tab.revision++;
     }
   }
 
@@ -130,6 +134,7 @@ export const useTabsStore = defineStore("tabs", () => {
     if (tab) {
       tab.content = content;
       tab.isDirty = markDirty;
+      tab.revision++;
     }
   }
 
@@ -137,6 +142,7 @@ export const useTabsStore = defineStore("tabs", () => {
     const tab = tabs.value.find((t) => t.id === tabId);
     if (tab) {
       tab.isDirty = false;
+      tab.revision++;
     }
   }
 